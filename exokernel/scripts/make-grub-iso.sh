@@ -10,6 +10,9 @@ PROJECT_ROOT="$(dirname "$SCRIPT_DIR")"
 ARCH=${1:-x86_64}
 ISO_NAME="exokernel-${ARCH}.iso"
 
+# LibOS 镜像等引导模块：目录中的每个文件作为一个 module2 传给内核
+MODULES_DIR=${MODULES_DIR:-"$PROJECT_ROOT/modules"}
+
 echo "======================================"
 echo "  Creating GRUB Bootable ISO"
 echo "  Architecture: $ARCH"
@@ -63,6 +66,20 @@ mkdir -p "$ISO_ROOT/boot/grub"
 echo "[2/5] Copying kernel..."
 cp "$KERNEL_PATH" "$ISO_ROOT/boot/$KERNEL_NAME"
 
+# 复制模块并生成 module2 行（命令行第一个词是模块名）
+MODULE_LINES=""
+if [ -d "$MODULES_DIR" ]; then
+    mkdir -p "$ISO_ROOT/boot/modules"
+    for module in "$MODULES_DIR"/*; do
+        [ -f "$module" ] || continue
+        name="$(basename "$module")"
+        cp "$module" "$ISO_ROOT/boot/modules/$name"
+        MODULE_LINES="${MODULE_LINES}    module2 /boot/modules/$name $name
+"
+        echo "  + module $name"
+    done
+fi
+
 # 创建GRUB配置
 echo "[3/5] Creating GRUB configuration..."
 cat > "$ISO_ROOT/boot/grub/grub.cfg" << EOF
@@ -75,20 +92,20 @@ set color_highlight=black/light-gray
 
 menuentry "Exokernel ($ARCH)" {
     echo "Loading Exokernel..."
-    multiboot2 /boot/$KERNEL_NAME
-    boot
+    multiboot2 /boot/$KERNEL_NAME loglevel=3
+${MODULE_LINES}    boot
 }
 
 menuentry "Exokernel ($ARCH) - Debug Mode" {
     echo "Loading Exokernel in debug mode..."
-    multiboot2 /boot/$KERNEL_NAME debug
-    boot
+    multiboot2 /boot/$KERNEL_NAME loglevel=4
+${MODULE_LINES}    boot
 }
 
 menuentry "Exokernel ($ARCH) - Safe Mode" {
     echo "Loading Exokernel in safe mode..."
-    multiboot2 /boot/$KERNEL_NAME safe
-    boot
+    multiboot2 /boot/$KERNEL_NAME selftest=none
+${MODULE_LINES}    boot
 }
 
 menuentry "Reboot" {
@@ -28,9 +28,41 @@ impl super::Architecture for AArch64 {
     fn write_serial(byte: u8) {
         uart::write_byte(byte);
     }
+
+    fn cpu_id() -> usize {
+        let mpidr: u64;
+        unsafe { asm!("mrs {}, mpidr_el1", out(reg) mpidr, options(nomem, nostack)); }
+        (mpidr & 0xff) as usize
+    }
+
+    fn timestamp() -> u64 {
+        let cnt: u64;
+        unsafe { asm!("mrs {}, cntvct_el0", out(reg) cnt, options(nomem, nostack)); }
+        cnt
+    }
 }
 
 pub fn halt() { AArch64::halt() }
 pub fn enable_interrupts() { AArch64::enable_interrupts() }
 pub fn disable_interrupts() { AArch64::disable_interrupts() }
 pub fn write_serial(byte: u8) { AArch64::write_serial(byte) }
+pub fn cpu_id() -> usize { AArch64::cpu_id() }
+pub fn timestamp() -> u64 { AArch64::timestamp() }
+
+/// 末级缓存的 (大小, 相联度)；该架构暂不探测，由配置提供
+pub fn llc_geometry() -> Option<(usize, usize)> {
+    None
+}
+
+/// 直接映射覆盖的物理地址上限（MMU 关闭，物理地址直接可访问）
+pub fn direct_map_limit() -> usize {
+    usize::MAX
+}
+
+/// 无需扩展直接映射
+pub unsafe fn extend_direct_map() {}
+
+/// 设备窗口已是不可缓存访问（MMU 关闭时所有访问都按 Device-nGnRnE 进行）
+pub unsafe fn map_uncached(_base: usize, _size: usize) -> bool {
+    true
+}
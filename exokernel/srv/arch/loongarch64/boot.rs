@@ -7,9 +7,11 @@ global_asm!(
     .global _start
 
 _start:
-    // a0 = bootinfo指针
-    la.global $t0, boot_info_ptr
+    // a0 = EFI引导标志，a1 = 命令行，a2 = EFI系统表
+    la.global $t0, boot_args
     st.d $a0, $t0, 0
+    st.d $a1, $t0, 8
+    st.d $a2, $t0, 16
 
     // 设置栈
     la.global $sp, boot_stack_top
@@ -24,7 +26,8 @@ _start:
     b .clear_bss
 .bss_done:
 
-    // 跳转到Rust
+    // 跳转到Rust，参数为引导参数的地址
+    la.global $a0, boot_args
     bl kernel_main
 
 .hang:
@@ -38,15 +41,40 @@ boot_stack_bottom:
 boot_stack_top:
 
     .section .data
-boot_info_ptr:
-    .dword 0
+    .align 3
+boot_args:
+    .dword 0, 0, 0
     "#
 );
 
+/// 固件按 LoongArch 引导协议在 a0-a2 中传入的参数
+#[repr(C)]
+pub struct BootArgs {
+    /// 非零表示经 UEFI 引导
+    pub efi_boot: u64,
+    /// 以 0 结尾的内核命令行（可能为 0）
+    pub cmdline: u64,
+    /// EFI 系统表（QEMU 直接引导时也会构造一个最小系统表）
+    pub system_table: u64,
+}
+
 extern "C" {
-    static boot_info_ptr: u64;
+    static boot_args: BootArgs;
+    static __kernel_vma_start: u8;
+    static __kernel_vma_end: u8;
+}
+
+const KERNEL_LMA: usize = 0x0000_0000_0100_0000;
+
+/// 内核映像的物理范围 [start, end)
+pub fn kernel_phys_range() -> (usize, usize) {
+    unsafe {
+        let start = &__kernel_vma_start as *const u8 as usize;
+        let end = &__kernel_vma_end as *const u8 as usize;
+        (KERNEL_LMA, KERNEL_LMA + (end - start))
+    }
 }
 
 pub unsafe fn get_boot_info() -> *const u8 {
-    boot_info_ptr as *const u8
+    &boot_args as *const BootArgs as *const u8
 }
@@ -35,9 +35,41 @@ impl super::Architecture for LoongArch64 {
     fn write_serial(byte: u8) {
         uart::write_byte(byte);
     }
+
+    fn cpu_id() -> usize {
+        let id: usize;
+        unsafe { asm!("csrrd {}, 0x20", out(reg) id, options(nomem, nostack)); } // CSR_CPUID
+        id & 0x1ff
+    }
+
+    fn timestamp() -> u64 {
+        let t: u64;
+        unsafe { asm!("rdtime.d {}, $zero", out(reg) t, options(nomem, nostack)); }
+        t
+    }
 }
 
 pub fn halt() { LoongArch64::halt() }
 pub fn enable_interrupts() { LoongArch64::enable_interrupts() }
 pub fn disable_interrupts() { LoongArch64::disable_interrupts() }
 pub fn write_serial(byte: u8) { LoongArch64::write_serial(byte) }
+pub fn cpu_id() -> usize { LoongArch64::cpu_id() }
+pub fn timestamp() -> u64 { LoongArch64::timestamp() }
+
+/// 末级缓存的 (大小, 相联度)；该架构暂不探测，由配置提供
+pub fn llc_geometry() -> Option<(usize, usize)> {
+    None
+}
+
+/// 直接映射覆盖的物理地址上限（沿用固件的地址翻译，物理地址直接可访问）
+pub fn direct_map_limit() -> usize {
+    usize::MAX
+}
+
+/// 无需扩展直接映射
+pub unsafe fn extend_direct_map() {}
+
+/// 设备窗口沿用固件的地址翻译直接访问，与串口相同，无需另行映射
+pub unsafe fn map_uncached(_base: usize, _size: usize) -> bool {
+    true
+}
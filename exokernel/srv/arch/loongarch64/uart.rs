@@ -1,14 +1,30 @@
 // src/arch/loongarch64/uart.rs
 //! LoongArch UART驱动（基于NS16550）
 
-const UART0_BASE: usize = 0x1FE001E0;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// QEMU virt 与 Loongson 7A 桥片的 UART0
+pub const UART0_BASE: usize = 0x1FE001E0;
+
+static BASE: AtomicUsize = AtomicUsize::new(UART0_BASE);
+
+/// 当前使用的 UART 基址
+pub fn base() -> usize {
+    BASE.load(Ordering::Relaxed)
+}
+
+/// 切换到引导信息给出的控制台 UART 并重新初始化
+pub unsafe fn set_base(addr: usize) {
+    BASE.store(addr, Ordering::Relaxed);
+    init();
+}
 
 unsafe fn write_reg(offset: usize, val: u8) {
-    core::ptr::write_volatile((UART0_BASE + offset) as *mut u8, val);
+    core::ptr::write_volatile((base() + offset) as *mut u8, val);
 }
 
 unsafe fn read_reg(offset: usize) -> u8 {
-    core::ptr::read_volatile((UART0_BASE + offset) as *const u8)
+    core::ptr::read_volatile((base() + offset) as *const u8)
 }
 
 pub unsafe fn init() {
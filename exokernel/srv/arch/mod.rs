@@ -44,4 +44,8 @@ pub trait Architecture {
     fn enable_interrupts();
     fn disable_interrupts();
     fn write_serial(byte: u8);
+    /// 当前 CPU 编号
+    fn cpu_id() -> usize;
+    /// 单调递增的时间戳计数器（周期数或定时器滴答）
+    fn timestamp() -> u64;
 }
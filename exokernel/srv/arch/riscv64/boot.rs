@@ -14,6 +14,9 @@ _start:
     la t0, dtb_ptr
     sd a1, (t0)
 
+    // hartid 放入 tp，供 cpu_id() 读取
+    mv tp, a0
+
     // 设置栈
     la sp, boot_stack_top
 
@@ -48,6 +51,15 @@ dtb_ptr:
 
 extern "C" {
     static dtb_ptr: u64;
+    static __kernel_start: u8;
+    static __kernel_end: u8;
+}
+
+/// 内核映像的物理范围 [start, end)（恒等映射）
+pub fn kernel_phys_range() -> (usize, usize) {
+    unsafe {
+        (&__kernel_start as *const u8 as usize, &__kernel_end as *const u8 as usize)
+    }
 }
 
 pub unsafe fn get_boot_info() -> *const u8 {
@@ -28,9 +28,42 @@ impl super::Architecture for RiscV64 {
     fn write_serial(byte: u8) {
         uart::write_byte(byte);
     }
+
+    fn cpu_id() -> usize {
+        // 启动时 hartid 存放在 tp 中
+        let hart: usize;
+        unsafe { asm!("mv {}, tp", out(reg) hart, options(nomem, nostack)); }
+        hart
+    }
+
+    fn timestamp() -> u64 {
+        let t: u64;
+        unsafe { asm!("rdtime {}", out(reg) t, options(nomem, nostack)); }
+        t
+    }
 }
 
 pub fn halt() { RiscV64::halt() }
 pub fn enable_interrupts() { RiscV64::enable_interrupts() }
 pub fn disable_interrupts() { RiscV64::disable_interrupts() }
 pub fn write_serial(byte: u8) { RiscV64::write_serial(byte) }
+pub fn cpu_id() -> usize { RiscV64::cpu_id() }
+pub fn timestamp() -> u64 { RiscV64::timestamp() }
+
+/// 末级缓存的 (大小, 相联度)；该架构暂不探测，由配置提供
+pub fn llc_geometry() -> Option<(usize, usize)> {
+    None
+}
+
+/// 直接映射覆盖的物理地址上限（未开启分页（satp = Bare），物理地址直接可访问）
+pub fn direct_map_limit() -> usize {
+    usize::MAX
+}
+
+/// 无需扩展直接映射
+pub unsafe fn extend_direct_map() {}
+
+/// 设备窗口已是不可缓存访问（未开启分页，可缓存属性由平台的 PMA 决定）
+pub unsafe fn map_uncached(_base: usize, _size: usize) -> bool {
+    true
+}
@@ -18,6 +18,13 @@ multiboot2_header:
     .long 8
     .long 10
 
+    .align 8
+    .short 1, 1
+    .long 20
+    .long 17
+    .long 14
+    .long 15
+
     .align 8
     .short 0, 0
     .long 8
@@ -179,6 +186,21 @@ boot_stack_top:
 extern "C" {
     static multiboot2_magic: u32;
     static multiboot2_info: u32;
+    static __kernel_end: u8;
+}
+
+const KERNEL_VMA: usize = 0xFFFF_FFFF_8000_0000;
+const KERNEL_LMA: usize = 0x100000;
+
+/// 内核映像的物理范围 [start, end)，包含 Multiboot2 头部
+pub fn kernel_phys_range() -> (usize, usize) {
+    let end = unsafe { &__kernel_end as *const u8 as usize };
+    (KERNEL_LMA, end - KERNEL_VMA)
+}
+
+/// 内核映像内地址（静态变量等）对应的物理地址
+pub fn virt_to_phys(addr: usize) -> usize {
+    if addr >= KERNEL_VMA { addr - KERNEL_VMA } else { addr }
 }
 
 pub unsafe fn get_boot_info() -> *const u8 {
@@ -2,8 +2,11 @@
 use core::arch::{asm, global_asm};
 
 pub mod boot;
+pub mod paging;
 pub mod serial;
 
+pub use paging::{direct_map_limit, extend_direct_map, map_uncached};
+
 pub struct X86_64;
 
 impl super::Architecture for X86_64 {
@@ -29,12 +32,54 @@ impl super::Architecture for X86_64 {
     fn write_serial(byte: u8) {
         serial::write_byte(byte);
     }
+
+    fn cpu_id() -> usize {
+        // CPUID.01H:EBX[31:24] 为初始 APIC ID
+        unsafe { (core::arch::x86_64::__cpuid(1).ebx >> 24) as usize }
+    }
+
+    fn timestamp() -> u64 {
+        unsafe { core::arch::x86_64::_rdtsc() }
+    }
 }
 
 pub fn halt() { X86_64::halt() }
 pub fn enable_interrupts() { X86_64::enable_interrupts() }
 pub fn disable_interrupts() { X86_64::disable_interrupts() }
 pub fn write_serial(byte: u8) { X86_64::write_serial(byte) }
+pub fn cpu_id() -> usize { X86_64::cpu_id() }
+pub fn timestamp() -> u64 { X86_64::timestamp() }
+
+/// 末级缓存的 (大小, 相联度)，由 CPUID.04H 的各子叶中级别最高的数据/统一缓存得出
+pub fn llc_geometry() -> Option<(usize, usize)> {
+    use core::arch::x86_64::{__cpuid, __cpuid_count};
+
+    if unsafe { __cpuid(0).eax } < 4 {
+        return None;
+    }
+
+    let mut best: Option<(u32, usize, usize)> = None;
+    for sub in 0..16 {
+        let r = unsafe { __cpuid_count(4, sub) };
+        let kind = r.eax & 0x1f;
+        if kind == 0 {
+            break;
+        }
+        // 1 = 数据缓存, 3 = 统一缓存
+        if kind != 1 && kind != 3 {
+            continue;
+        }
+        let level = (r.eax >> 5) & 0x7;
+        let ways = ((r.ebx >> 22) & 0x3ff) as usize + 1;
+        let partitions = ((r.ebx >> 12) & 0x3ff) as usize + 1;
+        let line = (r.ebx & 0xfff) as usize + 1;
+        let sets = r.ecx as usize + 1;
+        if best.map_or(true, |(l, _, _)| level > l) {
+            best = Some((level, ways * partitions * line * sets, ways));
+        }
+    }
+    best.map(|(_, size, ways)| (size, ways))
+}
 
 // GDT结构
 #[repr(C, packed)]
@@ -0,0 +1,161 @@
+// src/arch/x86_64/paging.rs
+//! 物理内存的直接映射
+//!
+//! 引导代码（boot.rs）只用一张页目录，以 2MiB 大页恒等映射了前 1GiB。
+//! 内核按物理地址直接访问 RAM 与固件表，所以在解析引导信息之前由
+//! `extend_direct_map` 把恒等映射扩展到 `DIRECT_MAP_LIMIT`。
+//! 新增的页目录放在静态 BSS 中，因为这时还没有页分配器。
+//! 上限以外的物理地址不可访问：内存区域在交给分配器前截断，固件表被跳过。
+//!
+//! 设备窗口不能走回写缓存：`map_uncached` 把窗口内的页改为 PCD|PWT。窗口只覆盖
+//! 大页的一部分时（如低 1MiB 内的 VGA 文本缓冲区，与内核映像同在第一个 2MiB），
+//! 先把该大页拆成 4KiB 页表，只改窗口内的页，同一大页内的 RAM 仍走回写缓存。
+
+use core::arch::asm;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use spin::Mutex;
+
+/// 直接映射覆盖的物理地址上限（4GiB，含 32 位 MMIO 空洞）
+pub const DIRECT_MAP_LIMIT: usize = 4 << 30;
+
+/// 引导代码已映射的范围
+const BOOT_DIRECT_MAP: usize = 1 << 30;
+
+const PAGE_1G: usize = 1 << 30;
+const PAGE_2M: usize = 2 << 20;
+const PAGE_4K: usize = 4 << 10;
+
+/// 除引导页目录外还需要的页目录数（每张覆盖 1GiB）
+const EXTRA_PDS: usize = DIRECT_MAP_LIMIT / PAGE_1G - 1;
+
+const PTE_PRESENT: u64 = 1 << 0;
+const PTE_WRITABLE: u64 = 1 << 1;
+const PTE_PWT: u64 = 1 << 3;
+const PTE_PCD: u64 = 1 << 4;
+const PTE_HUGE: u64 = 1 << 7;
+const PTE_ADDR_MASK: u64 = 0x000f_ffff_ffff_f000;
+/// 大页表项中拆分后仍适用于 4KiB 表项的位（P、RW、US、PWT、PCD、A、D、G）
+const PTE_SPLIT_FLAGS: u64 = 0x17f;
+
+/// 可拆分的 2MiB 大页数（每个设备窗口的首尾各至多一个）
+const MAX_SPLIT: usize = 16;
+
+#[repr(C, align(4096))]
+struct PageTable([u64; 512]);
+
+static mut DIRECT_PDS: [PageTable; EXTRA_PDS] = [const { PageTable([0; 512]) }; EXTRA_PDS];
+
+/// 拆分大页用的页表（运行期调用时仍不依赖页分配器）
+static mut SPLIT_PTS: [PageTable; MAX_SPLIT] = [const { PageTable([0; 512]) }; MAX_SPLIT];
+
+/// 已用的 SPLIT_PTS 数；同时串行化对直接映射的修改
+static SPLIT_USED: Mutex<usize> = Mutex::new(0);
+
+/// 当前直接映射的上限
+static MAPPED: AtomicUsize = AtomicUsize::new(BOOT_DIRECT_MAP);
+
+/// 直接映射覆盖的物理地址上限（不含）
+pub fn direct_map_limit() -> usize {
+    MAPPED.load(Ordering::Acquire)
+}
+
+/// 把恒等映射扩展到 DIRECT_MAP_LIMIT（可重复调用）
+///
+/// # Safety
+///
+/// 只能在单核启动阶段调用，此时仍在使用引导页表
+pub unsafe fn extend_direct_map() {
+    if direct_map_limit() >= DIRECT_MAP_LIMIT {
+        return;
+    }
+
+    let pdpt = boot_pdpt();
+    let pds = &mut *core::ptr::addr_of_mut!(DIRECT_PDS);
+    for (i, pd) in pds.iter_mut().enumerate() {
+        let gb = (i + 1) * PAGE_1G;
+        for (j, entry) in pd.0.iter_mut().enumerate() {
+            *entry = (gb + j * PAGE_2M) as u64 | PTE_PRESENT | PTE_WRITABLE | PTE_HUGE;
+        }
+        let pd_phys = super::boot::virt_to_phys(pd as *const PageTable as usize);
+        *pdpt.add(i + 1) = pd_phys as u64 | PTE_PRESENT | PTE_WRITABLE;
+    }
+
+    // 重新加载 CR3 刷新 TLB
+    let cr3: u64;
+    asm!("mov {}, cr3", out(reg) cr3, options(nomem, nostack));
+    asm!("mov cr3, {}", in(reg) cr3, options(nostack));
+    MAPPED.store(DIRECT_MAP_LIMIT, Ordering::Release);
+}
+
+/// 把覆盖 [base, base+size) 的直接映射改为不可缓存；超出直接映射或拆分用的页表耗尽时返回 false
+///
+/// 粒度为 4KiB：整块落在窗口内的 2MiB 大页直接改表项，只部分覆盖的大页先拆成 4KiB 页表，
+/// 同一大页内窗口以外的页（可能是 RAM 或内核映像）保持回写缓存
+///
+/// # Safety
+///
+/// 范围必须是设备内存
+pub unsafe fn map_uncached(base: usize, size: usize) -> bool {
+    let end = match base.checked_add(size) {
+        Some(end) if end <= direct_map_limit() => end,
+        _ => return false,
+    };
+    let start = base & !(PAGE_4K - 1);
+
+    let mut used = SPLIT_USED.lock();
+    let pdpt = boot_pdpt();
+    let mut ok = true;
+    let mut huge = start & !(PAGE_2M - 1);
+    while huge < end {
+        let pd = (*pdpt.add(huge / PAGE_1G) & PTE_ADDR_MASK) as *mut u64;
+        let pde = pd.add((huge % PAGE_1G) / PAGE_2M);
+        let (lo, hi) = (start.max(huge), end.min(huge + PAGE_2M));
+
+        if *pde & PTE_HUGE != 0 && lo == huge && hi == huge + PAGE_2M {
+            *pde |= PTE_PCD | PTE_PWT;
+        } else {
+            if *pde & PTE_HUGE != 0 && !split_huge(pde, huge, &mut used) {
+                ok = false;
+                break;
+            }
+            let pt = (*pde & PTE_ADDR_MASK) as *mut u64;
+            let mut page = lo & !(PAGE_4K - 1);
+            while page < hi {
+                *pt.add((page % PAGE_2M) / PAGE_4K) |= PTE_PCD | PTE_PWT;
+                page += PAGE_4K;
+            }
+        }
+        huge += PAGE_2M;
+    }
+
+    // 大页与 4KiB 表项都可能改过，重新加载 CR3 刷新整个 TLB
+    let cr3: u64;
+    asm!("mov {}, cr3", out(reg) cr3, options(nomem, nostack));
+    asm!("mov cr3, {}", in(reg) cr3, options(nostack));
+    ok
+}
+
+// 把 pde 指向的 2MiB 大页拆成 512 个属性相同的 4KiB 表项；页表用尽时返回 false
+unsafe fn split_huge(pde: *mut u64, huge: usize, used: &mut usize) -> bool {
+    if *used == MAX_SPLIT {
+        return false;
+    }
+    let pt = &mut (*core::ptr::addr_of_mut!(SPLIT_PTS))[*used];
+    *used += 1;
+
+    let flags = *pde & PTE_SPLIT_FLAGS;
+    for (i, entry) in pt.0.iter_mut().enumerate() {
+        *entry = (huge + i * PAGE_4K) as u64 | flags;
+    }
+    let pt_phys = super::boot::virt_to_phys(pt as *const PageTable as usize);
+    *pde = pt_phys as u64 | PTE_PRESENT | PTE_WRITABLE;
+    true
+}
+
+// 引导页表中映射低 512GiB 的 PDPT（CR3 -> PML4[0]，页表本身位于已映射的低 1GiB）
+unsafe fn boot_pdpt() -> *mut u64 {
+    let cr3: u64;
+    asm!("mov {}, cr3", out(reg) cr3, options(nomem, nostack));
+    let pml4 = (cr3 & PTE_ADDR_MASK) as *const u64;
+    (*pml4 & PTE_ADDR_MASK) as *mut u64
+}
@@ -0,0 +1,695 @@
+// src/boot/acpi.rs
+//! ACPI 表解析（x86_64）
+//!
+//! RSDP 来自 Multiboot2 标签 14/15，没有时扫描 EBDA 与 0xE0000-0xFFFFF。
+//! 从 RSDT/XSDT 出发逐个校验表的校验和，解析：
+//! - MADT：CPU（本地 APIC / x2APIC）、I/O APIC、中断源重定向、NMI
+//! - HPET：定时器块地址
+//! - MCFG：PCIe ECAM 配置空间窗口
+//! - SRAT：内存与 CPU 的邻近域（NUMA）
+//! - FADT：SCI、PM 定时器、复位寄存器、DSDT 与启动架构标志
+//!
+//! 结果汇总为 `Platform` 保存在全局，通过 `platform()` 查询；
+//! I/O APIC、HPET 与 ECAM 窗口同时登记为设备内存。
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use spin::Mutex;
+use super::MemoryAffinity;
+use crate::mm::device::{self, DeviceKind};
+
+const RSDP_SIGNATURE: &[u8; 8] = b"RSD PTR ";
+const RSDP_V1_LEN: usize = 20;
+const SDT_HEADER_LEN: usize = 36;
+
+/// 超过此长度的 RSDP / 系统描述表视为损坏（长度字段来自固件，不可信）
+const RSDP_MAX_LEN: usize = 4096;
+const SDT_MAX_LEN: usize = 1 << 20;
+
+/// 本地 APIC 与 I/O APIC 的架构默认地址（没有 MADT 时使用）
+const DEFAULT_LAPIC_BASE: usize = 0xFEE0_0000;
+const DEFAULT_IOAPIC_BASE: usize = 0xFEC0_0000;
+
+/// BIOS 数据区中 EBDA 段地址的位置
+const BDA_EBDA_SEGMENT: usize = 0x40E;
+const EBDA_SCAN_LEN: usize = 1024;
+const BIOS_ROM_START: usize = 0xE0000;
+const BIOS_ROM_END: usize = 0x100000;
+
+const MADT_ENTRIES_OFFSET: usize = SDT_HEADER_LEN + 8;
+const MADT_PCAT_COMPAT: u32 = 1;
+const MADT_LOCAL_APIC: u8 = 0;
+const MADT_IO_APIC: u8 = 1;
+const MADT_INTERRUPT_OVERRIDE: u8 = 2;
+const MADT_LOCAL_APIC_NMI: u8 = 4;
+const MADT_LOCAL_APIC_ADDRESS: u8 = 5;
+const MADT_LOCAL_X2APIC: u8 = 9;
+const MADT_LOCAL_X2APIC_NMI: u8 = 0xA;
+const LAPIC_ENABLED: u32 = 1;
+const LAPIC_ONLINE_CAPABLE: u32 = 2;
+
+const MCFG_ENTRIES_OFFSET: usize = SDT_HEADER_LEN + 8;
+const MCFG_ENTRY_LEN: usize = 16;
+
+/// SRAT 表头之后还有 12 字节保留字段
+const SRAT_ENTRIES_OFFSET: usize = SDT_HEADER_LEN + 12;
+const SRAT_PROCESSOR_AFFINITY: u8 = 0;
+const SRAT_MEMORY_AFFINITY: u8 = 1;
+const SRAT_X2APIC_AFFINITY: u8 = 2;
+const SRAT_ENABLED: u32 = 1;
+
+const FADT_RESET_REG_SUP: u32 = 1 << 10;
+const FADT_TMR_VAL_EXT: u32 = 1 << 8;
+const FADT_HW_REDUCED: u32 = 1 << 20;
+
+/// 已定位并通过校验的 RSDP
+#[derive(Debug, Clone, Copy)]
+pub struct Rsdp {
+    pub revision: u8,
+    pub oem_id: [u8; 6],
+    pub rsdt: usize,
+    pub xsdt: usize,
+}
+
+impl Rsdp {
+    /// 解析 RSDP（签名或校验和错误时返回 None）
+    ///
+    /// # Safety
+    ///
+    /// addr 必须指向可读的 RSDP 副本
+    pub unsafe fn parse(addr: *const u8) -> Option<Self> {
+        if core::slice::from_raw_parts(addr, 8) != RSDP_SIGNATURE {
+            return None;
+        }
+        if !checksum_ok(core::slice::from_raw_parts(addr, RSDP_V1_LEN)) {
+            return None;
+        }
+
+        let revision = *addr.add(15);
+        let mut oem_id = [0u8; 6];
+        oem_id.copy_from_slice(core::slice::from_raw_parts(addr.add(9), 6));
+        let rsdt = read_u32(addr, 16) as usize;
+
+        // ACPI 2.0+ 的扩展部分有自己的长度与校验和
+        let mut xsdt = 0;
+        if revision >= 2 {
+            let length = read_u32(addr, 20) as usize;
+            if (36..=RSDP_MAX_LEN).contains(&length) && checksum_ok(core::slice::from_raw_parts(addr, length)) {
+                xsdt = read_u64(addr, 24) as usize;
+            }
+        }
+        Some(Self { revision, oem_id, rsdt, xsdt })
+    }
+
+    /// 在传统 BIOS 区域中查找 RSDP：EBDA 前 1KB，再到 0xE0000-0xFFFFF，均按 16 字节对齐
+    ///
+    /// # Safety
+    ///
+    /// 低 1MB 物理内存必须可按物理地址直接访问
+    pub unsafe fn scan_bios() -> Option<Self> {
+        let ebda = (core::ptr::read_unaligned(BDA_EBDA_SEGMENT as *const u16) as usize) << 4;
+        let mut ranges = Vec::new();
+        if ebda >= 0x80000 && ebda < 0xA0000 {
+            ranges.push((ebda, ebda + EBDA_SCAN_LEN));
+        }
+        ranges.push((BIOS_ROM_START, BIOS_ROM_END));
+
+        ranges
+            .into_iter()
+            .flat_map(|(start, end)| (start..end).step_by(16))
+            .find_map(|addr| Self::parse(addr as *const u8))
+    }
+
+    pub fn oem_id(&self) -> &str {
+        core::str::from_utf8(&self.oem_id).unwrap_or("").trim_end()
+    }
+
+    /// RSDT/XSDT 列出的全部通过校验的表
+    ///
+    /// 超出直接映射或长度异常的表被跳过
+    ///
+    /// # Safety
+    ///
+    /// 直接映射范围内的 RSDT/XSDT 及其引用的表必须可读
+    pub unsafe fn tables(&self) -> Vec<&'static [u8]> {
+        // 优先使用 64 位的 XSDT，校验失败时退回 RSDT
+        let root = [(self.xsdt, 8), (self.rsdt, 4)]
+            .into_iter()
+            .find_map(|(addr, entry_size)| table(addr).map(|t| (t, entry_size)));
+        let (root, entry_size) = match root {
+            Some(r) => r,
+            None => return Vec::new(),
+        };
+
+        root[SDT_HEADER_LEN..]
+            .chunks_exact(entry_size)
+            .filter_map(|e| {
+                let addr = if entry_size == 8 { le_u64(e, 0) as usize } else { le_u32(e, 0) as usize };
+                table(addr)
+            })
+            .collect()
+    }
+
+    /// 按签名查找系统描述表
+    ///
+    /// # Safety
+    ///
+    /// 同 `tables`
+    pub unsafe fn find_table(&self, signature: &[u8; 4]) -> Option<&'static [u8]> {
+        self.tables().into_iter().find(|t| &t[0..4] == signature)
+    }
+}
+
+// 校验表头长度与校验和，返回整张表；表不在直接映射内时返回 None
+unsafe fn table(addr: usize) -> Option<&'static [u8]> {
+    if addr == 0 || !mapped(addr, SDT_HEADER_LEN) {
+        return None;
+    }
+    let length = read_u32(addr as *const u8, 4) as usize;
+    if !(SDT_HEADER_LEN..=SDT_MAX_LEN).contains(&length) || !mapped(addr, length) {
+        return None;
+    }
+    let bytes = core::slice::from_raw_parts(addr as *const u8, length);
+    checksum_ok(bytes).then_some(bytes)
+}
+
+// [addr, addr+len) 是否可按物理地址直接访问
+fn mapped(addr: usize, len: usize) -> bool {
+    addr.checked_add(len).map_or(false, |end| end <= crate::arch::direct_map_limit())
+}
+
+fn checksum_ok(bytes: &[u8]) -> bool {
+    bytes.iter().fold(0u8, |sum, &b| sum.wrapping_add(b)) == 0
+}
+
+// ========== 平台描述 ==========
+
+/// 一个逻辑 CPU（MADT 本地 APIC 或 x2APIC 条目）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Processor {
+    /// ACPI 处理器 UID
+    pub acpi_id: u32,
+    pub apic_id: u32,
+    pub enabled: bool,
+    /// 当前未启用但可热插拔上线
+    pub online_capable: bool,
+    /// 邻近域（SRAT 未给出时为 0）
+    pub node: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IoApic {
+    pub id: u8,
+    pub address: usize,
+    /// 该 I/O APIC 第一个输入对应的全局系统中断号
+    pub gsi_base: u32,
+}
+
+/// ISA 中断到全局系统中断的重定向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InterruptOverride {
+    pub bus: u8,
+    pub source: u8,
+    pub gsi: u32,
+    /// MPS INTI 标志：位 0-1 极性，位 2-3 触发方式
+    pub flags: u16,
+}
+
+/// 接到本地 APIC LINT 引脚的 NMI
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalNmi {
+    /// 目标处理器 UID（全部处理器时为 None）
+    pub acpi_id: Option<u32>,
+    pub lint: u8,
+    pub flags: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hpet {
+    pub address: usize,
+    pub number: u8,
+    /// 周期模式下的最小时钟滴答
+    pub min_tick: u16,
+    /// 比较器个数
+    pub comparators: u8,
+    pub vendor_id: u16,
+}
+
+/// 一个 PCIe 段组的 ECAM 窗口
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PciSegment {
+    /// 总线 0 对应的基址（窗口从 start_bus 处开始）
+    pub base: usize,
+    pub segment: u16,
+    pub start_bus: u8,
+    pub end_bus: u8,
+}
+
+impl PciSegment {
+    /// 窗口起始地址
+    pub fn window_base(&self) -> usize {
+        self.base + ((self.start_bus as usize) << 20)
+    }
+
+    /// 窗口大小：每条总线 1MB
+    pub fn size(&self) -> usize {
+        (self.end_bus.saturating_sub(self.start_bus) as usize + 1) << 20
+    }
+
+    /// 某个功能配置空间的物理地址
+    pub fn config_address(&self, bus: u8, device: u8, function: u8) -> Option<usize> {
+        if bus < self.start_bus || bus > self.end_bus || device >= 32 || function >= 8 {
+            return None;
+        }
+        Some(self.base
+             + ((bus as usize) << 20)
+             + ((device as usize) << 15)
+             + ((function as usize) << 12))
+    }
+}
+
+/// ACPI 通用地址结构
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenericAddress {
+    /// 0 = 系统内存，1 = I/O 端口
+    pub space: u8,
+    pub bit_width: u8,
+    pub address: u64,
+}
+
+/// FADT 中内核关心的部分
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fadt {
+    pub dsdt: usize,
+    pub sci_interrupt: u16,
+    pub smi_command: u32,
+    pub acpi_enable: u8,
+    pub acpi_disable: u8,
+    pub pm1a_event: u32,
+    pub pm1a_control: u32,
+    /// PM 定时器端口（0 表示没有）
+    pub pm_timer: u32,
+    /// PM 定时器为 32 位（否则 24 位）
+    pub pm_timer_32bit: bool,
+    /// RTC 世纪寄存器索引（0 表示没有）
+    pub century: u8,
+    /// IA-PC 启动架构标志：位 0 传统设备，位 1 8042，位 2 无 VGA，位 3 禁用 MSI
+    pub boot_arch: u16,
+    pub reset: Option<(GenericAddress, u8)>,
+    pub hardware_reduced: bool,
+}
+
+impl Fadt {
+    pub fn has_8042(&self) -> bool {
+        self.boot_arch & 2 != 0
+    }
+}
+
+/// ACPI 给出的平台描述
+#[derive(Debug, Clone, Default)]
+pub struct Platform {
+    pub revision: u8,
+    pub oem_id: String,
+    /// 找到并通过校验的表签名
+    pub tables: Vec<[u8; 4]>,
+    pub local_apic: usize,
+    /// 同时存在 8259 PIC（使用 APIC 前需屏蔽）
+    pub legacy_pic: bool,
+    pub processors: Vec<Processor>,
+    pub io_apics: Vec<IoApic>,
+    pub overrides: Vec<InterruptOverride>,
+    pub nmis: Vec<LocalNmi>,
+    pub hpet: Option<Hpet>,
+    pub pci_segments: Vec<PciSegment>,
+    pub memory_affinity: Vec<MemoryAffinity>,
+    pub fadt: Option<Fadt>,
+}
+
+impl Platform {
+    /// 已启用的 CPU 数
+    pub fn cpu_count(&self) -> usize {
+        self.processors.iter().filter(|p| p.enabled).count()
+    }
+
+    /// 邻近域个数（至少为 1）
+    pub fn node_count(&self) -> usize {
+        let mut nodes: Vec<u32> = self.memory_affinity.iter().map(|a| a.node)
+            .chain(self.processors.iter().map(|p| p.node))
+            .collect();
+        nodes.sort_unstable();
+        nodes.dedup();
+        nodes.len().max(1)
+    }
+
+    pub fn processor_by_apic_id(&self, apic_id: u32) -> Option<&Processor> {
+        self.processors.iter().find(|p| p.apic_id == apic_id)
+    }
+
+    /// ISA IRQ 对应的全局系统中断与 INTI 标志（无重定向时恒等映射）
+    pub fn isa_irq_to_gsi(&self, irq: u8) -> (u32, u16) {
+        self.overrides
+            .iter()
+            .find(|o| o.bus == 0 && o.source == irq)
+            .map_or((irq as u32, 0), |o| (o.gsi, o.flags))
+    }
+
+    /// 负责某个全局系统中断的 I/O APIC（每个 I/O APIC 至少 24 个输入）
+    pub fn io_apic_for_gsi(&self, gsi: u32) -> Option<&IoApic> {
+        self.io_apics
+            .iter()
+            .filter(|a| a.gsi_base <= gsi)
+            .max_by_key(|a| a.gsi_base)
+    }
+
+    pub fn pci_segment(&self, segment: u16, bus: u8) -> Option<&PciSegment> {
+        self.pci_segments.iter().find(|s| s.segment == segment && s.start_bus <= bus && bus <= s.end_bus)
+    }
+}
+
+static PLATFORM: Mutex<Option<Platform>> = Mutex::new(None);
+
+/// 启动时解析得到的平台描述（没有 ACPI 时为 None）
+pub fn platform() -> Option<Platform> {
+    PLATFORM.lock().clone()
+}
+
+/// 解析 ACPI 表、登记设备内存并保存平台描述
+///
+/// rsdp 为 None 时扫描 BIOS 区域；仍找不到时登记 APIC 的默认地址并返回 None
+///
+/// # Safety
+///
+/// 同 `Rsdp::tables`
+pub unsafe fn init(rsdp: Option<Rsdp>) -> Option<Platform> {
+    let rsdp = match rsdp.or_else(|| Rsdp::scan_bios()) {
+        Some(r) => r,
+        None => {
+            device::register(DEFAULT_LAPIC_BASE, crate::arch::PAGE_SIZE, DeviceKind::InterruptController);
+            device::register(DEFAULT_IOAPIC_BASE, crate::arch::PAGE_SIZE, DeviceKind::InterruptController);
+            return None;
+        }
+    };
+
+    let platform = parse(&rsdp);
+    register_devices(&platform);
+    *PLATFORM.lock() = Some(platform.clone());
+    Some(platform)
+}
+
+/// 解析 RSDP 引用的全部已知表
+///
+/// # Safety
+///
+/// 同 `Rsdp::tables`
+pub unsafe fn parse(rsdp: &Rsdp) -> Platform {
+    let mut platform = Platform {
+        revision: rsdp.revision,
+        oem_id: String::from(rsdp.oem_id()),
+        local_apic: DEFAULT_LAPIC_BASE,
+        ..Platform::default()
+    };
+
+    for table in rsdp.tables() {
+        let mut signature = [0u8; 4];
+        signature.copy_from_slice(&table[0..4]);
+        platform.tables.push(signature);
+
+        match &signature {
+            b"APIC" => parse_madt(table, &mut platform),
+            b"HPET" => platform.hpet = parse_hpet(table),
+            b"MCFG" => parse_mcfg(table, &mut platform),
+            b"FACP" => platform.fadt = parse_fadt(table),
+            _ => {}
+        }
+    }
+
+    // SRAT 引用 MADT 中的 APIC ID，最后处理
+    if let Some(srat) = rsdp.find_table(b"SRAT") {
+        parse_srat(srat, &mut platform);
+    }
+    platform
+}
+
+// 类型-长度结构的子表序列（MADT、SRAT）
+fn entries(table: &[u8], start: usize) -> impl Iterator<Item = (u8, &[u8])> {
+    let mut off = start;
+    core::iter::from_fn(move || {
+        if off + 2 > table.len() {
+            return None;
+        }
+        let len = table[off + 1] as usize;
+        if len < 2 || off + len > table.len() {
+            return None;
+        }
+        let entry = &table[off..off + len];
+        off += len;
+        Some((entry[0], entry))
+    })
+}
+
+fn parse_madt(table: &[u8], platform: &mut Platform) {
+    if table.len() < MADT_ENTRIES_OFFSET {
+        return;
+    }
+    platform.local_apic = le_u32(table, SDT_HEADER_LEN) as usize;
+    platform.legacy_pic = le_u32(table, SDT_HEADER_LEN + 4) & MADT_PCAT_COMPAT != 0;
+
+    for (typ, e) in entries(table, MADT_ENTRIES_OFFSET) {
+        match typ {
+            MADT_LOCAL_APIC if e.len() >= 8 => {
+                let flags = le_u32(e, 4);
+                platform.processors.push(Processor {
+                    acpi_id: e[2] as u32,
+                    apic_id: e[3] as u32,
+                    enabled: flags & LAPIC_ENABLED != 0,
+                    online_capable: flags & LAPIC_ONLINE_CAPABLE != 0,
+                    node: 0,
+                });
+            }
+            MADT_LOCAL_X2APIC if e.len() >= 16 => {
+                let flags = le_u32(e, 8);
+                platform.processors.push(Processor {
+                    acpi_id: le_u32(e, 12),
+                    apic_id: le_u32(e, 4),
+                    enabled: flags & LAPIC_ENABLED != 0,
+                    online_capable: flags & LAPIC_ONLINE_CAPABLE != 0,
+                    node: 0,
+                });
+            }
+            MADT_IO_APIC if e.len() >= 12 => {
+                platform.io_apics.push(IoApic {
+                    id: e[2],
+                    address: le_u32(e, 4) as usize,
+                    gsi_base: le_u32(e, 8),
+                });
+            }
+            MADT_INTERRUPT_OVERRIDE if e.len() >= 10 => {
+                platform.overrides.push(InterruptOverride {
+                    bus: e[2],
+                    source: e[3],
+                    gsi: le_u32(e, 4),
+                    flags: le_u16(e, 8),
+                });
+            }
+            MADT_LOCAL_APIC_NMI if e.len() >= 6 => {
+                platform.nmis.push(LocalNmi {
+                    acpi_id: (e[2] != 0xFF).then_some(e[2] as u32),
+                    flags: le_u16(e, 3),
+                    lint: e[5],
+                });
+            }
+            MADT_LOCAL_X2APIC_NMI if e.len() >= 12 => {
+                let uid = le_u32(e, 4);
+                platform.nmis.push(LocalNmi {
+                    acpi_id: (uid != u32::MAX).then_some(uid),
+                    flags: le_u16(e, 2),
+                    lint: e[8],
+                });
+            }
+            MADT_LOCAL_APIC_ADDRESS if e.len() >= 12 => {
+                platform.local_apic = le_u64(e, 4) as usize;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn parse_hpet(table: &[u8]) -> Option<Hpet> {
+    if table.len() < 56 {
+        return None;
+    }
+    let block_id = le_u32(table, SDT_HEADER_LEN);
+    Some(Hpet {
+        address: le_u64(table, 44) as usize,
+        number: table[52],
+        min_tick: le_u16(table, 53),
+        comparators: ((block_id >> 8) & 0x1F) as u8 + 1,
+        vendor_id: (block_id >> 16) as u16,
+    })
+}
+
+fn parse_mcfg(table: &[u8], platform: &mut Platform) {
+    if table.len() < MCFG_ENTRIES_OFFSET {
+        return;
+    }
+    for e in table[MCFG_ENTRIES_OFFSET..].chunks_exact(MCFG_ENTRY_LEN) {
+        platform.pci_segments.push(PciSegment {
+            base: le_u64(e, 0) as usize,
+            segment: le_u16(e, 8),
+            start_bus: e[10],
+            end_bus: e[11],
+        });
+    }
+}
+
+// 字段偏移见 ACPI 规范 5.2.9；ACPI 1.0 的 FADT 只有 116 字节
+fn parse_fadt(table: &[u8]) -> Option<Fadt> {
+    if table.len() < 116 {
+        return None;
+    }
+    let flags = le_u32(table, 112);
+    let x_dsdt = if table.len() >= 148 { le_u64(table, 140) as usize } else { 0 };
+    let reset = (table.len() >= 129 && flags & FADT_RESET_REG_SUP != 0).then(|| {
+        (GenericAddress { space: table[116], bit_width: table[117], address: le_u64(table, 120) }, table[128])
+    });
+
+    Some(Fadt {
+        dsdt: if x_dsdt != 0 { x_dsdt } else { le_u32(table, 40) as usize },
+        sci_interrupt: le_u16(table, 46),
+        smi_command: le_u32(table, 48),
+        acpi_enable: table[52],
+        acpi_disable: table[53],
+        pm1a_event: le_u32(table, 56),
+        pm1a_control: le_u32(table, 64),
+        pm_timer: le_u32(table, 76),
+        pm_timer_32bit: flags & FADT_TMR_VAL_EXT != 0,
+        century: table[108],
+        boot_arch: le_u16(table, 109),
+        reset,
+        hardware_reduced: flags & FADT_HW_REDUCED != 0,
+    })
+}
+
+// 内存亲和性进入 memory_affinity，CPU 亲和性写回对应处理器的 node
+fn parse_srat(table: &[u8], platform: &mut Platform) {
+    for (typ, e) in entries(table, SRAT_ENTRIES_OFFSET) {
+        match typ {
+            SRAT_MEMORY_AFFINITY if e.len() >= 40 => {
+                let size = le_u64(e, 16) as usize;
+                if le_u32(e, 28) & SRAT_ENABLED != 0 && size > 0 {
+                    platform.memory_affinity.push(MemoryAffinity {
+                        base: le_u64(e, 8) as usize,
+                        size,
+                        node: le_u32(e, 2),
+                    });
+                }
+            }
+            SRAT_PROCESSOR_AFFINITY if e.len() >= 16 => {
+                if le_u32(e, 4) & SRAT_ENABLED != 0 {
+                    // 邻近域低 8 位在偏移 2，高 24 位在偏移 9
+                    let node = e[2] as u32 | (e[9] as u32) << 8 | (e[10] as u32) << 16 | (e[11] as u32) << 24;
+                    set_node(platform, e[3] as u32, node);
+                }
+            }
+            SRAT_X2APIC_AFFINITY if e.len() >= 24 => {
+                if le_u32(e, 12) & SRAT_ENABLED != 0 {
+                    set_node(platform, le_u32(e, 8), le_u32(e, 4));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn set_node(platform: &mut Platform, apic_id: u32, node: u32) {
+    if let Some(p) = platform.processors.iter_mut().find(|p| p.apic_id == apic_id) {
+        p.node = node;
+    }
+}
+
+fn register_devices(platform: &Platform) {
+    let page = crate::arch::PAGE_SIZE;
+    device::register(platform.local_apic, page, DeviceKind::InterruptController);
+    if platform.io_apics.is_empty() {
+        device::register(DEFAULT_IOAPIC_BASE, page, DeviceKind::InterruptController);
+    }
+    for io in &platform.io_apics {
+        device::register(io.address, page, DeviceKind::InterruptController);
+    }
+    if let Some(hpet) = platform.hpet {
+        device::register(hpet.address, page, DeviceKind::Other);
+    }
+    for seg in &platform.pci_segments {
+        device::register(seg.window_base(), seg.size(), DeviceKind::PciConfig);
+    }
+}
+
+/// 打印平台摘要（loglevel 为 debug 时列出每个条目）
+pub fn report() {
+    let platform = match platform() {
+        Some(p) => p,
+        None => {
+            crate::println!("  [ACPI] No RSDP found");
+            return;
+        }
+    };
+
+    let mut names = String::new();
+    for sig in &platform.tables {
+        names.push_str(core::str::from_utf8(sig).unwrap_or("????"));
+        names.push(' ');
+    }
+    crate::println!("  [ACPI] Revision {} ({}), tables: {}", platform.revision, platform.oem_id, names.trim_end());
+    crate::println!("  [ACPI] {} CPUs ({} enabled), {} I/O APICs, {} NUMA nodes, local APIC 0x{:x}",
+                    platform.processors.len(), platform.cpu_count(), platform.io_apics.len(),
+                    platform.node_count(), platform.local_apic);
+    if let Some(hpet) = platform.hpet {
+        crate::println!("  [ACPI] HPET at 0x{:x} ({} comparators)", hpet.address, hpet.comparators);
+    }
+    for seg in &platform.pci_segments {
+        crate::println!("  [ACPI] PCIe segment {} buses {}-{} ECAM 0x{:x}",
+                        seg.segment, seg.start_bus, seg.end_bus, seg.window_base());
+    }
+
+    if !crate::console::log_enabled(crate::console::LOG_DEBUG) {
+        return;
+    }
+    for p in &platform.processors {
+        crate::println!("  [ACPI] CPU uid {} apic {} node {}{}",
+                        p.acpi_id, p.apic_id, p.node, if p.enabled { "" } else { " (disabled)" });
+    }
+    for io in &platform.io_apics {
+        crate::println!("  [ACPI] I/O APIC {} at 0x{:x}, GSI base {}", io.id, io.address, io.gsi_base);
+    }
+    for o in &platform.overrides {
+        crate::println!("  [ACPI] IRQ {} -> GSI {} (flags 0x{:x})", o.source, o.gsi, o.flags);
+    }
+    for a in &platform.memory_affinity {
+        crate::println!("  [NUMA] 0x{:016x} - 0x{:016x} -> node {}", a.base, a.end(), a.node);
+    }
+    if let Some(fadt) = platform.fadt {
+        crate::println!("  [ACPI] FADT: SCI {}, PM timer 0x{:x}{}, DSDT 0x{:x}",
+                        fadt.sci_interrupt, fadt.pm_timer,
+                        if fadt.pm_timer_32bit { " (32-bit)" } else { "" }, fadt.dsdt);
+    }
+}
+
+unsafe fn read_u32(base: *const u8, off: usize) -> u32 {
+    core::ptr::read_unaligned(base.add(off) as *const u32)
+}
+
+unsafe fn read_u64(base: *const u8, off: usize) -> u64 {
+    core::ptr::read_unaligned(base.add(off) as *const u64)
+}
+
+fn le_u16(bytes: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes([bytes[off], bytes[off + 1]])
+}
+
+fn le_u32(bytes: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes([bytes[off], bytes[off + 1], bytes[off + 2], bytes[off + 3]])
+}
+
+fn le_u64(bytes: &[u8], off: usize) -> u64 {
+    le_u32(bytes, off) as u64 | (le_u32(bytes, off + 4) as u64) << 32
+}
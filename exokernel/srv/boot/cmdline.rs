@@ -0,0 +1,355 @@
+// src/boot/cmdline.rs
+//! 内核命令行与运行时选项
+//!
+//! 命令行来自 Multiboot2 标签 1 或设备树 /chosen/bootargs，形如
+//! `loglevel=3 mem=512M selftest=none console=serial`。
+//!
+//! 各子系统把自己的选项声明为静态的 `BoolParam`/`UintParam`/`StrParam`/`ChoiceParam`，
+//! 在 `apply` 之前用 `register` 登记；`apply` 逐项解析命令行，未知选项与非法值
+//! 只打印警告并保留默认值。`report` 打印最终生效的配置。
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use spin::Mutex;
+
+/// 选项值解析失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamError {
+    /// 需要值但没有给出
+    MissingValue,
+    /// 值的格式不对
+    Invalid,
+    /// 数值超出允许范围
+    OutOfRange,
+}
+
+/// 一个可由命令行设置的选项
+pub trait KernelParam: Sync {
+    fn name(&self) -> &'static str;
+    fn help(&self) -> &'static str;
+    /// 按命令行中的值设置（`name` 单独出现时 value 为 None）
+    fn set(&self, value: Option<&str>) -> Result<(), ParamError>;
+    /// 当前值是否仍为默认值
+    fn is_default(&self) -> bool;
+    fn write_value(&self, out: &mut dyn Write) -> fmt::Result;
+}
+
+/// 开关选项：`name`、`name=on|off|1|0|yes|no|true|false`
+pub struct BoolParam {
+    name: &'static str,
+    help: &'static str,
+    default: bool,
+    value: AtomicBool,
+}
+
+impl BoolParam {
+    pub const fn new(name: &'static str, default: bool, help: &'static str) -> Self {
+        Self { name, help, default, value: AtomicBool::new(default) }
+    }
+
+    pub fn get(&self) -> bool {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+impl KernelParam for BoolParam {
+    fn name(&self) -> &'static str { self.name }
+    fn help(&self) -> &'static str { self.help }
+
+    fn set(&self, value: Option<&str>) -> Result<(), ParamError> {
+        let v = match value {
+            None | Some("1") | Some("on") | Some("yes") | Some("true") => true,
+            Some("0") | Some("off") | Some("no") | Some("false") => false,
+            Some(_) => return Err(ParamError::Invalid),
+        };
+        self.value.store(v, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn is_default(&self) -> bool { self.get() == self.default }
+
+    fn write_value(&self, out: &mut dyn Write) -> fmt::Result {
+        out.write_str(if self.get() { "on" } else { "off" })
+    }
+}
+
+/// 无符号整数选项，接受十进制、0x 十六进制与 K/M/G 后缀
+pub struct UintParam {
+    name: &'static str,
+    help: &'static str,
+    default: u64,
+    min: u64,
+    max: u64,
+    value: AtomicU64,
+}
+
+impl UintParam {
+    pub const fn new(name: &'static str, default: u64, min: u64, max: u64, help: &'static str) -> Self {
+        Self { name, help, default, min, max, value: AtomicU64::new(default) }
+    }
+
+    pub fn get(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+impl KernelParam for UintParam {
+    fn name(&self) -> &'static str { self.name }
+    fn help(&self) -> &'static str { self.help }
+
+    fn set(&self, value: Option<&str>) -> Result<(), ParamError> {
+        let v = parse_uint(value.ok_or(ParamError::MissingValue)?)?;
+        if v < self.min || v > self.max {
+            return Err(ParamError::OutOfRange);
+        }
+        self.value.store(v, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn is_default(&self) -> bool { self.get() == self.default }
+
+    fn write_value(&self, out: &mut dyn Write) -> fmt::Result {
+        write!(out, "{}", self.get())
+    }
+}
+
+/// 字符串选项
+pub struct StrParam {
+    name: &'static str,
+    help: &'static str,
+    default: &'static str,
+    value: Mutex<Option<String>>,
+}
+
+impl StrParam {
+    pub const fn new(name: &'static str, default: &'static str, help: &'static str) -> Self {
+        Self { name, help, default, value: Mutex::new(None) }
+    }
+
+    pub fn get(&self) -> String {
+        self.value.lock().clone().unwrap_or_else(|| String::from(self.default))
+    }
+
+    /// 按逗号分隔的列表中是否含有 item
+    pub fn contains(&self, item: &str) -> bool {
+        self.get().split(',').any(|s| s == item)
+    }
+}
+
+impl KernelParam for StrParam {
+    fn name(&self) -> &'static str { self.name }
+    fn help(&self) -> &'static str { self.help }
+
+    fn set(&self, value: Option<&str>) -> Result<(), ParamError> {
+        *self.value.lock() = Some(String::from(value.ok_or(ParamError::MissingValue)?));
+        Ok(())
+    }
+
+    fn is_default(&self) -> bool { self.value.lock().is_none() }
+
+    fn write_value(&self, out: &mut dyn Write) -> fmt::Result {
+        out.write_str(&self.get())
+    }
+}
+
+/// 从固定候选中选一个
+pub struct ChoiceParam {
+    name: &'static str,
+    help: &'static str,
+    choices: &'static [&'static str],
+    default: usize,
+    value: AtomicUsize,
+}
+
+impl ChoiceParam {
+    pub const fn new(name: &'static str, choices: &'static [&'static str], default: usize, help: &'static str) -> Self {
+        Self { name, help, choices, default, value: AtomicUsize::new(default) }
+    }
+
+    /// 当前选中项的下标
+    pub fn index(&self) -> usize {
+        self.value.load(Ordering::Relaxed)
+    }
+
+    pub fn get(&self) -> &'static str {
+        self.choices[self.index()]
+    }
+}
+
+impl KernelParam for ChoiceParam {
+    fn name(&self) -> &'static str { self.name }
+    fn help(&self) -> &'static str { self.help }
+
+    fn set(&self, value: Option<&str>) -> Result<(), ParamError> {
+        let value = value.ok_or(ParamError::MissingValue)?;
+        let idx = self.choices.iter().position(|&c| c == value).ok_or(ParamError::Invalid)?;
+        self.value.store(idx, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn is_default(&self) -> bool { self.index() == self.default }
+
+    fn write_value(&self, out: &mut dyn Write) -> fmt::Result {
+        out.write_str(self.get())
+    }
+}
+
+static PARAMS: Mutex<Vec<&'static dyn KernelParam>> = Mutex::new(Vec::new());
+static CMDLINE: Mutex<String> = Mutex::new(String::new());
+
+/// 登记一个选项（重名时后登记的被忽略）
+pub fn register(param: &'static dyn KernelParam) {
+    let mut params = PARAMS.lock();
+    if params.iter().any(|p| p.name() == param.name()) {
+        crate::println!("  [CMDLINE] Duplicate option '{}' ignored", param.name());
+        return;
+    }
+    params.push(param);
+}
+
+/// 记录引导程序传入的命令行（解析引导信息时调用）
+pub fn set(cmdline: &str) {
+    let mut current = CMDLINE.lock();
+    current.clear();
+    current.push_str(cmdline.trim());
+}
+
+/// 原始命令行
+pub fn get() -> String {
+    CMDLINE.lock().clone()
+}
+
+/// 把命令行应用到已登记的选项，返回成功设置的选项数
+pub fn apply() -> usize {
+    let cmdline = get();
+    let params = PARAMS.lock();
+    let mut applied = 0;
+
+    for word in split(&cmdline) {
+        let (key, value) = match word.split_once('=') {
+            Some((k, v)) => (k, Some(v.trim_matches('"'))),
+            None => (word, None),
+        };
+        let param = match params.iter().find(|p| p.name() == key) {
+            Some(p) => p,
+            None => {
+                crate::println!("  [CMDLINE] Warning: unknown option '{}'", key);
+                continue;
+            }
+        };
+        match param.set(value) {
+            Ok(()) => applied += 1,
+            Err(e) => crate::println!("  [CMDLINE] Warning: {}: {:?} ({}), keeping default",
+                                      key, e, param.help()),
+        }
+    }
+    applied
+}
+
+/// 打印生效的配置（* 表示被命令行修改过）
+pub fn report() {
+    crate::println!("  [CMDLINE] \"{}\"", get());
+    for param in PARAMS.lock().iter() {
+        let mut value = String::new();
+        let _ = param.write_value(&mut value);
+        crate::println!("  [CMDLINE] {} {:<12} = {:<12} {}",
+                        if param.is_default() { ' ' } else { '*' },
+                        param.name(), value, param.help());
+    }
+}
+
+// 按空白切分，双引号内的空白不切分
+fn split(cmdline: &str) -> impl Iterator<Item = &str> {
+    let mut in_quotes = false;
+    cmdline
+        .split(move |c: char| {
+            if c == '"' {
+                in_quotes = !in_quotes;
+            }
+            c.is_whitespace() && !in_quotes
+        })
+        .filter(|w| !w.is_empty())
+}
+
+fn parse_uint(s: &str) -> Result<u64, ParamError> {
+    let (digits, shift) = match s.as_bytes().last() {
+        Some(b'K') | Some(b'k') => (&s[..s.len() - 1], 10),
+        Some(b'M') | Some(b'm') => (&s[..s.len() - 1], 20),
+        Some(b'G') | Some(b'g') => (&s[..s.len() - 1], 30),
+        _ => (s, 0),
+    };
+    let v = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => digits.parse::<u64>(),
+    }
+    .map_err(|_| ParamError::Invalid)?;
+    v.checked_mul(1 << shift).ok_or(ParamError::OutOfRange)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_uint_suffixes() {
+        assert_eq!(parse_uint("0"), Ok(0));
+        assert_eq!(parse_uint("42"), Ok(42));
+        assert_eq!(parse_uint("4K"), Ok(4 << 10));
+        assert_eq!(parse_uint("4k"), Ok(4 << 10));
+        assert_eq!(parse_uint("512M"), Ok(512 << 20));
+        assert_eq!(parse_uint("2g"), Ok(2 << 30));
+        // 后缀只能出现一次，且不能单独出现
+        assert_eq!(parse_uint("1KK"), Err(ParamError::Invalid));
+        assert_eq!(parse_uint("M"), Err(ParamError::Invalid));
+    }
+
+    #[test]
+    fn test_parse_uint_hex() {
+        assert_eq!(parse_uint("0x10"), Ok(16));
+        assert_eq!(parse_uint("0XfF"), Ok(255));
+        assert_eq!(parse_uint("0x10K"), Ok(16 << 10));
+        assert_eq!(parse_uint("0x"), Err(ParamError::Invalid));
+        assert_eq!(parse_uint("0xg"), Err(ParamError::Invalid));
+        assert_eq!(parse_uint("10x"), Err(ParamError::Invalid));
+    }
+
+    #[test]
+    fn test_parse_uint_overflow() {
+        assert_eq!(parse_uint("18446744073709551615"), Ok(u64::MAX));
+        assert_eq!(parse_uint("0xffffffffffffffff"), Ok(u64::MAX));
+        // 数字本身溢出按格式错误处理
+        assert_eq!(parse_uint("18446744073709551616"), Err(ParamError::Invalid));
+        assert_eq!(parse_uint("0x10000000000000000"), Err(ParamError::Invalid));
+        // 乘上后缀后溢出
+        assert_eq!(parse_uint("16777215K"), Ok(16777215 << 10));
+        assert_eq!(parse_uint("17179869184G"), Err(ParamError::OutOfRange));
+        assert_eq!(parse_uint("0x400000000G"), Err(ParamError::OutOfRange));
+    }
+
+    #[test]
+    fn test_parse_uint_rejects_junk() {
+        assert_eq!(parse_uint(""), Err(ParamError::Invalid));
+        assert_eq!(parse_uint("-1"), Err(ParamError::Invalid));
+        assert_eq!(parse_uint(" 1"), Err(ParamError::Invalid));
+        assert_eq!(parse_uint("1.5M"), Err(ParamError::Invalid));
+    }
+
+    #[test]
+    fn test_uint_param_range() {
+        let param = UintParam::new("test", 8, 1, 1 << 20, "test option");
+        assert_eq!(param.set(Some("1M")), Ok(()));
+        assert_eq!(param.get(), 1 << 20);
+        assert_eq!(param.set(Some("2M")), Err(ParamError::OutOfRange));
+        assert_eq!(param.set(Some("0")), Err(ParamError::OutOfRange));
+        assert_eq!(param.set(None), Err(ParamError::MissingValue));
+        assert_eq!(param.get(), 1 << 20);
+    }
+
+    #[test]
+    fn test_split_keeps_quoted_values() {
+        let words: Vec<&str> = split("  a=1  b=\"x y\"\tc ").collect();
+        assert_eq!(words, ["a=1", "b=\"x y\"", "c"]);
+    }
+}
@@ -1,49 +1,134 @@
-//! 设备树(DTB)解析 - 用于ARM/RISC-V
+//! 设备树(DTB)解析 - 用于ARM/RISC-V，以及经 EFI 配置表传入 DTB 的 LoongArch
+//!
+//! 启动时用 `fdt::Fdt` 遍历 DTB：DTB 本身、memreserve 块、/reserved-memory
+//! 和 initrd 登记为保留内存，/memory 节点给出可用内存，已知设备的 MMIO 窗口
+//! 登记为设备内存。解析过的树保存在全局，驱动通过 `get()` 查询。
 
 use alloc::vec::Vec;
-use super::MemoryRegion;
+use spin::Mutex;
+use super::{MemoryRegion, RegionKind};
+use super::fdt::{Fdt, Node};
+use crate::mm::device::{self, DeviceKind};
+use crate::mm::reserved::{self, ReservedKind};
 
-const FDT_MAGIC: u32 = 0xd00dfeed;
-const FDT_BEGIN_NODE: u32 = 0x00000001;
-const FDT_END_NODE: u32 = 0x00000002;
-const FDT_PROP: u32 = 0x00000003;
-const FDT_END: u32 = 0x00000009;
+static DEVICE_TREE: Mutex<Option<Fdt<'static>>> = Mutex::new(None);
+
+/// 启动时解析的设备树（非设备树平台为 None）
+pub fn get() -> Option<Fdt<'static>> {
+    *DEVICE_TREE.lock()
+}
 
 pub fn parse(dtb_addr: *const u8) -> Vec<MemoryRegion> {
+    let fdt = match unsafe { Fdt::from_ptr(dtb_addr) } {
+        Ok(fdt) => fdt,
+        Err(e) => {
+            crate::println!("  [DTB] Invalid device tree at {:p}: {:?}", dtb_addr, e);
+            return Vec::new();
+        }
+    };
+
+    crate::println!("  [DTB] Valid device tree at {:p} ({} bytes)", dtb_addr, fdt.total_size());
+    *DEVICE_TREE.lock() = Some(fdt);
+    if let Some(args) = fdt.chosen().bootargs {
+        super::cmdline::set(args);
+    }
+
+    let mut regions = memory_regions(&fdt);
+    reserve_ranges(&fdt, &mut regions);
+    register_devices(&fdt);
+    report(&fdt);
+    regions
+}
+
+// DTB 本身、memreserve 块、/reserved-memory 与 initrd 都不能被分配出去；
+// 固件保留的部分同时记入内存图
+fn reserve_ranges(fdt: &Fdt, regions: &mut Vec<MemoryRegion>) {
+    reserved::reserve(fdt.addr(), fdt.total_size(), ReservedKind::DeviceTree);
+
+    for (addr, size) in fdt.memory_reservations() {
+        reserved::reserve(addr as usize, size as usize, ReservedKind::Firmware);
+        regions.push(MemoryRegion::new(addr as usize, size as usize, RegionKind::Reserved));
+    }
+
+    for r in fdt.reserved_memory() {
+        reserved::reserve(r.base as usize, r.size as usize, ReservedKind::Firmware);
+        regions.push(MemoryRegion::new(r.base as usize, r.size as usize, RegionKind::Reserved));
+        crate::println!("  [DTB] Reserved {} 0x{:x} + {}KB{}",
+                        r.name, r.base, r.size / 1024, if r.no_map { " (no-map)" } else { "" });
+    }
+
+    if let Some((start, end)) = fdt.chosen().initrd {
+        reserved::reserve(start as usize, (end - start) as usize, ReservedKind::Initrd);
+        super::modules::register(start as usize, end as usize, "initrd");
+        crate::println!("  [DTB] Initrd 0x{:x} - 0x{:x}", start, end);
+    }
+}
+
+// 每个 memory 节点的每一项 reg，标注 numa-node-id
+fn memory_regions(fdt: &Fdt) -> Vec<MemoryRegion> {
     let mut regions = Vec::new();
 
-    unsafe {
-        let magic = u32::from_be(*(dtb_addr as *const u32));
-        if magic != FDT_MAGIC {
-            crate::println!("  [DTB] Invalid magic: 0x{:x}", magic);
-            return regions;
+    for node in fdt.memory().filter(|n| n.is_enabled()) {
+        let numa = node.property("numa-node-id").and_then(|p| p.as_u32()).unwrap_or(0);
+        for reg in node.reg() {
+            let size = reg.size.unwrap_or(0);
+            if size == 0 {
+                continue;
+            }
+            regions.push(MemoryRegion {
+                node: numa,
+                ..MemoryRegion::new(reg.address as usize, size as usize, RegionKind::Usable)
+            });
+            crate::println!("  [DTB] Memory 0x{:016x} - 0x{:016x} ({}MB, node {})",
+                            reg.address, reg.address + size, size / (1024 * 1024), numa);
         }
+    }
 
-        crate::println!("  [DTB] Valid device tree at {:p}", dtb_addr);
+    if regions.is_empty() {
+        // 没有 memory 节点时退回常见 ARM/RISC-V 板子的默认值
+        regions.push(MemoryRegion::new(0x80000000, 256 * 1024 * 1024, RegionKind::Usable));
+        crate::println!("  [DTB] No memory node, assuming 0x80000000 + 256MB");
+    }
 
-        let totalsize = u32::from_be(*(dtb_addr.add(4) as *const u32));
-        let off_struct = u32::from_be(*(dtb_addr.add(8) as *const u32));
+    regions
+}
 
-        parse_memory_node(dtb_addr, off_struct as usize, &mut regions);
+// 把已知设备的 MMIO 窗口登记为设备内存
+fn register_devices(fdt: &Fdt) {
+    for node in fdt.nodes().filter(|n| n.is_enabled()) {
+        if let Some(kind) = device_kind(&node) {
+            for reg in node.reg() {
+                device::register(reg.address as usize, reg.size.unwrap_or(0) as usize, kind);
+            }
+        }
     }
+}
 
-    regions
+// compatible 中任一项匹配即可
+fn device_kind(node: &Node) -> Option<DeviceKind> {
+    node.compatible().find_map(|c| match c {
+        "ns16550a" | "ns16550" | "arm,pl011" | "snps,dw-apb-uart" => Some(DeviceKind::Uart),
+        "riscv,plic0" | "sifive,plic-1.0.0" | "riscv,aplic" | "riscv,imsics"
+        | "arm,gic-v3" | "arm,cortex-a15-gic" | "arm,gic-400" => Some(DeviceKind::InterruptController),
+        "pci-host-ecam-generic" => Some(DeviceKind::PciConfig),
+        "simple-framebuffer" => Some(DeviceKind::Framebuffer),
+        "riscv,clint0" | "sifive,clint0" | "arm,pl031" | "google,goldfish-rtc"
+        | "virtio,mmio" => Some(DeviceKind::Other),
+        _ => None,
+    })
 }
 
-unsafe fn parse_memory_node(
-    dtb: *const u8,
-    struct_offset: usize,
-    regions: &mut Vec<MemoryRegion>
-) {
-    // 简化实现：查找/memory节点
-    // 完整实现需要遍历整个FDT结构
-
-    // 默认返回一些合理的内存区域（针对常见ARM/RISC-V板子）
-    regions.push(MemoryRegion {
-        base: 0x80000000, // RISC-V/ARM常见起始地址
-        size: 256 * 1024 * 1024, // 256MB
-        available: true,
-    });
-
-    crate::println!("  [DTB] Default memory: 0x80000000 + 256MB");
+fn report(fdt: &Fdt) {
+    if let Some(model) = fdt.root().and_then(|r| r.property("model")).and_then(|p| p.as_str()) {
+        crate::println!("  [DTB] Model: {}", model);
+    }
+
+    let chosen = fdt.chosen();
+    if let Some(path) = chosen.stdout_path {
+        crate::println!("  [DTB] Stdout: {}", path);
+    }
+
+    let cpus = fdt.cpus();
+    let enabled = cpus.iter().filter(|c| c.enabled).count();
+    crate::println!("  [DTB] {} CPUs ({} enabled), boot CPU {}", cpus.len(), enabled, fdt.boot_cpuid());
 }
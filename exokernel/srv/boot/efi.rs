@@ -0,0 +1,186 @@
+// src/boot/efi.rs
+//! UEFI 系统表与内存图
+//!
+//! LoongArch 引导协议经 a2 传入 EFI 系统表；内核需要的信息都在它的配置表里：
+//! - Linux 引导内存图（`efi_boot_memmap`，EFI 退出引导服务前的内存图）
+//! - 设备树（DTB）
+//! - initrd（`LINUX_EFI_INITRD_MEDIA_GUID`，基址与大小）
+//!
+//! 内存描述符的解析与 Multiboot2 EFI 内存图标签共用。
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use super::{MemoryRegion, RegionKind};
+use crate::mm::device::{self, DeviceKind};
+
+/// "IBI SYST"
+const SYSTEM_TABLE_SIGNATURE: u64 = 0x5453_5953_2049_4249;
+
+/// 内存描述符中的页大小
+const EFI_PAGE_SIZE: u64 = 4096;
+
+/// 配置表项：GUID(16) + 表指针(8)
+const CONFIG_ENTRY_LEN: usize = 24;
+
+/// 超过此数目的配置表视为损坏
+const MAX_CONFIG_TABLES: usize = 256;
+
+/// EFI GUID（按固件内存布局：前三段小端）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Guid([u8; 16]);
+
+impl Guid {
+    pub const fn new(d1: u32, d2: u16, d3: u16, d4: [u8; 8]) -> Self {
+        let a = d1.to_le_bytes();
+        let b = d2.to_le_bytes();
+        let c = d3.to_le_bytes();
+        Self([a[0], a[1], a[2], a[3], b[0], b[1], c[0], c[1],
+              d4[0], d4[1], d4[2], d4[3], d4[4], d4[5], d4[6], d4[7]])
+    }
+}
+
+/// Linux 引导内存图
+pub const BOOT_MEMMAP_GUID: Guid =
+    Guid::new(0x800f683f, 0xd08b, 0x423a, [0xa2, 0x93, 0x96, 0x5c, 0x3c, 0x6f, 0xe2, 0xb4]);
+/// 扁平设备树
+pub const DEVICE_TREE_GUID: Guid =
+    Guid::new(0xb1b621d5, 0xf19c, 0x41a5, [0x83, 0x0b, 0xd9, 0x15, 0x2c, 0x69, 0xaa, 0xe0]);
+/// initrd 的物理范围
+pub const INITRD_MEDIA_GUID: Guid =
+    Guid::new(0x5568e427, 0x68fc, 0x4f3d, [0xac, 0x74, 0xca, 0x55, 0x52, 0x31, 0xcc, 0x68]);
+
+#[repr(C)]
+struct MemoryDescriptor {
+    typ: u32,
+    _pad: u32,
+    phys_start: u64,
+    _virt_start: u64,
+    num_pages: u64,
+    _attribute: u64,
+}
+
+/// EFI 系统表中内核关心的部分
+#[derive(Debug, Clone)]
+pub struct SystemTable {
+    pub revision: u32,
+    pub vendor: String,
+    /// (GUID, 表地址)
+    pub config_tables: Vec<(Guid, usize)>,
+}
+
+impl SystemTable {
+    /// 解析系统表（签名不符时返回 None）
+    ///
+    /// # Safety
+    ///
+    /// addr 必须可读；系统表引用的字符串与配置表须可按物理地址直接访问
+    pub unsafe fn parse(addr: *const u8) -> Option<Self> {
+        if addr.is_null() || read_u64(addr, 0) != SYSTEM_TABLE_SIGNATURE {
+            return None;
+        }
+
+        let revision = read_u32(addr, 8);
+        let vendor = read_ucs2(read_u64(addr, 24) as *const u16);
+        let count = (read_u64(addr, 104) as usize).min(MAX_CONFIG_TABLES);
+        let tables = read_u64(addr, 112) as *const u8;
+
+        let mut config_tables = Vec::with_capacity(count);
+        if !tables.is_null() {
+            for i in 0..count {
+                let entry = tables.add(i * CONFIG_ENTRY_LEN);
+                let mut guid = [0u8; 16];
+                guid.copy_from_slice(core::slice::from_raw_parts(entry, 16));
+                config_tables.push((Guid(guid), read_u64(entry, 16) as usize));
+            }
+        }
+        Some(Self { revision, vendor, config_tables })
+    }
+
+    /// 按 GUID 查找配置表
+    pub fn find(&self, guid: &Guid) -> Option<usize> {
+        self.config_tables.iter().find(|(g, _)| g == guid).map(|&(_, addr)| addr)
+    }
+
+    /// 引导内存图中的全部区域
+    ///
+    /// # Safety
+    ///
+    /// 同 `parse`
+    pub unsafe fn memory_map(&self) -> Vec<MemoryRegion> {
+        let mut regions = Vec::new();
+        // efi_boot_memmap：map_size、desc_size、desc_ver(u32)、map_key、buff_size，随后是描述符
+        if let Some(addr) = self.find(&BOOT_MEMMAP_GUID) {
+            let map = addr as *const u8;
+            let map_size = read_u64(map, 0) as usize;
+            let desc_size = read_u64(map, 8) as usize;
+            parse_memory_map(map.add(40), map_size, desc_size, &mut regions);
+        }
+        regions
+    }
+
+    /// initrd 的物理范围 [start, end)
+    pub fn initrd(&self) -> Option<(usize, usize)> {
+        let addr = self.find(&INITRD_MEDIA_GUID)? as *const u8;
+        let (base, size) = unsafe { (read_u64(addr, 0) as usize, read_u64(addr, 8) as usize) };
+        (size > 0).then(|| (base, base + size))
+    }
+
+    pub fn device_tree(&self) -> Option<*const u8> {
+        self.find(&DEVICE_TREE_GUID).map(|addr| addr as *const u8)
+    }
+}
+
+/// 解析一段 EFI 内存描述符数组，追加到 regions
+///
+/// 描述符大小以固件给出的 desc_size 为准（可能大于结构体本身）；
+/// 设备窗口同时登记为设备内存
+///
+/// # Safety
+///
+/// [map, map + size) 必须可读
+pub unsafe fn parse_memory_map(map: *const u8, size: usize, desc_size: usize, regions: &mut Vec<MemoryRegion>) {
+    if desc_size < core::mem::size_of::<MemoryDescriptor>() {
+        return;
+    }
+
+    let mut off = 0;
+    while off + desc_size <= size {
+        let desc = core::ptr::read_unaligned(map.add(off) as *const MemoryDescriptor);
+        off += desc_size;
+        if desc.num_pages == 0 {
+            continue;
+        }
+
+        let kind = RegionKind::from_efi(desc.typ);
+        let bytes = desc.num_pages * EFI_PAGE_SIZE;
+        regions.push(MemoryRegion::new(desc.phys_start as usize, bytes as usize, kind));
+        if kind == RegionKind::Device {
+            device::register(desc.phys_start as usize, bytes as usize, DeviceKind::Other);
+        }
+    }
+}
+
+// 固件厂商名为以 0 结尾的 UCS-2 字符串；非 ASCII 字符显示为 '?'
+unsafe fn read_ucs2(mut ptr: *const u16) -> String {
+    let mut s = String::new();
+    if ptr.is_null() {
+        return s;
+    }
+    while s.len() < 64 {
+        let c = core::ptr::read_unaligned(ptr);
+        if c == 0 {
+            break;
+        }
+        s.push(if c < 0x80 { c as u8 as char } else { '?' });
+        ptr = ptr.add(1);
+    }
+    s
+}
+
+unsafe fn read_u32(base: *const u8, off: usize) -> u32 {
+    core::ptr::read_unaligned(base.add(off) as *const u32)
+}
+
+unsafe fn read_u64(base: *const u8, off: usize) -> u64 {
+    core::ptr::read_unaligned(base.add(off) as *const u64)
+}
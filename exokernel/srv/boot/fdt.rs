@@ -0,0 +1,838 @@
+// src/boot/fdt.rs
+//! 扁平设备树（FDT）解析
+//!
+//! 直接在 DTB 原始字节上工作，不复制也不分配：`Fdt`、`Node` 与 `Property`
+//! 都只是结构块/字符串块上的切片，可以随意复制。DTB 所在内存在启动时已被保留，
+//! 因此 `Fdt<'static>` 在内核整个生命周期内有效（见 `devicetree::get`）。
+//!
+//! 节点的 `reg` 按父节点的 `#address-cells`/`#size-cells` 解释（缺省 2/1），
+//! 不做 `ranges` 转换。
+
+use alloc::vec::Vec;
+
+const FDT_MAGIC: u32 = 0xd00dfeed;
+const FDT_BEGIN_NODE: u32 = 0x00000001;
+const FDT_END_NODE: u32 = 0x00000002;
+const FDT_PROP: u32 = 0x00000003;
+const FDT_NOP: u32 = 0x00000004;
+const FDT_END: u32 = 0x00000009;
+
+/// 头部长度（版本 17）
+const HEADER_LEN: usize = 40;
+/// 支持的最高“最后兼容版本”
+const LAST_COMPATIBLE_VERSION: u32 = 17;
+/// 遍历时记录 cells 的最大嵌套深度
+const MAX_DEPTH: usize = 16;
+
+/// DTB 解析错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FdtError {
+    /// 魔数不对
+    BadMagic(u32),
+    /// 头部声明的块超出 DTB 范围
+    Truncated,
+    /// 不兼容的格式版本
+    BadVersion(u32),
+}
+
+/// 节点的 #address-cells / #size-cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cells {
+    pub address: usize,
+    pub size: usize,
+}
+
+impl Cells {
+    /// 未声明时的缺省值
+    pub const DEFAULT: Cells = Cells { address: 2, size: 1 };
+}
+
+/// 一项 reg：地址与大小（size-cells 为 0 时没有大小）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegEntry {
+    pub address: u64,
+    pub size: Option<u64>,
+}
+
+/// 已验证头部的设备树
+#[derive(Clone, Copy)]
+pub struct Fdt<'a> {
+    blob: &'a [u8],
+    structs: &'a [u8],
+    strings: &'a [u8],
+    rsvmap: usize,
+    boot_cpuid: u32,
+}
+
+impl<'a> Fdt<'a> {
+    /// 从字节切片解析（切片至少包含头部声明的 totalsize）
+    pub fn new(blob: &'a [u8]) -> Result<Self, FdtError> {
+        let header = |i: usize| be32(blob, i * 4).ok_or(FdtError::Truncated);
+
+        let magic = header(0)?;
+        if magic != FDT_MAGIC {
+            return Err(FdtError::BadMagic(magic));
+        }
+        let total = header(1)? as usize;
+        if total < HEADER_LEN || total > blob.len() {
+            return Err(FdtError::Truncated);
+        }
+        let last_compatible = header(6)?;
+        if last_compatible > LAST_COMPATIBLE_VERSION {
+            return Err(FdtError::BadVersion(last_compatible));
+        }
+
+        let blob = &blob[..total];
+        let (off_struct, off_strings) = (header(2)? as usize, header(3)? as usize);
+        let (size_strings, size_struct) = (header(8)? as usize, header(9)? as usize);
+        let block = |off: usize, len: usize| {
+            off.checked_add(len).and_then(|end| blob.get(off..end)).ok_or(FdtError::Truncated)
+        };
+
+        Ok(Self {
+            blob,
+            structs: block(off_struct, size_struct)?,
+            strings: block(off_strings, size_strings)?,
+            rsvmap: header(4)? as usize,
+            boot_cpuid: header(7)?,
+        })
+    }
+
+    /// 从物理地址解析
+    ///
+    /// # Safety
+    ///
+    /// ptr 必须指向恒等映射、在内核生命周期内保持不变的 DTB
+    pub unsafe fn from_ptr(ptr: *const u8) -> Result<Fdt<'static>, FdtError> {
+        let magic = u32::from_be(core::ptr::read_unaligned(ptr as *const u32));
+        if magic != FDT_MAGIC {
+            return Err(FdtError::BadMagic(magic));
+        }
+        let total = u32::from_be(core::ptr::read_unaligned(ptr.add(4) as *const u32)) as usize;
+        Fdt::new(core::slice::from_raw_parts(ptr, total))
+    }
+
+    /// DTB 的物理地址
+    pub fn addr(&self) -> usize {
+        self.blob.as_ptr() as usize
+    }
+
+    /// DTB 总字节数
+    pub fn total_size(&self) -> usize {
+        self.blob.len()
+    }
+
+    /// 引导 CPU 的物理 ID
+    pub fn boot_cpuid(&self) -> u32 {
+        self.boot_cpuid
+    }
+
+    /// 根节点
+    pub fn root(&self) -> Option<Node<'a>> {
+        let mut off = 0;
+        loop {
+            let (token, next) = read_token(self.structs, off)?;
+            match token {
+                Token::BeginNode(name) => {
+                    return Some(Node { fdt: *self, name, body: next, depth: 0, parent_cells: Cells::DEFAULT });
+                }
+                Token::Nop => off = next,
+                _ => return None,
+            }
+        }
+    }
+
+    /// 按路径查找节点（如 "/chosen"、"/cpus/cpu@0"）
+    ///
+    /// 路径分量不带单元地址时，匹配第一个同名节点
+    pub fn find_node(&self, path: &str) -> Option<Node<'a>> {
+        let mut node = self.root()?;
+        for component in path.split('/').filter(|c| !c.is_empty()) {
+            node = node.children().find(|c| {
+                c.name() == component || (!component.contains('@') && c.base_name() == component)
+            })?;
+        }
+        Some(node)
+    }
+
+    /// 深度优先遍历所有节点（含根节点）
+    pub fn nodes(&self) -> NodeIter<'a> {
+        NodeIter { fdt: *self, off: 0, depth: 0, cells: [Cells::DEFAULT; MAX_DEPTH] }
+    }
+
+    /// 所有 compatible 中包含 compat 的节点
+    pub fn find_compatible<'b>(&self, compat: &'b str) -> impl Iterator<Item = Node<'a>> + 'b
+    where
+        'a: 'b,
+    {
+        self.nodes().filter(move |n| n.is_compatible(compat))
+    }
+
+    /// 按 phandle 查找节点
+    pub fn find_phandle(&self, phandle: u32) -> Option<Node<'a>> {
+        self.nodes().find(|n| n.phandle() == Some(phandle))
+    }
+
+    /// memreserve 块中的 (地址, 大小)
+    pub fn memory_reservations(&self) -> MemReserveIter<'a> {
+        MemReserveIter { blob: self.blob, off: self.rsvmap }
+    }
+
+    /// 根节点下的 memory 节点（device_type = "memory" 或名为 memory）
+    pub fn memory(&self) -> impl Iterator<Item = Node<'a>> {
+        self.root().into_iter().flat_map(|root| root.children()).filter(|n| {
+            n.device_type() == Some("memory") || n.base_name() == "memory"
+        })
+    }
+
+    /// /reserved-memory 下的各个区域（只含带 reg 的静态区域）
+    pub fn reserved_memory(&self) -> Vec<ReservedMemory<'a>> {
+        let mut result = Vec::new();
+        let parent = match self.find_node("/reserved-memory") {
+            Some(n) => n,
+            None => return result,
+        };
+        for child in parent.children().filter(|c| c.is_enabled()) {
+            let no_map = child.property("no-map").is_some();
+            for reg in child.reg() {
+                result.push(ReservedMemory {
+                    name: child.name(),
+                    base: reg.address,
+                    size: reg.size.unwrap_or(0),
+                    no_map,
+                });
+            }
+        }
+        result
+    }
+
+    /// /chosen 中的启动参数
+    pub fn chosen(&self) -> Chosen<'a> {
+        let chosen = match self.find_node("/chosen") {
+            Some(n) => n,
+            None => return Chosen::default(),
+        };
+        let str_prop = |name| chosen.property(name).and_then(|p| p.as_str());
+        let initrd = chosen.property("linux,initrd-start").and_then(|p| p.as_u64())
+            .zip(chosen.property("linux,initrd-end").and_then(|p| p.as_u64()))
+            .filter(|(start, end)| end > start);
+
+        Chosen {
+            bootargs: str_prop("bootargs").filter(|s| !s.is_empty()),
+            stdout_path: str_prop("stdout-path").or_else(|| str_prop("linux,stdout-path")),
+            initrd,
+        }
+    }
+
+    /// stdout-path 指向的控制台节点（去掉 `:115200` 之类的选项，解析 /aliases 中的别名）
+    pub fn stdout(&self) -> Option<Node<'a>> {
+        let path = self.chosen().stdout_path?;
+        let path = path.split(':').next().unwrap_or(path);
+        if path.starts_with('/') {
+            return self.find_node(path);
+        }
+        let target = self.find_node("/aliases")?.property(path)?.as_str()?;
+        self.find_node(target)
+    }
+
+    /// /cpus 下的 CPU 节点
+    pub fn cpus(&self) -> Vec<Cpu<'a>> {
+        let cpus = match self.find_node("/cpus") {
+            Some(n) => n,
+            None => return Vec::new(),
+        };
+        let timebase = cpus.property("timebase-frequency").and_then(|p| p.as_u64());
+
+        cpus.children()
+            .filter(|c| c.device_type() == Some("cpu") || c.base_name() == "cpu")
+            .map(|c| Cpu {
+                name: c.name(),
+                id: c.reg().next().map_or(0, |r| r.address),
+                enabled: c.is_enabled(),
+                compatible: c.compatible().next(),
+                numa_node: c.property("numa-node-id").and_then(|p| p.as_u32()),
+                timebase_frequency: c.property("timebase-frequency").and_then(|p| p.as_u64()).or(timebase),
+            })
+            .collect()
+    }
+
+    fn string(&self, off: usize) -> Option<&'a str> {
+        cstr(self.strings.get(off..)?)
+    }
+}
+
+/// /chosen 节点的内容
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Chosen<'a> {
+    /// 内核命令行
+    pub bootargs: Option<&'a str>,
+    /// 控制台设备路径（可能带 ":波特率" 后缀）
+    pub stdout_path: Option<&'a str>,
+    /// 初始内存盘 [start, end)
+    pub initrd: Option<(u64, u64)>,
+}
+
+/// /reserved-memory 下的一段静态保留区
+#[derive(Debug, Clone, Copy)]
+pub struct ReservedMemory<'a> {
+    pub name: &'a str,
+    pub base: u64,
+    pub size: u64,
+    /// 操作系统不得映射该区域
+    pub no_map: bool,
+}
+
+/// /cpus 下的一个 CPU
+#[derive(Debug, Clone, Copy)]
+pub struct Cpu<'a> {
+    pub name: &'a str,
+    /// 硬件 ID（MPIDR、hartid 等，取自 reg）
+    pub id: u64,
+    /// status 不是 disabled/fail
+    pub enabled: bool,
+    pub compatible: Option<&'a str>,
+    pub numa_node: Option<u32>,
+    pub timebase_frequency: Option<u64>,
+}
+
+/// 树中的一个节点
+#[derive(Clone, Copy)]
+pub struct Node<'a> {
+    fdt: Fdt<'a>,
+    name: &'a str,
+    /// 节点名之后第一个 token 在结构块中的偏移
+    body: usize,
+    depth: usize,
+    parent_cells: Cells,
+}
+
+impl<'a> Node<'a> {
+    /// 完整节点名（含单元地址，根节点为空串）
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// 去掉单元地址的节点名
+    pub fn base_name(&self) -> &'a str {
+        self.name.split('@').next().unwrap_or(self.name)
+    }
+
+    /// 单元地址（"@" 之后的部分）
+    pub fn unit_address(&self) -> Option<&'a str> {
+        self.name.split_once('@').map(|(_, unit)| unit)
+    }
+
+    /// 嵌套深度（根节点为 0）
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn properties(&self) -> PropertyIter<'a> {
+        PropertyIter { fdt: self.fdt, off: self.body }
+    }
+
+    pub fn property(&self, name: &str) -> Option<Property<'a>> {
+        self.properties().find(|p| p.name == name)
+    }
+
+    /// 直接子节点
+    pub fn children(&self) -> ChildIter<'a> {
+        ChildIter {
+            fdt: self.fdt,
+            off: self.body,
+            level: 0,
+            depth: self.depth + 1,
+            cells: self.cells(),
+            done: false,
+        }
+    }
+
+    /// 本节点为子节点声明的 cells
+    pub fn cells(&self) -> Cells {
+        let read = |name| self.property(name).and_then(|p| p.as_u32()).map(|v| v as usize);
+        Cells {
+            address: read("#address-cells").unwrap_or(Cells::DEFAULT.address),
+            size: read("#size-cells").unwrap_or(Cells::DEFAULT.size),
+        }
+    }
+
+    /// 按父节点的 cells 解释 reg
+    pub fn reg(&self) -> RegIter<'a> {
+        RegIter {
+            data: self.property("reg").map_or(&[][..], |p| p.data),
+            cells: self.parent_cells,
+        }
+    }
+
+    /// compatible 列表（按优先级从高到低）
+    pub fn compatible(&self) -> StringList<'a> {
+        StringList { data: self.property("compatible").map_or(&[][..], |p| p.data) }
+    }
+
+    pub fn is_compatible(&self, compat: &str) -> bool {
+        self.compatible().any(|c| c == compat)
+    }
+
+    pub fn device_type(&self) -> Option<&'a str> {
+        self.property("device_type").and_then(|p| p.as_str())
+    }
+
+    /// status 缺省或为 "okay"/"ok"
+    pub fn is_enabled(&self) -> bool {
+        match self.property("status").and_then(|p| p.as_str()) {
+            None => true,
+            Some(status) => status == "okay" || status == "ok",
+        }
+    }
+
+    pub fn phandle(&self) -> Option<u32> {
+        self.property("phandle")
+            .or_else(|| self.property("linux,phandle"))
+            .and_then(|p| p.as_u32())
+    }
+}
+
+impl core::fmt::Debug for Node<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Node").field("name", &self.name).field("depth", &self.depth).finish()
+    }
+}
+
+/// 节点属性
+#[derive(Debug, Clone, Copy)]
+pub struct Property<'a> {
+    pub name: &'a str,
+    pub data: &'a [u8],
+}
+
+impl<'a> Property<'a> {
+    /// 单个 u32 cell
+    pub fn as_u32(&self) -> Option<u32> {
+        be32(self.data, 0)
+    }
+
+    /// 一个或两个 cell 组成的整数
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.data.len() {
+            4 => self.as_u32().map(u64::from),
+            8 => Some(read_cells(self.data)),
+            _ => None,
+        }
+    }
+
+    /// 以 NUL 结尾的字符串（字符串列表时取第一项）
+    pub fn as_str(&self) -> Option<&'a str> {
+        cstr(self.data)
+    }
+
+    /// 以 NUL 分隔的字符串列表
+    pub fn strings(&self) -> StringList<'a> {
+        StringList { data: self.data }
+    }
+}
+
+/// 属性迭代器
+pub struct PropertyIter<'a> {
+    fdt: Fdt<'a>,
+    off: usize,
+}
+
+impl<'a> Iterator for PropertyIter<'a> {
+    type Item = Property<'a>;
+
+    fn next(&mut self) -> Option<Property<'a>> {
+        loop {
+            let (token, next) = read_token(self.fdt.structs, self.off)?;
+            match token {
+                Token::Prop { name_off, data } => {
+                    self.off = next;
+                    match self.fdt.string(name_off) {
+                        Some(name) => return Some(Property { name, data }),
+                        None => continue,
+                    }
+                }
+                Token::Nop => self.off = next,
+                // 属性总在子节点之前
+                _ => return None,
+            }
+        }
+    }
+}
+
+/// 直接子节点迭代器
+pub struct ChildIter<'a> {
+    fdt: Fdt<'a>,
+    off: usize,
+    /// 相对父节点的嵌套层数（0 表示位于父节点体内）
+    level: usize,
+    depth: usize,
+    cells: Cells,
+    done: bool,
+}
+
+impl<'a> Iterator for ChildIter<'a> {
+    type Item = Node<'a>;
+
+    fn next(&mut self) -> Option<Node<'a>> {
+        while !self.done {
+            let (token, next) = read_token(self.fdt.structs, self.off)?;
+            self.off = next;
+            match token {
+                Token::BeginNode(name) => {
+                    self.level += 1;
+                    if self.level == 1 {
+                        return Some(Node {
+                            fdt: self.fdt,
+                            name,
+                            body: next,
+                            depth: self.depth,
+                            parent_cells: self.cells,
+                        });
+                    }
+                }
+                Token::EndNode if self.level == 0 => self.done = true,
+                Token::EndNode => self.level -= 1,
+                Token::End => self.done = true,
+                Token::Prop { .. } | Token::Nop => {}
+            }
+        }
+        None
+    }
+}
+
+/// 深度优先的全树迭代器
+pub struct NodeIter<'a> {
+    fdt: Fdt<'a>,
+    off: usize,
+    depth: usize,
+    /// cells[d]：深度 d 的当前节点为其子节点声明的 cells
+    cells: [Cells; MAX_DEPTH],
+}
+
+impl<'a> Iterator for NodeIter<'a> {
+    type Item = Node<'a>;
+
+    fn next(&mut self) -> Option<Node<'a>> {
+        loop {
+            let (token, next) = read_token(self.fdt.structs, self.off)?;
+            self.off = next;
+            match token {
+                Token::BeginNode(name) => {
+                    let parent_cells = match self.depth {
+                        0 => Cells::DEFAULT,
+                        d => self.cells[(d - 1).min(MAX_DEPTH - 1)],
+                    };
+                    let node = Node { fdt: self.fdt, name, body: next, depth: self.depth, parent_cells };
+                    if self.depth < MAX_DEPTH {
+                        self.cells[self.depth] = node.cells();
+                    }
+                    self.depth += 1;
+                    return Some(node);
+                }
+                Token::EndNode => self.depth = self.depth.saturating_sub(1),
+                Token::End => return None,
+                Token::Prop { .. } | Token::Nop => {}
+            }
+        }
+    }
+}
+
+/// reg 项迭代器
+pub struct RegIter<'a> {
+    data: &'a [u8],
+    cells: Cells,
+}
+
+impl Iterator for RegIter<'_> {
+    type Item = RegEntry;
+
+    fn next(&mut self) -> Option<RegEntry> {
+        let (addr_len, size_len) = (self.cells.address * 4, self.cells.size * 4);
+        if addr_len == 0 || self.data.len() < addr_len + size_len {
+            return None;
+        }
+        let address = read_cells(&self.data[..addr_len]);
+        let size = (size_len > 0).then(|| read_cells(&self.data[addr_len..addr_len + size_len]));
+        self.data = &self.data[addr_len + size_len..];
+        Some(RegEntry { address, size })
+    }
+}
+
+/// 以 NUL 分隔的字符串列表
+pub struct StringList<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for StringList<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        while !self.data.is_empty() {
+            let len = self.data.iter().position(|&b| b == 0).unwrap_or(self.data.len());
+            let (s, rest) = self.data.split_at(len);
+            self.data = rest.get(1..).unwrap_or(&[]);
+            if let Ok(s) = core::str::from_utf8(s) {
+                if !s.is_empty() {
+                    return Some(s);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// memreserve 块迭代器：每项为两个大端 u64，以全零项结束
+pub struct MemReserveIter<'a> {
+    blob: &'a [u8],
+    off: usize,
+}
+
+impl Iterator for MemReserveIter<'_> {
+    type Item = (u64, u64);
+
+    fn next(&mut self) -> Option<(u64, u64)> {
+        let entry = self.blob.get(self.off..self.off + 16)?;
+        let (addr, size) = (read_cells(&entry[..8]), read_cells(&entry[8..]));
+        if addr == 0 && size == 0 {
+            return None;
+        }
+        self.off += 16;
+        Some((addr, size))
+    }
+}
+
+enum Token<'a> {
+    BeginNode(&'a str),
+    EndNode,
+    Prop { name_off: usize, data: &'a [u8] },
+    Nop,
+    End,
+}
+
+// 读取 off 处的 token，返回 (token, 下一个 token 的偏移)；越界或未知 token 时为 None
+fn read_token(structs: &[u8], off: usize) -> Option<(Token<'_>, usize)> {
+    let body = off.checked_add(4)?;
+    match be32(structs, off)? {
+        FDT_BEGIN_NODE => {
+            let rest = structs.get(body..)?;
+            let len = rest.iter().position(|&b| b == 0)?;
+            let name = core::str::from_utf8(&rest[..len]).ok()?;
+            Some((Token::BeginNode(name), align4(body + len + 1)))
+        }
+        FDT_END_NODE => Some((Token::EndNode, body)),
+        FDT_PROP => {
+            let len = be32(structs, body)? as usize;
+            let name_off = be32(structs, body + 4)? as usize;
+            let data = structs.get(body + 8..body + 8 + len)?;
+            Some((Token::Prop { name_off, data }, align4(body + 8 + len)))
+        }
+        FDT_NOP => Some((Token::Nop, body)),
+        FDT_END => Some((Token::End, body)),
+        _ => None,
+    }
+}
+
+fn be32(bytes: &[u8], off: usize) -> Option<u32> {
+    let b = bytes.get(off..off.checked_add(4)?)?;
+    Some(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_cells(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64)
+}
+
+fn cstr(bytes: &[u8]) -> Option<&str> {
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    core::str::from_utf8(&bytes[..len]).ok()
+}
+
+fn align4(off: usize) -> usize {
+    (off + 3) & !3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 按规范布局拼出最小的 DTB：头部、memreserve 块、结构块、字符串块
+    struct Builder {
+        structs: Vec<u8>,
+        strings: Vec<u8>,
+    }
+
+    impl Builder {
+        fn new() -> Self {
+            Self { structs: Vec::new(), strings: Vec::new() }
+        }
+
+        fn word(&mut self, v: u32) -> &mut Self {
+            self.structs.extend_from_slice(&v.to_be_bytes());
+            self
+        }
+
+        fn pad(&mut self) {
+            while self.structs.len() % 4 != 0 {
+                self.structs.push(0);
+            }
+        }
+
+        fn begin(&mut self, name: &str) -> &mut Self {
+            self.word(FDT_BEGIN_NODE);
+            self.structs.extend_from_slice(name.as_bytes());
+            self.structs.push(0);
+            self.pad();
+            self
+        }
+
+        fn end(&mut self) -> &mut Self {
+            self.word(FDT_END_NODE)
+        }
+
+        fn nop(&mut self) -> &mut Self {
+            self.word(FDT_NOP)
+        }
+
+        fn prop(&mut self, name: &str, data: &[u8]) -> &mut Self {
+            let name_off = self.strings.len() as u32;
+            self.strings.extend_from_slice(name.as_bytes());
+            self.strings.push(0);
+            self.word(FDT_PROP).word(data.len() as u32).word(name_off);
+            self.structs.extend_from_slice(data);
+            self.pad();
+            self
+        }
+
+        fn prop_u32s(&mut self, name: &str, values: &[u32]) -> &mut Self {
+            let data: Vec<u8> = values.iter().flat_map(|v| v.to_be_bytes()).collect();
+            self.prop(name, &data)
+        }
+
+        fn finish(&mut self, reservations: &[(u64, u64)]) -> Vec<u8> {
+            self.word(FDT_END);
+            let rsvmap = HEADER_LEN;
+            let off_struct = rsvmap + (reservations.len() + 1) * 16;
+            let off_strings = off_struct + self.structs.len();
+            let total = off_strings + self.strings.len();
+
+            let mut blob = Vec::new();
+            for v in [
+                FDT_MAGIC, total as u32, off_struct as u32, off_strings as u32, rsvmap as u32,
+                17, 16, 0, self.strings.len() as u32, self.structs.len() as u32,
+            ] {
+                blob.extend_from_slice(&v.to_be_bytes());
+            }
+            for &(addr, size) in reservations.iter().chain([(0, 0)].iter()) {
+                blob.extend_from_slice(&addr.to_be_bytes());
+                blob.extend_from_slice(&size.to_be_bytes());
+            }
+            blob.extend_from_slice(&self.structs);
+            blob.extend_from_slice(&self.strings);
+            blob
+        }
+    }
+
+    fn sample() -> Vec<u8> {
+        let mut b = Builder::new();
+        b.begin("")
+            .prop_u32s("#address-cells", &[2])
+            .nop()
+            .prop_u32s("#size-cells", &[2])
+            .begin("memory@80000000")
+            .prop("device_type", b"memory\0")
+            .nop()
+            .prop_u32s("reg", &[0, 0x8000_0000, 0, 0x1000_0000, 0x1, 0, 0, 0x2000_0000])
+            .end()
+            .nop()
+            .begin("cpus")
+            .prop_u32s("#address-cells", &[1])
+            .prop_u32s("#size-cells", &[0])
+            .begin("cpu@0")
+            .prop("device_type", b"cpu\0")
+            .prop_u32s("reg", &[0])
+            .end()
+            .nop()
+            .begin("cpu@1")
+            .prop("device_type", b"cpu\0")
+            .prop_u32s("reg", &[1])
+            .prop("status", b"disabled\0")
+            .end()
+            .end()
+            .end();
+        b.finish(&[(0x4000_0000, 0x1000)])
+    }
+
+    #[test]
+    fn test_parses_sample_tree() {
+        let blob = sample();
+        let fdt = Fdt::new(&blob).expect("valid DTB");
+        assert_eq!(fdt.total_size(), blob.len());
+        assert_eq!(fdt.nodes().count(), 5);
+        assert_eq!(fdt.memory_reservations().collect::<Vec<_>>(), [(0x4000_0000, 0x1000)]);
+    }
+
+    #[test]
+    fn test_truncated_blobs_are_rejected() {
+        let blob = sample();
+
+        // 头部不完整
+        assert_eq!(Fdt::new(&blob[..HEADER_LEN - 4]).err(), Some(FdtError::Truncated));
+        // totalsize 超出切片
+        assert_eq!(Fdt::new(&blob[..blob.len() - 1]).err(), Some(FdtError::Truncated));
+
+        // 结构块声明的大小越过 totalsize
+        let mut bad = blob.clone();
+        let size_struct = u32::from_be_bytes([bad[36], bad[37], bad[38], bad[39]]);
+        bad[36..40].copy_from_slice(&(size_struct + 64).to_be_bytes());
+        assert_eq!(Fdt::new(&bad).err(), Some(FdtError::Truncated));
+
+        let mut bad = blob.clone();
+        bad[0] = 0;
+        assert!(matches!(Fdt::new(&bad), Err(FdtError::BadMagic(_))));
+    }
+
+    #[test]
+    fn test_truncated_struct_block_stops_iteration() {
+        let mut b = Builder::new();
+        b.begin("").begin("child");
+        // 属性长度越过结构块末尾：迭代在此停止，而不是越界读取
+        b.word(FDT_PROP).word(0x100).word(0);
+        let blob = b.finish(&[]);
+        let fdt = Fdt::new(&blob).expect("header is valid");
+        assert_eq!(fdt.nodes().count(), 2);
+        assert!(fdt.find_node("/child").expect("child").properties().next().is_none());
+    }
+
+    #[test]
+    fn test_nop_tokens_are_skipped() {
+        let blob = sample();
+        let fdt = Fdt::new(&blob).unwrap();
+        let root = fdt.root().unwrap();
+        assert_eq!(root.cells(), Cells { address: 2, size: 2 });
+        assert_eq!(root.children().count(), 2);
+
+        let memory = fdt.find_node("/memory").expect("memory node");
+        assert_eq!(memory.device_type(), Some("memory"));
+        assert_eq!(fdt.memory().count(), 1);
+
+        let cpus = fdt.cpus();
+        assert_eq!(cpus.len(), 2);
+        assert!(cpus[0].enabled);
+        assert!(!cpus[1].enabled);
+        assert_eq!(cpus[1].id, 1);
+    }
+
+    #[test]
+    fn test_multi_entry_reg() {
+        let blob = sample();
+        let fdt = Fdt::new(&blob).unwrap();
+        let reg: Vec<RegEntry> = fdt.find_node("/memory@80000000").unwrap().reg().collect();
+        assert_eq!(reg, [
+            RegEntry { address: 0x8000_0000, size: Some(0x1000_0000) },
+            RegEntry { address: 0x1_0000_0000, size: Some(0x2000_0000) },
+        ]);
+
+        // #size-cells = 0 的节点只有地址
+        let cpu = fdt.find_node("/cpus/cpu@1").unwrap();
+        assert_eq!(cpu.reg().collect::<Vec<_>>(), [RegEntry { address: 1, size: None }]);
+    }
+}
@@ -1,19 +1,225 @@
 // src/boot/mod.rs
-//! 启动信息解析 - 支持Multiboot2和设备树
+//! 启动信息解析 - 支持Multiboot2、设备树和LoongArch的EFI系统表
 
 pub mod multiboot2;
 pub mod devicetree;
+pub mod fdt;
+pub mod modules;
+pub mod cmdline;
+pub mod acpi;
+pub mod efi;
 
 use alloc::vec::Vec;
 
+/// 内存区域类型
+///
+/// 声明顺序即优先级：区域重叠时，排在后面（更严格）的类型胜出
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RegionKind {
+    /// 可用 RAM
+    Usable,
+    /// 引导程序/固件启动服务使用过，进入内核后即可回收
+    BootloaderReclaimable,
+    /// ACPI 表，读完后可回收
+    AcpiReclaimable,
+    /// 固件保留
+    Reserved,
+    /// ACPI 非易失存储，必须保留
+    AcpiNvs,
+    /// MMIO 等设备窗口
+    Device,
+    /// 内核映像
+    Kernel,
+    /// 坏内存
+    BadMemory,
+}
+
+impl RegionKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            RegionKind::Usable => "usable",
+            RegionKind::BootloaderReclaimable => "bootloader",
+            RegionKind::AcpiReclaimable => "acpi-reclaim",
+            RegionKind::Reserved => "reserved",
+            RegionKind::AcpiNvs => "acpi-nvs",
+            RegionKind::Device => "device",
+            RegionKind::Kernel => "kernel",
+            RegionKind::BadMemory => "bad",
+        }
+    }
+
+    /// 能否交给物理页分配器（引导信息、模块等仍由 mm::reserved 排除）
+    pub fn is_allocatable(self) -> bool {
+        matches!(self, RegionKind::Usable | RegionKind::BootloaderReclaimable)
+    }
+
+    /// Multiboot2 内存图条目类型
+    pub fn from_multiboot(typ: u32) -> Self {
+        match typ {
+            1 => RegionKind::Usable,
+            3 => RegionKind::AcpiReclaimable,
+            4 => RegionKind::AcpiNvs,
+            5 => RegionKind::BadMemory,
+            _ => RegionKind::Reserved,
+        }
+    }
+
+    /// UEFI 内存描述符类型（EFI_MEMORY_TYPE）
+    pub fn from_efi(typ: u32) -> Self {
+        match typ {
+            // LoaderCode/Data、BootServicesCode/Data
+            1..=4 => RegionKind::BootloaderReclaimable,
+            7 => RegionKind::Usable,
+            8 => RegionKind::BadMemory,
+            9 => RegionKind::AcpiReclaimable,
+            10 => RegionKind::AcpiNvs,
+            // MemoryMappedIO、MemoryMappedIOPortSpace
+            11 | 12 => RegionKind::Device,
+            // Reserved、RuntimeServicesCode/Data、PalCode、PersistentMemory 等
+            _ => RegionKind::Reserved,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct MemoryRegion {
     pub base: usize,
     pub size: usize,
-    pub available: bool,
+    pub kind: RegionKind,
+    /// NUMA 节点（未知时为 0）
+    pub node: u32,
+}
+
+impl MemoryRegion {
+    pub fn new(base: usize, size: usize, kind: RegionKind) -> Self {
+        Self { base, size, kind, node: 0 }
+    }
+
+    pub fn end(&self) -> usize {
+        self.base + self.size
+    }
+}
+
+/// 一段物理内存所属的 NUMA 节点（来自 ACPI SRAT 或设备树 numa-node-id）
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryAffinity {
+    pub base: usize,
+    pub size: usize,
+    pub node: u32,
+}
+
+impl MemoryAffinity {
+    pub fn end(&self) -> usize {
+        self.base + self.size
+    }
+}
+
+/// 按亲和性范围切分内存区域并标注节点；未覆盖的部分保持原节点
+pub fn apply_affinity(regions: &[MemoryRegion], affinity: &[MemoryAffinity]) -> Vec<MemoryRegion> {
+    if affinity.is_empty() {
+        return regions.to_vec();
+    }
+
+    let mut sorted = affinity.to_vec();
+    sorted.sort_unstable_by_key(|a| a.base);
+
+    let mut out = Vec::new();
+    for r in regions {
+        let end = r.base + r.size;
+        let mut cursor = r.base;
+        for a in sorted.iter() {
+            if a.end() <= cursor || a.base >= end {
+                continue;
+            }
+            if a.base > cursor {
+                out.push(MemoryRegion { base: cursor, size: a.base - cursor, ..*r });
+                cursor = a.base;
+            }
+            let piece_end = a.end().min(end);
+            out.push(MemoryRegion { base: cursor, size: piece_end - cursor, node: a.node, ..*r });
+            cursor = piece_end;
+            if cursor >= end {
+                break;
+            }
+        }
+        if cursor < end {
+            out.push(MemoryRegion { base: cursor, size: end - cursor, ..*r });
+        }
+    }
+    out
+}
+
+/// 整理内存图：按页对齐、按优先级消解重叠、排序并合并相邻的同类区域
+///
+/// 可分配的区域向内收缩到整页，其余区域向外扩展，保证不会把半页保留内存交给分配器
+pub fn normalize(regions: &[MemoryRegion]) -> Vec<MemoryRegion> {
+    let page = crate::arch::PAGE_SIZE;
+    let aligned: Vec<MemoryRegion> = regions
+        .iter()
+        .filter_map(|r| {
+            let (base, end) = if r.kind.is_allocatable() {
+                ((r.base + page - 1) & !(page - 1), r.end() & !(page - 1))
+            } else {
+                (r.base & !(page - 1), (r.end() + page - 1) & !(page - 1))
+            };
+            (end > base).then(|| MemoryRegion { base, size: end - base, ..*r })
+        })
+        .collect();
+
+    let mut bounds: Vec<usize> = aligned.iter().flat_map(|r| [r.base, r.end()]).collect();
+    bounds.sort_unstable();
+    bounds.dedup();
+
+    // 每个基本区间取覆盖它的优先级最高的区域
+    let mut out: Vec<MemoryRegion> = Vec::new();
+    for w in bounds.windows(2) {
+        let (lo, hi) = (w[0], w[1]);
+        let winner = aligned
+            .iter()
+            .filter(|r| r.base <= lo && r.end() >= hi)
+            .max_by_key(|r| r.kind);
+        let winner = match winner {
+            Some(r) => r,
+            None => continue,
+        };
+
+        match out.last_mut() {
+            Some(last) if last.end() == lo && last.kind == winner.kind && last.node == winner.node => {
+                last.size += hi - lo;
+            }
+            _ => out.push(MemoryRegion { base: lo, size: hi - lo, ..*winner }),
+        }
+    }
+    out
+}
+
+/// 打印整理后的内存图、引导模块与平台信息
+pub fn report(regions: &[MemoryRegion]) {
+    if crate::console::log_enabled(crate::console::LOG_DEBUG) {
+        for r in regions {
+            crate::println!("  [BOOT] 0x{:016x} - 0x{:016x} {:>12} ({}KB, node {})",
+                            r.base, r.end(), r.kind.name(), r.size / 1024, r.node);
+        }
+    }
+    modules::report();
+    #[cfg(target_arch = "x86_64")]
+    acpi::report();
 }
 
 pub fn parse_boot_info(boot_info: *const u8) -> Vec<MemoryRegion> {
+    let mut regions = parse_firmware_map(boot_info);
+    if regions.is_empty() {
+        return regions;
+    }
+
+    let (kernel_start, kernel_end) = crate::arch::boot::kernel_phys_range();
+    regions.push(MemoryRegion::new(kernel_start, kernel_end - kernel_start, RegionKind::Kernel));
+
+    normalize(&regions)
+}
+
+// 各架构的原始内存图（未整理）
+fn parse_firmware_map(boot_info: *const u8) -> Vec<MemoryRegion> {
     if boot_info.is_null() {
         return Vec::new();
     }
@@ -30,7 +236,165 @@ pub fn parse_boot_info(boot_info: *const u8) -> Vec<MemoryRegion> {
 
     #[cfg(target_arch = "loongarch64")]
     {
-        // LoongArch通常使用UEFI或自定义格式
-        Vec::new()
+        parse_loongarch(boot_info)
+    }
+}
+
+// LoongArch 引导协议：a0 = 是否经 UEFI 引导，a1 = 命令行，a2 = EFI 系统表。
+// 系统表的配置表给出引导内存图、设备树与 initrd；a2 直接指向 DTB 时也接受
+#[cfg(target_arch = "loongarch64")]
+fn parse_loongarch(boot_info: *const u8) -> Vec<MemoryRegion> {
+    use crate::arch::boot::BootArgs;
+    use crate::mm::device::{self, DeviceKind};
+    use crate::mm::reserved::{self, ReservedKind};
+
+    let args = unsafe { &*(boot_info as *const BootArgs) };
+    let firmware = args.system_table as *const u8;
+    crate::println!("  [BOOT] LoongArch boot: efi={} cmdline=0x{:x} systab=0x{:x}",
+                    args.efi_boot, args.cmdline, args.system_table);
+
+    let systab = unsafe { efi::SystemTable::parse(firmware) };
+    let dtb = match &systab {
+        Some(st) => st.device_tree(),
+        None if !firmware.is_null() && unsafe { fdt::Fdt::from_ptr(firmware) }.is_ok() => Some(firmware),
+        None => None,
+    };
+    let mut regions = dtb.map(devicetree::parse).unwrap_or_default();
+
+    if let Some(st) = &systab {
+        crate::println!("  [EFI] System table rev {}.{} ({}), {} config tables",
+                        st.revision >> 16, st.revision & 0xffff, st.vendor, st.config_tables.len());
+        let efi_regions = unsafe { st.memory_map() };
+        if !efi_regions.is_empty() {
+            // EFI 内存图更细，设备树只补充其中的保留区域
+            crate::println!("  [MEM] EFI memory map: {} descriptors", efi_regions.len());
+            regions.retain(|r| !r.kind.is_allocatable());
+            regions.extend(efi_regions);
+        }
+        if let Some((start, end)) = st.initrd().filter(|_| modules::find("initrd").is_none()) {
+            reserved::reserve(start, end - start, ReservedKind::Initrd);
+            modules::register(start, end, "initrd");
+        }
+    }
+
+    // a1 的命令行优先于设备树的 bootargs
+    if args.cmdline != 0 {
+        let ptr = args.cmdline as *const u8;
+        let len = (0..4096).take_while(|&i| unsafe { *ptr.add(i) } != 0).count();
+        let bytes = unsafe { core::slice::from_raw_parts(ptr, len) };
+        if let Ok(s) = core::str::from_utf8(bytes).map(str::trim) {
+            if !s.is_empty() {
+                cmdline::set(s);
+            }
+        }
+    }
+
+    // 控制台：设备树 stdout-path 指向的 UART，否则用默认的 UART0
+    let console = devicetree::get()
+        .and_then(|fdt| fdt.stdout())
+        .and_then(|node| node.reg().next())
+        .map(|reg| reg.address as usize);
+    if let Some(addr) = console.filter(|&a| a != crate::arch::uart::base()) {
+        unsafe { crate::arch::uart::set_base(addr); }
+    }
+    let uart = crate::arch::uart::base();
+    device::register(uart & !(crate::arch::PAGE_SIZE - 1), crate::arch::PAGE_SIZE, DeviceKind::Uart);
+    crate::println!("  [BOOT] Console: ns16550a at 0x{:x}", uart);
+
+    regions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use RegionKind::*;
+
+    const P: usize = crate::arch::PAGE_SIZE;
+
+    fn region(base: usize, end: usize, kind: RegionKind) -> MemoryRegion {
+        MemoryRegion::new(base, end - base, kind)
+    }
+
+    fn affinity(base: usize, end: usize, node: u32) -> MemoryAffinity {
+        MemoryAffinity { base, size: end - base, node }
+    }
+
+    fn layout(regions: &[MemoryRegion]) -> Vec<(usize, usize, RegionKind, u32)> {
+        regions.iter().map(|r| (r.base, r.end(), r.kind, r.node)).collect()
+    }
+
+    #[test]
+    fn test_normalize_overlapping_kinds() {
+        let map = [
+            region(14 * P, 20 * P, Usable),
+            region(0, 16 * P, Usable),
+            region(5 * P, 8 * P, Kernel),
+            // 未对齐的保留区向外扩展到整页
+            region(4 * P + 100, 6 * P, Reserved),
+            // 未对齐的可回收区向内收缩到整页
+            region(20 * P + 10, 24 * P + 10, BootloaderReclaimable),
+            // 不足一页的可用区被丢弃
+            region(30 * P + 1, 31 * P - 1, Usable),
+        ];
+        assert_eq!(layout(&normalize(&map)), [
+            (0, 4 * P, Usable, 0),
+            (4 * P, 5 * P, Reserved, 0),
+            (5 * P, 8 * P, Kernel, 0),
+            (8 * P, 20 * P, Usable, 0),
+            (21 * P, 24 * P, BootloaderReclaimable, 0),
+        ]);
+    }
+
+    #[test]
+    fn test_normalize_stricter_kind_wins() {
+        let map = [
+            region(0, 8 * P, Reserved),
+            region(2 * P, 4 * P, Usable),
+            region(3 * P, 12 * P, BadMemory),
+            region(10 * P, 16 * P, AcpiReclaimable),
+        ];
+        assert_eq!(layout(&normalize(&map)), [
+            (0, 3 * P, Reserved, 0),
+            (3 * P, 12 * P, BadMemory, 0),
+            (12 * P, 16 * P, AcpiReclaimable, 0),
+        ]);
+    }
+
+    #[test]
+    fn test_apply_affinity_splits_regions() {
+        let map = [region(0, 16 * P, Usable), region(16 * P, 20 * P, Reserved)];
+        // 故意乱序，且一段亲和性跨越两个区域
+        let numa = [affinity(8 * P, 18 * P, 1), affinity(0, 4 * P, 2)];
+        let split = apply_affinity(&map, &numa);
+        assert_eq!(layout(&split), [
+            (0, 4 * P, Usable, 2),
+            (4 * P, 8 * P, Usable, 0),
+            (8 * P, 16 * P, Usable, 1),
+            (16 * P, 18 * P, Reserved, 1),
+            (18 * P, 20 * P, Reserved, 0),
+        ]);
+
+        // 相邻的同类区域只在节点相同时合并
+        let merged = normalize(&split);
+        assert_eq!(layout(&merged), layout(&split));
+    }
+
+    #[test]
+    fn test_affinity_then_normalize_mixed_kinds() {
+        let map = [
+            region(0, 32 * P, Usable),
+            region(6 * P, 10 * P, Kernel),
+            region(12 * P, 14 * P, AcpiNvs),
+        ];
+        let numa = [affinity(0, 16 * P, 0), affinity(16 * P, 32 * P, 1)];
+        let regions = normalize(&apply_affinity(&map, &numa));
+        assert_eq!(layout(&regions), [
+            (0, 6 * P, Usable, 0),
+            (6 * P, 10 * P, Kernel, 0),
+            (10 * P, 12 * P, Usable, 0),
+            (12 * P, 14 * P, AcpiNvs, 0),
+            (14 * P, 16 * P, Usable, 0),
+            (16 * P, 32 * P, Usable, 1),
+        ]);
     }
 }
@@ -0,0 +1,101 @@
+// src/boot/modules.rs
+//! 引导模块（Multiboot2 module2、设备树 initrd）
+//!
+//! 引导程序把 LibOS 镜像等文件放进内存，并传入它们的物理范围与命令行。
+//! 模块内存在解析时已登记为保留（`ReservedKind::Module`），不会进入物理页分配器；
+//! 模块本身是 `ResourceType::BootModule` 资源（id 为模块序号），
+//! 内核按需以只读能力把它授予某个进程。
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use spin::Mutex;
+use crate::capability::{
+    ProcessId, ResourceId, CapabilityHandle, CapError, ScopeKind, access, lifetime, caps,
+    bind_resource_scoped,
+};
+
+/// 一个引导模块
+#[derive(Debug, Clone)]
+pub struct BootModule {
+    /// 物理范围 [start, end)
+    pub start: usize,
+    pub end: usize,
+    /// 引导程序传入的命令行（第一个词通常是模块路径）
+    pub cmdline: String,
+}
+
+impl BootModule {
+    pub fn size(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// 模块名：命令行第一个词去掉目录部分
+    pub fn name(&self) -> &str {
+        let path = self.cmdline.split_whitespace().next().unwrap_or("");
+        path.rsplit('/').next().unwrap_or(path)
+    }
+
+    /// 模块名之后的参数
+    pub fn args(&self) -> &str {
+        let cmdline = self.cmdline.trim_start();
+        cmdline.split_once(char::is_whitespace).map_or("", |(_, rest)| rest.trim_start())
+    }
+}
+
+static MODULES: Mutex<Vec<BootModule>> = Mutex::new(Vec::new());
+
+/// 登记一个模块，返回其序号
+pub fn register(start: usize, end: usize, cmdline: &str) -> usize {
+    let mut modules = MODULES.lock();
+    modules.push(BootModule { start, end: end.max(start), cmdline: String::from(cmdline) });
+    modules.len() - 1
+}
+
+pub fn count() -> usize {
+    MODULES.lock().len()
+}
+
+pub fn get(index: usize) -> Option<BootModule> {
+    MODULES.lock().get(index).cloned()
+}
+
+/// 按模块名查找，返回 (序号, 模块)
+pub fn find(name: &str) -> Option<(usize, BootModule)> {
+    let modules = MODULES.lock();
+    modules.iter().enumerate().find(|(_, m)| m.name() == name).map(|(i, m)| (i, m.clone()))
+}
+
+/// 所有模块的副本
+pub fn list() -> Vec<BootModule> {
+    MODULES.lock().clone()
+}
+
+/// 模块对应的资源 ID
+pub fn resource_id(index: usize) -> ResourceId {
+    ResourceId::from_boot_module(index)
+}
+
+/// 把模块以只读（可执行、可映射）能力授予 pid
+pub fn grant(pid: ProcessId, index: usize)
+             -> Result<CapabilityHandle<access::ReadOnly, lifetime::Process>, CapError>
+{
+    if index >= count() {
+        return Err(CapError::ResourceNotFound);
+    }
+    bind_resource_scoped(pid, resource_id(index), caps::READ | caps::EXECUTE | caps::MAP, ScopeKind::Process)
+}
+
+/// 模块内容
+///
+/// 模块内存已被保留且恒等映射，在内核生命周期内不会被改写
+pub fn bytes(index: usize) -> Option<&'static [u8]> {
+    get(index).map(|m| unsafe { core::slice::from_raw_parts(m.start as *const u8, m.size()) })
+}
+
+/// 打印模块列表
+pub fn report() {
+    for (i, m) in MODULES.lock().iter().enumerate() {
+        crate::println!("  [BOOT] Module {}: 0x{:x} - 0x{:x} ({}KB) {}",
+                        i, m.start, m.end, m.size() / 1024, m.cmdline);
+    }
+}
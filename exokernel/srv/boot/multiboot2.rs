@@ -2,11 +2,19 @@
 //! 解析 Multiboot2 引导信息
 
 use alloc::vec::Vec;
-use super::MemoryRegion;
+use super::{acpi, efi, modules, MemoryRegion, RegionKind};
+use crate::mm::device::{self, DeviceKind};
+use crate::mm::reserved::{self, ReservedKind};
 
 const MULTIBOOT2_TAG_END: u32 = 0;
+const MULTIBOOT2_TAG_CMDLINE: u32 = 1;
+const MULTIBOOT2_TAG_MODULE: u32 = 3;
 const MULTIBOOT2_TAG_MMAP: u32 = 6;
 const MULTIBOOT2_TAG_BOOTLOADER_NAME: u32 = 2;
+const MULTIBOOT2_TAG_FRAMEBUFFER: u32 = 8;
+const MULTIBOOT2_TAG_ACPI_OLD: u32 = 14;
+const MULTIBOOT2_TAG_ACPI_NEW: u32 = 15;
+const MULTIBOOT2_TAG_EFI_MMAP: u32 = 17;
 
 #[repr(C)]
 struct Multiboot2Tag {
@@ -24,12 +32,17 @@ struct Multiboot2MmapEntry {
 
 pub fn parse(info_addr: *const u8) -> Vec<MemoryRegion> {
     let mut regions = Vec::new();
+    let mut efi_regions = Vec::new();
+    let mut rsdp = None;
 
     unsafe {
         let total_size = *(info_addr as *const u32);
         let mut tag_addr = info_addr.add(8); // 跳过总大小和保留字段
         let end_addr = info_addr.add(total_size as usize);
 
+        // 引导信息结构本身不能被分配出去
+        reserved::reserve(info_addr as usize, total_size as usize, ReservedKind::BootInfo);
+
         while tag_addr < end_addr {
             let tag = &*(tag_addr as *const Multiboot2Tag);
 
@@ -41,6 +54,31 @@ pub fn parse(info_addr: *const u8) -> Vec<MemoryRegion> {
                 parse_memory_map(tag_addr, &mut regions);
             }
 
+            if tag.typ == MULTIBOOT2_TAG_EFI_MMAP {
+                parse_efi_memory_map(tag_addr, &mut efi_regions);
+            }
+
+            if tag.typ == MULTIBOOT2_TAG_CMDLINE {
+                let bytes = core::slice::from_raw_parts(tag_addr.add(8), (tag.size as usize).saturating_sub(8));
+                let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+                super::cmdline::set(core::str::from_utf8(&bytes[..len]).unwrap_or(""));
+            }
+
+            if tag.typ == MULTIBOOT2_TAG_MODULE {
+                parse_module(tag_addr);
+            }
+
+            if tag.typ == MULTIBOOT2_TAG_FRAMEBUFFER {
+                parse_framebuffer(tag_addr);
+            }
+
+            // 标签体是 RSDP 的副本；新版（XSDT）优先
+            if tag.typ == MULTIBOOT2_TAG_ACPI_NEW
+                || (tag.typ == MULTIBOOT2_TAG_ACPI_OLD && rsdp.is_none())
+            {
+                rsdp = acpi::Rsdp::parse(tag_addr.add(8));
+            }
+
             if tag.typ == MULTIBOOT2_TAG_BOOTLOADER_NAME {
                 let name_ptr = tag_addr.add(8);
                 crate::println!("  [BOOT] Bootloader: {}",
@@ -54,7 +92,45 @@ pub fn parse(info_addr: *const u8) -> Vec<MemoryRegion> {
         }
     }
 
-    regions
+    // UEFI 引导时内存图由固件给出，比 GRUB 转换过的 e820 风格内存图更细
+    if !efi_regions.is_empty() {
+        regions = efi_regions;
+    }
+
+    // 没有 RSDP 标签时扫描 BIOS 区域
+    match unsafe { acpi::init(rsdp) } {
+        Some(platform) => super::apply_affinity(&regions, &platform.memory_affinity),
+        None => regions,
+    }
+}
+
+// 模块标签：mod_start(u32) mod_end(u32)，随后是以 NUL 结尾的命令行
+unsafe fn parse_module(tag_addr: *const u8) {
+    let mod_start = *(tag_addr.add(8) as *const u32) as usize;
+    let mod_end = *(tag_addr.add(12) as *const u32) as usize;
+    let tag_size = *(tag_addr.add(4) as *const u32) as usize;
+
+    let bytes = core::slice::from_raw_parts(tag_addr.add(16), tag_size.saturating_sub(16));
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    let cmdline = core::str::from_utf8(&bytes[..len]).unwrap_or("");
+
+    reserved::reserve(mod_start, mod_end.saturating_sub(mod_start), ReservedKind::Module);
+    modules::register(mod_start, mod_end, cmdline);
+}
+
+// 帧缓冲标签：addr(u64) pitch(u32) width(u32) height(u32) bpp(u8) type(u8)
+unsafe fn parse_framebuffer(tag_addr: *const u8) {
+    let addr = core::ptr::read_unaligned(tag_addr.add(8) as *const u64) as usize;
+    let pitch = *(tag_addr.add(16) as *const u32) as usize;
+    let width = *(tag_addr.add(20) as *const u32);
+    let height = *(tag_addr.add(24) as *const u32) as usize;
+    let bpp = *tag_addr.add(28);
+
+    // type 2 为 EGA 文本模式，地址仍是设备内存
+    if addr != 0 && pitch * height > 0 {
+        device::register(addr, pitch * height, DeviceKind::Framebuffer);
+        crate::println!("  [BOOT] Framebuffer: 0x{:x} {}x{}x{}", addr, width, height, bpp);
+    }
 }
 
 unsafe fn parse_memory_map(tag_addr: *const u8, regions: &mut Vec<MemoryRegion>) {
@@ -68,19 +144,26 @@ unsafe fn parse_memory_map(tag_addr: *const u8, regions: &mut Vec<MemoryRegion>)
     while entry_addr < end_addr {
         let entry = &*(entry_addr as *const Multiboot2MmapEntry);
 
-        if entry.typ == 1 && entry.length > 0 {
-            regions.push(MemoryRegion {
-                base: entry.base_addr as usize,
-                size: entry.length as usize,
-                available: true,
-            });
+        if entry.length > 0 {
+            let kind = RegionKind::from_multiboot(entry.typ);
+            regions.push(MemoryRegion::new(entry.base_addr as usize, entry.length as usize, kind));
 
-            crate::println!("  [MEM] 0x{:016x} - 0x{:016x} ({}MB)",
+            crate::println!("  [MEM] 0x{:016x} - 0x{:016x} ({}MB, {})",
                             entry.base_addr,
                             entry.base_addr + entry.length,
-                            entry.length / (1024 * 1024));
+                            entry.length / (1024 * 1024),
+                            kind.name());
         }
 
         entry_addr = entry_addr.add(entry_size as usize);
     }
 }
+
+// EFI 内存图标签：descriptor_size(u32) descriptor_version(u32)，随后是若干描述符
+unsafe fn parse_efi_memory_map(tag_addr: *const u8, regions: &mut Vec<MemoryRegion>) {
+    let tag_size = *(tag_addr.add(4) as *const u32) as usize;
+    let desc_size = *(tag_addr.add(8) as *const u32) as usize;
+    efi::parse_memory_map(tag_addr.add(16), tag_size.saturating_sub(16), desc_size, regions);
+
+    crate::println!("  [MEM] EFI memory map: {} descriptors", regions.len());
+}
@@ -23,6 +23,7 @@ static NEXT_PID: AtomicU32 = AtomicU32::new(1);
 
 pub fn init() {
     NEXT_PID.store(1, Ordering::Release);
+    resource::init();
 }
 
 pub fn allocate_pid() -> ProcessId {
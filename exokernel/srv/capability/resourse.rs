@@ -7,6 +7,8 @@
 //! - RAII：进程/线程/系统调用作用域退出时按创建顺序逆序撤销（确定性 Drop 顺序）
 
 use super::ProcessId;
+use crate::boot::cmdline::{self, UintParam};
+use crate::mm::physical::{call_site, CallSite};
 use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::vec::Vec;
 use core::marker::PhantomData;
@@ -78,6 +80,8 @@ pub mod caps {
     pub const TRANSFER: u32 = 1 << 5;
     pub const GRANT: u32 = 1 << 6;
     pub const REVOKE: u32 = 1 << 7;
+    /// 写时复制：共享期间不可直接写，首次写时复制出私有帧
+    pub const COW: u32 = 1 << 8;
     pub const ALL: u32 = 0xFF;
     pub const RW: u32 = READ | WRITE;
     pub const RO: u32 = READ;
@@ -94,6 +98,12 @@ pub enum ResourceType {
     DmaChannel = 4,
     Device = 5,
     IpcChannel = 6,
+    HugePage2M = 7,
+    HugePage1G = 8,
+    /// MMIO 设备窗口（id 为窗口物理基址）
+    DeviceMemory = 9,
+    /// 引导模块（id 为模块序号）
+    BootModule = 10,
     Custom = 255,
 }
 
@@ -108,12 +118,29 @@ impl ResourceId {
     pub fn resource_type(&self) -> ResourceType { self.typ }
     pub fn id(&self) -> u64 { self.id }
     pub fn from_page_addr(addr: usize) -> Self { Self::new(ResourceType::PhysicalPage, addr as u64) }
+    pub fn from_huge_2m_addr(addr: usize) -> Self { Self::new(ResourceType::HugePage2M, addr as u64) }
+    pub fn from_huge_1g_addr(addr: usize) -> Self { Self::new(ResourceType::HugePage1G, addr as u64) }
+    /// 物理帧类资源的字节大小（非帧资源为 None）
+    pub fn frame_size(&self) -> Option<usize> {
+        match self.typ {
+            ResourceType::PhysicalPage => Some(PAGE_4K),
+            ResourceType::HugePage2M => Some(PAGE_2M),
+            ResourceType::HugePage1G => Some(PAGE_1G),
+            _ => None,
+        }
+    }
     pub fn from_interrupt(irq: u8) -> Self { Self::new(ResourceType::Interrupt, irq as u64) }
     pub fn from_io_port(port: u16) -> Self { Self::new(ResourceType::IoPort, port as u64) }
+    pub fn from_device_addr(addr: usize) -> Self { Self::new(ResourceType::DeviceMemory, addr as u64) }
+    pub fn from_boot_module(index: usize) -> Self { Self::new(ResourceType::BootModule, index as u64) }
     #[inline(always)]
     pub fn fast_hash(&self) -> u64 { self.id.wrapping_mul(0x9e3779b97f4a7c15) ^ (self.typ as u64) }
 }
 
+const PAGE_4K: usize = 4096;
+const PAGE_2M: usize = 2 * 1024 * 1024;
+const PAGE_1G: usize = 1024 * 1024 * 1024;
+
 pub mod access {
     pub struct ReadOnly;
     pub struct Exclusive;
@@ -191,7 +218,7 @@ struct CapabilityEntry {
     capabilities: u32,
     generation: u32,
     state: SlotState,
-    _pad_cc: u8,           // reserved
+    frame_ref: u8,         // 1 = 持有帧引用（派生的页能力、bind_frame_owned）
     _pad1: [u8; 7],
     // 32B
     created_at: u64,
@@ -203,7 +230,7 @@ impl CapabilityEntry {
         Self {
             resource_id: ResourceId { id: 0, typ: ResourceType::Custom },
             owner_pid: 0, capabilities: 0, generation: 0,
-            state: SlotState::Free, _pad_cc: 0, _pad1: [0; 7],
+            state: SlotState::Free, frame_ref: 0, _pad1: [0; 7],
             created_at: 0, creation_order: 0, scope: ScopeKind::Permanent,
         }
     }
@@ -211,6 +238,18 @@ impl CapabilityEntry {
 
 const MAX_CAPABILITIES: usize = 8192;
 
+/// 可用的能力表槽位数（命令行 caps=，不超过 MAX_CAPABILITIES）
+pub static CAP_SLOTS: UintParam = UintParam::new(
+    "caps", MAX_CAPABILITIES as u64, 64, MAX_CAPABILITIES as u64, "capability table slots");
+
+pub fn register_params() {
+    cmdline::register(&CAP_SLOTS);
+}
+
+fn slot_limit() -> usize {
+    (CAP_SLOTS.get() as usize).min(MAX_CAPABILITIES)
+}
+
 // 真源：只读表
 static RO_DATA: RwLock<[CapabilityEntry; MAX_CAPABILITIES]> =
     RwLock::new([CapabilityEntry::empty(); MAX_CAPABILITIES]);
@@ -275,7 +314,8 @@ impl ResourceBorrowState {
     }
     fn try_exclusive(&mut self, cap_idx: u32, tid: ThreadId, scope: ScopeKind, caps_bits: u32, rty: ResourceType)
                      -> Result<(), CapError> {
-        let req = match rty { ResourceType::PhysicalPage|ResourceType::VirtualMemory => caps::WRITE|caps::MAP,
+        let req = match rty { ResourceType::PhysicalPage|ResourceType::HugePage2M|ResourceType::HugePage1G
+            |ResourceType::VirtualMemory|ResourceType::DeviceMemory => caps::WRITE|caps::MAP,
             ResourceType::Device|ResourceType::IoPort => caps::WRITE,
             _ => caps::WRITE };
         if (caps_bits & req) != req { return Err(CapError::PermissionDenied); }
@@ -336,6 +376,8 @@ pub enum CapError {
     AlreadyBorrowed,
     StillFrozen,
     NotFrozen,
+    /// 需要分配内存（如写时复制）但内存不足
+    OutOfMemory,
 }
 
 // ========== 初始化 ==========
@@ -343,8 +385,9 @@ pub enum CapError {
 pub fn init() {
     let mut wr = WR_DATA.lock();
     wr.free_slots.clear();
-    wr.free_slots.reserve(MAX_CAPABILITIES);
-    for i in (0..MAX_CAPABILITIES).rev() { wr.free_slots.push(i as u32); }
+    let slots = slot_limit();
+    wr.free_slots.reserve(slots);
+    for i in (0..slots).rev() { wr.free_slots.push(i as u32); }
     wr.quick_cache.clear();
     wr.process_caps.clear();
     wr.thread_caps.clear();
@@ -412,11 +455,14 @@ fn free_slot_locked(wr: &mut WriteData, ro: &mut [CapabilityEntry; MAX_CAPABILIT
 }
 
 // 若资源无借用且未挂起，则立即撤销；否则严格/延迟策略
+//
+// site 是发起撤销的调用位置，能力持有的帧在这里被释放时记在它名下
 fn revoke_one_locked(
     wr: &mut WriteData,
     ro: &mut [CapabilityEntry; MAX_CAPABILITIES],
     idx: u32,
     strict: bool,
+    site: CallSite,
 ) -> Result<(), CapError> {
     let e = ro[idx as usize]; // copy
     let rid = e.resource_id;
@@ -433,28 +479,41 @@ fn revoke_one_locked(
     scope_remove_idx(wr, e.scope, idx);
     unlink_graph_locked(wr, idx);
     free_slot_locked(wr, ro, idx);
+    if e.frame_ref != 0 {
+        if let Some((addr, pages)) = frame_span(&rid) {
+            unsafe { crate::mm::physical::put_frames_at(addr, pages, site); }
+        }
+    }
     Ok(())
 }
 
+// 页类资源对应的 (起始物理地址, 4KiB 页数)
+fn frame_span(rid: &ResourceId) -> Option<(usize, usize)> {
+    rid.frame_size().map(|bytes| (rid.id as usize, bytes / PAGE_4K))
+}
+
 // DFS 撤销（先子后父）
 fn revoke_dfs_locked(
     wr: &mut WriteData,
     ro: &mut [CapabilityEntry; MAX_CAPABILITIES],
     idx: u32,
     strict: bool,
+    site: CallSite,
 ) -> Result<(), CapError> {
     if (idx as usize) >= MAX_CAPABILITIES { return Ok(()); }
     if ro[idx as usize].state == SlotState::Free { return Ok(()); }
 
     let children = wr.children_of.get(&idx).cloned().unwrap_or_default();
     for c in children {
-        revoke_dfs_locked(wr, ro, c, strict)?;
+        revoke_dfs_locked(wr, ro, c, strict, site)?;
     }
-    revoke_one_locked(wr, ro, idx, strict)
+    revoke_one_locked(wr, ro, idx, strict, site)
 }
 
 // 借用释放后尝试完成延迟撤销
-fn try_complete_pending_for(wr: &mut WriteData, ro: &mut [CapabilityEntry; MAX_CAPABILITIES], rid: ResourceId) {
+fn try_complete_pending_for(
+    wr: &mut WriteData, ro: &mut [CapabilityEntry; MAX_CAPABILITIES], rid: ResourceId, site: CallSite,
+) {
     if let Some(list) = wr.pending_revoke.get_mut(&rid) {
         // 先检查是否仍有活跃借用
         if let Some(bs) = wr.resource_borrows.get(&rid) {
@@ -462,7 +521,7 @@ fn try_complete_pending_for(wr: &mut WriteData, ro: &mut [CapabilityEntry; MAX_C
         }
         let idxs = core::mem::take(list);
         for idx in idxs {
-            let _ = revoke_one_locked(wr, ro, idx, true); // 现在应能立即撤销
+            let _ = revoke_one_locked(wr, ro, idx, true, site); // 现在应能立即撤销
         }
         wr.pending_revoke.remove(&rid);
     }
@@ -495,34 +554,112 @@ pub fn bind_resource_scoped<A,S>(
     bind_internal::<A,S>(pid, rid, caps_bits, scope, creation, None)
 }
 
+/// 为 pid 刚分配的页帧绑定能力，由该能力接管分配引用
+///
+/// 此后撤销该能力（包括作用域结束、`on_process_exit`）即释放帧，调用者不再单独释放。
+/// 每个帧都必须由 pid 分配且没有其他持有者（否则 PermissionDenied）；
+/// 只供 `OwnedPage::into_process` 使用
+pub(crate) fn bind_frame_owned<A,S>(
+    pid: ProcessId, rid: ResourceId, caps_bits: u32, scope: ScopeKind,
+) -> Result<CapabilityHandle<A,S>, CapError> {
+    let (addr, pages) = frame_span(&rid).ok_or(CapError::Unsupported)?;
+    let creation = CREATION_SEQ.fetch_add(1, Ordering::Relaxed);
+    let mut wr = WR_DATA.lock();
+    let mut ro = RO_DATA.write();
+    if wr.quick_cache.get(&(pid.as_u32(), rid)).map_or(false, |v| !v.is_empty()) {
+        return Err(CapError::AlreadyBound);
+    }
+    let sole_owner = (0..pages).all(|i| {
+        crate::mm::physical::frame_info(addr + i * PAGE_4K)
+            .map_or(false, |f| f.owner == pid.as_u32() && !f.orphaned && f.refs == 1)
+    });
+    if !sole_owner { return Err(CapError::PermissionDenied); }
+    let idx = bind_locked(&mut wr, &mut ro, pid, rid, caps_bits, scope, creation, None)?;
+    ro[idx as usize].frame_ref = 1;
+    let e = ro[idx as usize];
+    Ok(CapabilityHandle::new(idx, e.generation, e.scope, e.creation_order))
+}
+
+/// 独占绑定：资源上已有任何进程的活跃能力时失败（AlreadyBound）
+///
+/// 检查与绑定在同一次加锁内完成，用于设备窗口等同一时刻只能有一个持有者的资源
+pub fn bind_resource_unique(pid: ProcessId, rid: ResourceId)
+                            -> Result<CapabilityHandle<access::Exclusive>, CapError>
+{
+    let creation = CREATION_SEQ.fetch_add(1, Ordering::Relaxed);
+    let mut wr = WR_DATA.lock();
+    let mut ro = RO_DATA.write();
+    let held = wr.quick_cache.iter()
+        .filter(|((_, r), _)| *r == rid)
+        .any(|(_, idxs)| idxs.iter().any(|&i| ro[i as usize].state != SlotState::Free));
+    if held { return Err(CapError::AlreadyBound); }
+    let idx = bind_locked(&mut wr, &mut ro, pid, rid, caps::RW | caps::MAP, ScopeKind::Process, creation, None)?;
+    let e = ro[idx as usize];
+    Ok(CapabilityHandle::new(idx, e.generation, e.scope, e.creation_order))
+}
+
 // 内部绑定；可指定父节点（授权）
 fn bind_internal<A,S>(
     pid: ProcessId, rid: ResourceId, caps_bits: u32, scope: ScopeKind, creation_order: u64, parent: Option<u32>,
 ) -> Result<CapabilityHandle<A,S>, CapError> {
     let mut wr = WR_DATA.lock();
+    let mut ro = RO_DATA.write();
+    let idx = bind_locked(&mut wr, &mut ro, pid, rid, caps_bits, scope, creation_order, parent)?;
+    let e = ro[idx as usize];
+    Ok(CapabilityHandle::new(idx, e.generation, e.scope, e.creation_order))
+}
+
+// 持锁绑定（调用者持有 WR_DATA 与 RO_DATA.write），返回表索引
+fn bind_locked(
+    wr: &mut WriteData, ro: &mut [CapabilityEntry; MAX_CAPABILITIES],
+    pid: ProcessId, rid: ResourceId, caps_bits: u32, scope: ScopeKind, creation_order: u64, parent: Option<u32>,
+) -> Result<u32, CapError> {
     let key = (pid.as_u32(), rid);
 
     if let Some(indices) = wr.quick_cache.get(&key) {
-        let ro = RO_DATA.read();
         for &idx in indices {
             let e = ro[idx as usize];
             if e.state == SlotState::Live && e.owner_pid == pid.as_u32() && e.resource_id == rid {
-                // 可在此升级权限（需要 RO 写锁）——此处保持只读以避免竞态
-                return Ok(CapabilityHandle::new(idx, e.generation, e.scope, e.creation_order));
+                // 已存在则复用（不在此处升级权限）
+                return Ok(idx);
             }
         }
     }
 
-    let idx = wr.free_slots.pop().ok_or(CapError::TableFull)?;
+    // 限制子节点数量（先检查，避免分配槽位后失败泄漏）
+    const MAX_CHILDREN_PER_CAP: usize = 32;
+    if let Some(p) = parent {
+        if wr.children_of.get(&p).map_or(false, |v| v.len() >= MAX_CHILDREN_PER_CAP) {
+            return Err(CapError::TooManyChildren);
+        }
+    }
+
+    // 派生的页能力各持有一个帧引用，帧在最后一个持有者离开后才释放
+    let frame_ref = match (parent, frame_span(&rid)) {
+        (Some(_), Some((addr, pages))) => {
+            if !crate::mm::physical::get_frames(addr, pages) { return Err(CapError::ResourceNotFound); }
+            true
+        }
+        _ => false,
+    };
+
+    let idx = match wr.free_slots.pop() {
+        Some(idx) => idx,
+        None => {
+            if let (true, Some((addr, pages))) = (frame_ref, frame_span(&rid)) {
+                unsafe { crate::mm::physical::put_frames(addr, pages); }
+            }
+            return Err(CapError::TableFull);
+        }
+    };
     let ts = GLOBAL_TIMESTAMP.fetch_add(1, Ordering::Relaxed);
 
     {
-        let mut ro = RO_DATA.write();
         let e = &mut ro[idx as usize];
         let gen = e.generation;
         *e = CapabilityEntry {
             resource_id: rid, owner_pid: pid.as_u32(), capabilities: caps_bits,
-            generation: gen, state: SlotState::Live, _pad_cc: 0, _pad1: [0; 7],
+            generation: gen, state: SlotState::Live, frame_ref: frame_ref as u8, _pad1: [0; 7],
             created_at: ts, creation_order, scope,
         };
     }
@@ -541,16 +678,50 @@ fn bind_internal<A,S>(
     }
 
     if let Some(p) = parent {
-        // 限制子节点数量
-        let v = wr.children_of.entry(p).or_default();
-        const MAX_CHILDREN_PER_CAP: usize = 32;
-        if v.len() >= MAX_CHILDREN_PER_CAP { return Err(CapError::TooManyChildren); }
-        v.push(idx);
+        wr.children_of.entry(p).or_default().push(idx);
         wr.parent_of.insert(idx, p);
     }
 
-    let ro = RO_DATA.read(); let e = ro[idx as usize];
-    Ok(CapabilityHandle::new(idx, e.generation, e.scope, e.creation_order))
+    Ok(idx)
+}
+
+// 持锁查找授权者的活跃能力，返回 (索引, 权限位)
+fn find_grantor_locked(
+    wr: &WriteData, ro: &[CapabilityEntry; MAX_CAPABILITIES], grantor_pid: ProcessId, rid: ResourceId,
+) -> Result<(u32, u32), CapError> {
+    let idxs = wr.quick_cache.get(&(grantor_pid.as_u32(), rid)).ok_or(CapError::ResourceNotFound)?;
+    for &idx in idxs {
+        let e = ro[idx as usize];
+        if e.state == SlotState::Live && e.owner_pid == grantor_pid.as_u32() && e.resource_id == rid {
+            if (e.capabilities & caps::GRANT) == 0 { return Err(CapError::PermissionDenied); }
+            return Ok((idx, e.capabilities));
+        }
+    }
+    Err(CapError::ResourceNotFound)
+}
+
+// 持锁授权：只读
+fn grant_readonly_locked(
+    wr: &mut WriteData, ro: &mut [CapabilityEntry; MAX_CAPABILITIES],
+    grantor_pid: ProcessId, grantee_pid: ProcessId, rid: ResourceId,
+) -> Result<u32, CapError> {
+    let (parent_idx, parent_caps) = find_grantor_locked(wr, ro, grantor_pid, rid)?;
+    // 只能授予自己拥有且可传播的权限（这里授予只读）
+    if (parent_caps & caps::READ) == 0 { return Err(CapError::PermissionDenied); }
+    bind_locked(wr, ro, grantee_pid, rid, caps::READ, ScopeKind::Process,
+                CREATION_SEQ.fetch_add(1, Ordering::Relaxed), Some(parent_idx))
+}
+
+// 持锁授权：独占
+fn grant_exclusive_locked(
+    wr: &mut WriteData, ro: &mut [CapabilityEntry; MAX_CAPABILITIES],
+    grantor_pid: ProcessId, grantee_pid: ProcessId, rid: ResourceId,
+) -> Result<u32, CapError> {
+    let (parent_idx, parent_caps) = find_grantor_locked(wr, ro, grantor_pid, rid)?;
+    let grantable = parent_caps & caps::TRANSFERABLE_MASK;
+    if (grantable & (caps::RW)) != (caps::RW) { return Err(CapError::PermissionDenied); }
+    bind_locked(wr, ro, grantee_pid, rid, caps::RW | caps::MAP, ScopeKind::Process,
+                CREATION_SEQ.fetch_add(1, Ordering::Relaxed), Some(parent_idx))
 }
 
 // ========== 授权与转移 ==========
@@ -559,86 +730,142 @@ pub fn grant_readonly(
     grantor_pid: ProcessId, grantee_pid: ProcessId, rid: ResourceId
 ) -> Result<CapabilityHandle<access::ReadOnly>, CapError> {
     let mut wr = WR_DATA.lock();
-    let key = (grantor_pid.as_u32(), rid);
-    let (parent_idx, parent_caps) = {
-        let ro = RO_DATA.read();
-        let idxs = wr.quick_cache.get(&key).cloned().ok_or(CapError::ResourceNotFound)?;
-        let mut found = None;
-        for idx in idxs {
-            let e = ro[idx as usize];
-            if e.state == SlotState::Live && e.owner_pid == grantor_pid.as_u32() && e.resource_id == rid {
-                if (e.capabilities & caps::GRANT) == 0 { return Err(CapError::PermissionDenied); }
-                found = Some((idx, e.capabilities)); break;
-            }
-        }
-        found.ok_or(CapError::ResourceNotFound)?
-    };
-    // 只能授予自己拥有且可传播的权限（这里授予只读）
-    if (parent_caps & caps::READ) == 0 { return Err(CapError::PermissionDenied); }
-    drop(wr);
-    bind_internal::<access::ReadOnly, lifetime::Process>(
-        grantee_pid, rid, caps::READ, ScopeKind::Process, CREATION_SEQ.fetch_add(1, Ordering::Relaxed), Some(parent_idx))
+    let mut ro = RO_DATA.write();
+    let idx = grant_readonly_locked(&mut wr, &mut ro, grantor_pid, grantee_pid, rid)?;
+    let e = ro[idx as usize];
+    Ok(CapabilityHandle::new(idx, e.generation, e.scope, e.creation_order))
+}
+
+/// 以写时复制方式共享：授权者暂时失去写权限，被授权者获得只读 + COW
+///
+/// 双方首次写入前都必须先复制出私有帧（见 `promote_cow`）
+pub fn grant_cow(
+    grantor_pid: ProcessId, grantee_pid: ProcessId, rid: ResourceId
+) -> Result<CapabilityHandle<access::Exclusive>, CapError> {
+    let mut wr = WR_DATA.lock();
+    let mut ro = RO_DATA.write();
+    let (parent_idx, parent_caps) = find_grantor_locked(&wr, &ro, grantor_pid, rid)?;
+    if (parent_caps & caps::READ) == 0 || rid.frame_size().is_none() { return Err(CapError::PermissionDenied); }
+    let idx = bind_locked(&mut wr, &mut ro, grantee_pid, rid, caps::READ | caps::MAP | caps::COW,
+                          ScopeKind::Process, CREATION_SEQ.fetch_add(1, Ordering::Relaxed), Some(parent_idx))?;
+    let p = &mut ro[parent_idx as usize];
+    p.capabilities = (p.capabilities & !caps::WRITE) | caps::COW;
+    let e = ro[idx as usize];
+    Ok(CapabilityHandle::new(idx, e.generation, e.scope, e.creation_order))
+}
+
+/// 写时复制的能力不再与他人共享时，清除 COW 并恢复写权限
+///
+/// 帧仍有其他持有者时不做修改并返回 false（调用者应复制出私有帧）。
+/// 持有者数在能力表锁内读取：新的共享者只能经 grant_cow 持同一把锁加入，判断不会过时
+pub fn promote_cow<A,S>(h: &CapabilityHandle<A,S>) -> Result<bool, CapError> {
+    fast_validate(h)?;
+    let _wr = WR_DATA.lock();
+    let mut ro = RO_DATA.write();
+    let e = &mut ro[h.index() as usize];
+    if (e.capabilities & caps::COW) == 0 { return Err(CapError::PermissionDenied); }
+    let (addr, _) = frame_span(&e.resource_id).ok_or(CapError::Unsupported)?;
+    if crate::mm::physical::frame_info(addr).map_or(1, |f| f.refs) > 1 {
+        return Ok(false);
+    }
+    e.capabilities = (e.capabilities & !caps::COW) | caps::WRITE;
+    Ok(true)
+}
+
+/// 能力当前的权限位
+pub fn capability_bits<A,S>(h: &CapabilityHandle<A,S>) -> Result<u32, CapError> {
+    fast_validate(h)?;
+    Ok(RO_DATA.read()[h.index() as usize].capabilities)
 }
 
 pub fn grant_exclusive(
     grantor_pid: ProcessId, grantee_pid: ProcessId, rid: ResourceId
 ) -> Result<CapabilityHandle<access::Exclusive>, CapError> {
     let mut wr = WR_DATA.lock();
-    let key = (grantor_pid.as_u32(), rid);
-    let (parent_idx, parent_caps) = {
-        let ro = RO_DATA.read();
-        let idxs = wr.quick_cache.get(&key).cloned().ok_or(CapError::ResourceNotFound)?;
-        let mut found = None;
-        for idx in idxs {
-            let e = ro[idx as usize];
-            if e.state == SlotState::Live && e.owner_pid == grantor_pid.as_u32() && e.resource_id == rid {
-                if (e.capabilities & caps::GRANT) == 0 { return Err(CapError::PermissionDenied); }
-                found = Some((idx, e.capabilities)); break;
-            }
-        }
-        found.ok_or(CapError::ResourceNotFound)?
-    };
-    let grantable = parent_caps & caps::TRANSFERABLE_MASK;
-    if (grantable & (caps::RW)) != (caps::RW) { return Err(CapError::PermissionDenied); }
-    drop(wr);
-    bind_internal::<access::Exclusive, lifetime::Process>(
-        grantee_pid, rid, caps::RW | caps::MAP, ScopeKind::Process, CREATION_SEQ.fetch_add(1, Ordering::Relaxed), Some(parent_idx))
+    let mut ro = RO_DATA.write();
+    let idx = grant_exclusive_locked(&mut wr, &mut ro, grantor_pid, grantee_pid, rid)?;
+    let e = ro[idx as usize];
+    Ok(CapabilityHandle::new(idx, e.generation, e.scope, e.creation_order))
 }
 
+/// 把 from_pid 对某资源的能力转移给 to_pid（整个过程在一次加锁内完成）
+///
+/// 页类资源连同帧一起转移：能力持有的帧引用随能力转给新能力；
+/// from_pid 是帧的分配者时，分配者同时改为 to_pid，此后由 to_pid 释放、计入 to_pid 的用量
+#[cfg_attr(feature = "debug-poison", track_caller)]
 pub fn transfer_resource(
     from_pid: ProcessId, to_pid: ProcessId, rid: ResourceId
 ) -> Result<(), CapError> {
     let mut wr = WR_DATA.lock();
-    let key = (from_pid.as_u32(), rid);
-    let idx = {
-        let ro = RO_DATA.read();
-        let idxs = wr.quick_cache.get(&key).cloned().ok_or(CapError::ResourceNotFound)?;
-        let mut found = None;
-        for i in idxs {
-            let e = ro[i as usize];
-            if e.state == SlotState::Live && e.owner_pid == from_pid.as_u32() && e.resource_id == rid {
-                if (e.capabilities & caps::TRANSFER) == 0 { return Err(CapError::PermissionDenied); }
-                found = Some(i); break;
-            }
+    let mut ro = RO_DATA.write();
+    let idxs = wr.quick_cache.get(&(from_pid.as_u32(), rid)).cloned().ok_or(CapError::ResourceNotFound)?;
+    let mut found = None;
+    for i in idxs {
+        let e = ro[i as usize];
+        if e.state == SlotState::Live && e.owner_pid == from_pid.as_u32() && e.resource_id == rid {
+            if (e.capabilities & caps::TRANSFER) == 0 { return Err(CapError::PermissionDenied); }
+            found = Some(i); break;
+        }
+    }
+    let idx = found.ok_or(CapError::ResourceNotFound)?;
+    let site = call_site();
+
+    // 先检查所有可能失败的条件，状态改动开始后不再半途返回：
+    // 资源上有借用时撤销会失败；to_pid 没有可复用的能力时需要一个空槽
+    if wr.resource_borrows.get(&rid).map_or(false, |bs| bs.has_active()) {
+        return Err(CapError::BorrowConflict);
+    }
+    let reusable = wr.quick_cache.get(&(to_pid.as_u32(), rid)).map_or(false, |v| v.iter().any(|&i| {
+        let e = ro[i as usize];
+        e.state == SlotState::Live && e.owner_pid == to_pid.as_u32()
+    }));
+    if !reusable && wr.free_slots.is_empty() {
+        return Err(CapError::TableFull);
+    }
+
+    // 分配引用随分配者转移（否则 to_pid 无法释放该帧，from_pid 退出或被回收时帧会被提前释放）；
+    // from_pid 不是分配者时不能转移
+    let span = frame_span(&rid);
+    if let Some((addr, pages)) = span {
+        if !crate::mm::physical::transfer_frames(addr, pages, from_pid.as_u32(), to_pid.as_u32()) {
+            return Err(CapError::PermissionDenied);
+        }
+    }
+    let undo_ownership = || {
+        if let Some((addr, pages)) = span {
+            crate::mm::physical::transfer_frames(addr, pages, to_pid.as_u32(), from_pid.as_u32());
         }
-        found.ok_or(CapError::ResourceNotFound)?
     };
-    // 剥离管理权限
-    let caps_new = {
-        let ro = RO_DATA.read(); ro[idx as usize].capabilities & caps::TRANSFERABLE_MASK
+
+    // 剥离管理权限（根据剩余权限选择只读或独占）
+    let caps_new = match ro[idx as usize].capabilities & caps::TRANSFERABLE_MASK {
+        bits if (bits & (caps::WRITE|caps::MAP)) == (caps::WRITE|caps::MAP) => caps::RW | caps::MAP,
+        _ => caps::READ,
     };
-    {
-        let mut ro = RO_DATA.write();
-        revoke_dfs_locked(&mut wr, &mut ro, idx, true)?;
+
+    // 帧引用随能力转移：撤销旧能力时不能放掉它
+    let frame_ref = ro[idx as usize].frame_ref;
+    ro[idx as usize].frame_ref = 0;
+    if let Err(e) = revoke_dfs_locked(&mut wr, &mut ro, idx, true, site) {
+        ro[idx as usize].frame_ref = frame_ref;
+        undo_ownership();
+        return Err(e);
     }
-    drop(wr);
-    // 为新进程建立独立能力（根据新权限选择只读或独占）
-    if (caps_new & (caps::WRITE|caps::MAP)) == (caps::WRITE|caps::MAP) {
-        let _ = bind_internal::<access::Exclusive, lifetime::Process>(
-            to_pid, rid, caps::RW | caps::MAP, ScopeKind::Process, CREATION_SEQ.fetch_add(1, Ordering::Relaxed), None)?;
-    } else {
-        let _ = bind_internal::<access::ReadOnly, lifetime::Process>(
-            to_pid, rid, caps::READ, ScopeKind::Process, CREATION_SEQ.fetch_add(1, Ordering::Relaxed), None)?;
+
+    let creation = CREATION_SEQ.fetch_add(1, Ordering::Relaxed);
+    match (bind_locked(&mut wr, &mut ro, to_pid, rid, caps_new, ScopeKind::Process, creation, None), span) {
+        // to_pid 已有持帧的能力时，多出的引用直接交回
+        (Ok(i), _) if ro[i as usize].frame_ref == 0 => ro[i as usize].frame_ref = frame_ref,
+        (Ok(_), Some((addr, pages))) if frame_ref != 0 => unsafe { crate::mm::physical::put_frames_at(addr, pages, site); },
+        (Ok(_), _) => {}
+        // 已预先确认有槽位，不应到达这里；旧能力已撤销，分配者还给 from_pid，帧引用随旧能力放掉
+        (Err(e), _) => {
+            undo_ownership();
+            if let (Some((addr, pages)), true) = (span, frame_ref != 0) {
+                unsafe { crate::mm::physical::put_frames_at(addr, pages, site); }
+            }
+            return Err(e);
+        }
     }
     Ok(())
 }
@@ -686,6 +913,7 @@ pub fn borrow_exclusive(
     bs.try_exclusive(h.index(), tid, borrow_scope, caps_bits, rty)
 }
 
+#[cfg_attr(feature = "debug-poison", track_caller)]
 pub fn release_shared(
     h: &CapabilityHandle<access::ReadOnly>, tid: ThreadId
 ) -> Result<(), CapError> {
@@ -696,10 +924,11 @@ pub fn release_shared(
     bs.release_shared(h.index(), tid)?;
     // 尝试完成延迟撤销
     let mut ro = RO_DATA.write();
-    try_complete_pending_for(&mut wr, &mut ro, e.resource_id);
+    try_complete_pending_for(&mut wr, &mut ro, e.resource_id, call_site());
     Ok(())
 }
 
+#[cfg_attr(feature = "debug-poison", track_caller)]
 pub fn release_shared_frozen(
     h: &CapabilityHandle<access::FrozenShared>, tid: ThreadId
 ) -> Result<(), CapError> {
@@ -709,10 +938,11 @@ pub fn release_shared_frozen(
     let bs = wr.resource_borrows.get_mut(&e.resource_id).ok_or(CapError::ResourceNotFound)?;
     bs.release_shared(h.index(), tid)?;
     let mut ro = RO_DATA.write();
-    try_complete_pending_for(&mut wr, &mut ro, e.resource_id);
+    try_complete_pending_for(&mut wr, &mut ro, e.resource_id, call_site());
     Ok(())
 }
 
+#[cfg_attr(feature = "debug-poison", track_caller)]
 pub fn release_exclusive(
     h: &CapabilityHandle<access::Exclusive>, tid: ThreadId
 ) -> Result<(), CapError> {
@@ -722,7 +952,7 @@ pub fn release_exclusive(
     let bs = wr.resource_borrows.get_mut(&e.resource_id).ok_or(CapError::ResourceNotFound)?;
     bs.release_exclusive(h.index(), tid)?;
     let mut ro = RO_DATA.write();
-    try_complete_pending_for(&mut wr, &mut ro, e.resource_id);
+    try_complete_pending_for(&mut wr, &mut ro, e.resource_id, call_site());
     Ok(())
 }
 
@@ -748,18 +978,97 @@ pub fn unfreeze_exclusive(
 
 // ========== 撤销（严格/延迟） ==========
 
+#[cfg_attr(feature = "debug-poison", track_caller)]
 pub fn revoke_capability<A,S>(h: &CapabilityHandle<A,S>) -> Result<(), CapError> {
+    revoke_capability_at(h, call_site()).map(|_| ())
+}
+
+/// 同 `revoke_capability`，释放的帧记在 site 名下（用于 Drop 等拿不到调用位置的地方）
+///
+/// 返回该能力是否持有帧引用：持有时撤销本身已放掉这份引用，调用者不应再按分配者身份释放
+pub fn revoke_capability_at<A,S>(h: &CapabilityHandle<A,S>, site: CallSite) -> Result<bool, CapError> {
+    fast_validate(h)?;
+    let mut wr = WR_DATA.lock();
+    let mut ro = RO_DATA.write();
+    let frame_ref = ro[h.index() as usize].frame_ref != 0;
+    revoke_dfs_locked(&mut wr, &mut ro, h.index(), true, site)?;
+    Ok(frame_ref)
+}
+
+/// 只放弃这一个能力；派生出的子能力成为独立的根并继续有效
+///
+/// 用于共享页：所有者离开后，被授权者仍可访问，帧在最后一个持有者离开时释放
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub fn release_capability<A,S>(h: &CapabilityHandle<A,S>) -> Result<(), CapError> {
+    release_capability_at(h, call_site()).map(|_| ())
+}
+
+/// 同 `release_capability`，释放的帧记在 site 名下
+///
+/// 返回值同 `revoke_capability_at`（延迟撤销时帧引用在撤销完成时放掉，同样不应再释放）
+pub fn release_capability_at<A,S>(h: &CapabilityHandle<A,S>, site: CallSite) -> Result<bool, CapError> {
     fast_validate(h)?;
     let mut wr = WR_DATA.lock();
     let mut ro = RO_DATA.write();
-    revoke_dfs_locked(&mut wr, &mut ro, h.index(), true)
+    let frame_ref = ro[h.index() as usize].frame_ref != 0;
+    revoke_one_locked(&mut wr, &mut ro, h.index(), false, site)?;
+    Ok(frame_ref)
 }
 
+#[cfg_attr(feature = "debug-poison", track_caller)]
 pub fn revoke_capability_deferred<A,S>(h: &CapabilityHandle<A,S>) -> Result<(), CapError> {
     fast_validate(h)?;
     let mut wr = WR_DATA.lock();
     let mut ro = RO_DATA.write();
-    revoke_dfs_locked(&mut wr, &mut ro, h.index(), false)
+    revoke_dfs_locked(&mut wr, &mut ro, h.index(), false, call_site())
+}
+
+/// 强制撤销 pid 持有的某资源的全部能力（含派生子能力），无视借用
+///
+/// 用于内存回收的中止协议：LibOS 未按期归还时由内核直接收回。
+/// 返回被撤销的根能力数量。
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub fn force_revoke_resource(pid: ProcessId, rid: ResourceId) -> usize {
+    let mut wr = WR_DATA.lock();
+    let mut ro = RO_DATA.write();
+    force_revoke_locked(&mut wr, &mut ro, pid, rid, call_site())
+}
+
+/// 强制撤销 pid 持有的、覆盖物理地址 addr 的页能力（4KiB、2MiB 或 1GiB 粒度）
+///
+/// 返回被撤销能力覆盖的 (起始地址, 4KiB 页数, 该能力是否持有帧引用)；
+/// 持有帧引用时撤销本身已放掉这份引用，调用者不应再按分配者身份释放。
+/// pid 没有覆盖该地址的页能力时返回 None
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub fn force_revoke_frame(pid: ProcessId, addr: usize) -> Option<(usize, usize, bool)> {
+    let mut wr = WR_DATA.lock();
+    let mut ro = RO_DATA.write();
+    let rid = [
+        ResourceId::from_page_addr(addr & !(PAGE_4K - 1)),
+        ResourceId::from_huge_2m_addr(addr & !(PAGE_2M - 1)),
+        ResourceId::from_huge_1g_addr(addr & !(PAGE_1G - 1)),
+    ].into_iter().find(|rid| wr.quick_cache.contains_key(&(pid.as_u32(), *rid)))?;
+    let held = wr.quick_cache[&(pid.as_u32(), rid)].iter()
+        .any(|&i| ro[i as usize].state != SlotState::Free && ro[i as usize].frame_ref != 0);
+    force_revoke_locked(&mut wr, &mut ro, pid, rid, call_site());
+    frame_span(&rid).map(|(base, pages)| (base, pages, held))
+}
+
+fn force_revoke_locked(
+    wr: &mut WriteData, ro: &mut [CapabilityEntry; MAX_CAPABILITIES], pid: ProcessId, rid: ResourceId, site: CallSite,
+) -> usize {
+    let idxs = wr.quick_cache.get(&(pid.as_u32(), rid)).cloned().unwrap_or_default();
+
+    // 作废该资源上的所有借用，之前挂起的延迟撤销随之完成
+    wr.resource_borrows.insert(rid, ResourceBorrowState::new());
+    try_complete_pending_for(wr, ro, rid, site);
+
+    let mut count = 0usize;
+    for idx in idxs {
+        if ro[idx as usize].state != SlotState::Free
+            && revoke_dfs_locked(wr, ro, idx, true, site).is_ok() { count += 1; }
+    }
+    count
 }
 
 // ========== 验证（快路径 + 回退） ==========
@@ -774,28 +1083,173 @@ pub fn verify_capability_fast(pid: ProcessId, rid: ResourceId, required: u32) ->
 }
 pub fn verify_capability(pid: ProcessId, rid: ResourceId, required: u32) -> bool {
     if verify_capability_fast(pid, rid, required) { return true; }
-    {
-        let wr = WR_DATA.lock();
-        if let Some(indices) = wr.quick_cache.get(&(pid.as_u32(), rid)) {
-            let ro = RO_DATA.read();
-            for &idx in indices {
-                let e = ro[idx as usize];
-                if e.state == SlotState::Live && e.owner_pid == pid.as_u32() && e.resource_id == rid
-                    && (e.capabilities & required) == required { return true; }
-            }
-        }
-    }
+    let wr = WR_DATA.lock();
     let ro = RO_DATA.read();
-    for e in ro.iter() {
-        if e.state == SlotState::Live && e.owner_pid == pid.as_u32() && e.resource_id == rid
-            && (e.capabilities & required) == required { return true; }
+    verify_locked(&wr, &ro, pid, rid, required)
+}
+
+// 持锁验证：先查 quick_cache，再全表回退
+fn verify_locked(
+    wr: &WriteData, ro: &[CapabilityEntry; MAX_CAPABILITIES], pid: ProcessId, rid: ResourceId, required: u32,
+) -> bool {
+    let live = |e: &CapabilityEntry| e.state == SlotState::Live && e.owner_pid == pid.as_u32()
+        && e.resource_id == rid && (e.capabilities & required) == required;
+    if let Some(indices) = wr.quick_cache.get(&(pid.as_u32(), rid)) {
+        if indices.iter().any(|&idx| live(&ro[idx as usize])) { return true; }
     }
-    false
+    ro.iter().any(live)
+}
+
+/// 验证对某物理地址的访问权限，理解大页粒度
+///
+/// 依次检查覆盖该地址的 4KiB 页、2MiB 大页和 1GiB 大页能力
+pub fn verify_page_access(pid: ProcessId, addr: usize, required: u32) -> bool {
+    verify_capability(pid, ResourceId::from_page_addr(addr & !(PAGE_4K - 1)), required)
+        || verify_capability(pid, ResourceId::from_huge_2m_addr(addr & !(PAGE_2M - 1)), required)
+        || verify_capability(pid, ResourceId::from_huge_1g_addr(addr & !(PAGE_1G - 1)), required)
+}
+
+// ========== 批量操作（单次加锁） ==========
+
+/// 批量能力操作（内核使用，见 `execute_batch`）
+#[derive(Debug, Clone, Copy)]
+pub enum CapOp {
+    /// 绑定资源（同 bind_resource_scoped；不检查 pid 对资源的权限）
+    Bind { pid: ProcessId, rid: ResourceId, caps: u32, scope: ScopeKind },
+    /// 只读授权（同 grant_readonly）
+    GrantReadOnly { grantor: ProcessId, grantee: ProcessId, rid: ResourceId },
+    /// 独占授权（同 grant_exclusive）
+    GrantExclusive { grantor: ProcessId, grantee: ProcessId, rid: ResourceId },
+    /// 权限验证（同 verify_capability；不满足时返回 PermissionDenied）
+    Verify { pid: ProcessId, rid: ResourceId, required: u32 },
+}
+
+/// 批量执行的错误语义
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchMode {
+    /// 遇到第一个错误即停止，结果只包含已执行的操作
+    StopOnError,
+    /// 尽力执行全部操作，逐项返回结果
+    BestEffort,
+}
+
+/// 原始能力句柄（批量接口返回，未携带类型状态）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawCapability {
+    pub index: u32,
+    pub generation: u32,
+    pub scope: ScopeKind,
+    pub creation_order: u64,
+}
+
+impl RawCapability {
+    fn from_entry(idx: u32, e: &CapabilityEntry) -> Self {
+        Self { index: idx, generation: e.generation, scope: e.scope, creation_order: e.creation_order }
+    }
+
+    /// 转换为类型化句柄
+    ///
+    /// # Safety
+    ///
+    /// 调用者必须确保 `A` 与该能力实际授予的权限一致
+    pub unsafe fn into_handle<A, S>(self) -> CapabilityHandle<A, S> {
+        CapabilityHandle::new(self.index, self.generation, self.scope, self.creation_order)
+    }
+}
+
+/// 单个批量操作的输出
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapOpOutput {
+    Bound(RawCapability),
+    Granted(RawCapability),
+    Verified,
+}
+
+/// 在一次 WR_DATA -> RO_DATA.write 加锁内执行一组能力操作
+///
+/// 返回逐项结果；StopOnError 模式下结果在第一个错误处截断。
+/// 只供内核使用：Bind 可以在任何资源上建立能力，返回的原始句柄也没有 RAII 所有者。
+/// LibOS 经 `execute_user_batch` 提交
+pub(crate) fn execute_batch(ops: &[CapOp], mode: BatchMode) -> Vec<Result<CapOpOutput, CapError>> {
+    let mut results = Vec::with_capacity(ops.len());
+    let mut wr = WR_DATA.lock();
+    let mut ro = RO_DATA.write();
+
+    for op in ops {
+        let r = match *op {
+            CapOp::Bind { pid, rid, caps, scope } => {
+                let creation = CREATION_SEQ.fetch_add(1, Ordering::Relaxed);
+                bind_locked(&mut wr, &mut ro, pid, rid, caps, scope, creation, None)
+                    .map(|idx| CapOpOutput::Bound(RawCapability::from_entry(idx, &ro[idx as usize])))
+            }
+            CapOp::GrantReadOnly { grantor, grantee, rid } => {
+                grant_readonly_locked(&mut wr, &mut ro, grantor, grantee, rid)
+                    .map(|idx| CapOpOutput::Granted(RawCapability::from_entry(idx, &ro[idx as usize])))
+            }
+            CapOp::GrantExclusive { grantor, grantee, rid } => {
+                grant_exclusive_locked(&mut wr, &mut ro, grantor, grantee, rid)
+                    .map(|idx| CapOpOutput::Granted(RawCapability::from_entry(idx, &ro[idx as usize])))
+            }
+            CapOp::Verify { pid, rid, required } => {
+                if verify_locked(&wr, &ro, pid, rid, required) { Ok(CapOpOutput::Verified) }
+                else { Err(CapError::PermissionDenied) }
+            }
+        };
+        let failed = r.is_err();
+        results.push(r);
+        if failed && mode == BatchMode::StopOnError { break; }
+    }
+    results
+}
+
+/// LibOS 可提交的批量操作（以调用者身份执行，没有 Bind）
+#[derive(Debug, Clone, Copy)]
+pub enum UserCapOp {
+    /// 把调用者持有的资源只读授权给 grantee（同 grant_readonly）
+    GrantReadOnly { grantee: ProcessId, rid: ResourceId },
+    /// 把调用者持有的资源独占授权给 grantee（同 grant_exclusive）
+    GrantExclusive { grantee: ProcessId, rid: ResourceId },
+    /// 验证调用者自己的权限（不满足时返回 PermissionDenied）
+    Verify { rid: ResourceId, required: u32 },
+}
+
+/// 单个用户批量操作的输出（授权结果是类型化句柄）
+pub enum UserCapOutput {
+    ReadOnly(CapabilityHandle<access::ReadOnly>),
+    Exclusive(CapabilityHandle<access::Exclusive>),
+    Verified,
+}
+
+/// 以 caller 的身份在一次加锁内执行一组能力操作
+///
+/// 授权者固定为 caller，且 caller 必须持有该资源带 GRANT 的活跃能力；
+/// 结果语义同 `execute_batch`
+pub fn execute_user_batch(
+    caller: ProcessId, ops: &[UserCapOp], mode: BatchMode,
+) -> Vec<Result<UserCapOutput, CapError>> {
+    let kernel_ops: Vec<CapOp> = ops.iter().map(|op| match *op {
+        UserCapOp::GrantReadOnly { grantee, rid } => CapOp::GrantReadOnly { grantor: caller, grantee, rid },
+        UserCapOp::GrantExclusive { grantee, rid } => CapOp::GrantExclusive { grantor: caller, grantee, rid },
+        UserCapOp::Verify { rid, required } => CapOp::Verify { pid: caller, rid, required },
+    }).collect();
+
+    // 结果与 ops 一一对应（StopOnError 时只是更短）
+    execute_batch(&kernel_ops, mode).into_iter().zip(ops).map(|(r, op)| {
+        r.map(|out| match (out, op) {
+            // grant_readonly_locked 只授予 READ，grant_exclusive_locked 授予 RW | MAP
+            (CapOpOutput::Granted(raw), UserCapOp::GrantReadOnly { .. }) =>
+                UserCapOutput::ReadOnly(unsafe { raw.into_handle() }),
+            (CapOpOutput::Granted(raw), _) => UserCapOutput::Exclusive(unsafe { raw.into_handle() }),
+            _ => UserCapOutput::Verified,
+        })
+    }).collect()
 }
 
 // ========== RAII 作用域回收（确定性 Drop） ==========
 
+#[cfg_attr(feature = "debug-poison", track_caller)]
 fn revoke_indices_deterministic(mut idxs: Vec<u32>) -> usize {
+    let site = call_site();
     // 读取创建序并按逆序撤销（Rust 的 Drop 顺序）
     {
         let ro = RO_DATA.read();
@@ -806,24 +1260,31 @@ fn revoke_indices_deterministic(mut idxs: Vec<u32>) -> usize {
     let mut count = 0usize;
     for idx in idxs {
         if ro[idx as usize].state != SlotState::Free {
-            if revoke_dfs_locked(&mut wr, &mut ro, idx, true).is_ok() { count += 1; }
+            if revoke_dfs_locked(&mut wr, &mut ro, idx, true, site).is_ok() { count += 1; }
         }
     }
     count
 }
 
+#[cfg_attr(feature = "debug-poison", track_caller)]
 pub fn on_process_exit(pid: ProcessId) -> usize {
     let mut wr = WR_DATA.lock();
     let idxs = wr.process_caps.remove(&pid.as_u32()).unwrap_or_default();
     drop(wr);
-    revoke_indices_deterministic(idxs)
+    let revoked = revoke_indices_deterministic(idxs);
+    // 能力已全部撤销，仍归该进程所有的帧都是泄漏
+    #[cfg(feature = "debug-poison")]
+    crate::mm::physical::report_leaks(pid.as_u32());
+    revoked
 }
+#[cfg_attr(feature = "debug-poison", track_caller)]
 pub fn on_thread_exit(tid: ThreadId) -> usize {
     let mut wr = WR_DATA.lock();
     let idxs = wr.thread_caps.remove(&tid.as_u64()).unwrap_or_default();
     drop(wr);
     revoke_indices_deterministic(idxs)
 }
+#[cfg_attr(feature = "debug-poison", track_caller)]
 pub fn on_syscall_return(tid: ThreadId, seq: u64) -> usize {
     let mut wr = WR_DATA.lock();
     let idxs = wr.syscall_caps.remove(&(tid.as_u64(), seq)).unwrap_or_default();
@@ -847,9 +1308,9 @@ pub fn get_stats() -> CapabilityStats {
     for c in &PER_CPU { hits += c.hits.load(Ordering::Relaxed); misses += c.misses.load(Ordering::Relaxed); }
     let tot = hits + misses;
     CapabilityStats {
-        total_slots: MAX_CAPABILITIES,
+        total_slots: slot_limit(),
         used_slots: wr.used_count as usize,
-        free_slots: MAX_CAPABILITIES - wr.used_count as usize,
+        free_slots: slot_limit() - wr.used_count as usize,
         cache_hits: hits, cache_misses: misses,
         cache_hit_rate: if tot>0 { (hits as f32 / tot as f32)*100.0 } else { 0.0 },
     }
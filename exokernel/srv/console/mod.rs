@@ -2,6 +2,28 @@
 //! 控制台输出
 
 use core::fmt::{self, Write};
+use crate::boot::cmdline::{self, ChoiceParam, UintParam};
+
+/// 日志级别：达到 LOG_DEBUG（4）时打印逐项细节（内存图、ACPI 表等），低于它时省略；
+/// 启动进度与错误不分级，总是打印（关闭全部输出用 console=none）
+pub static LOG_LEVEL: UintParam =
+    UintParam::new("loglevel", 4, 0, 4, "detail output at 4, omitted below (progress always shown)");
+
+/// 控制台设备（none 关闭普通输出，panic 信息仍写到串口）
+pub static CONSOLE: ChoiceParam = ChoiceParam::new("console", &["serial", "none"], 0, "console device");
+
+/// 细节输出（内存图逐项等）对应的级别
+pub const LOG_DEBUG: u64 = 4;
+
+pub fn register_params() {
+    cmdline::register(&LOG_LEVEL);
+    cmdline::register(&CONSOLE);
+}
+
+/// 该级别的输出是否打开
+pub fn log_enabled(level: u64) -> bool {
+    LOG_LEVEL.get() >= level
+}
 
 struct Console;
 
@@ -20,10 +42,18 @@ pub fn init() {
 
 #[doc(hidden)]
 pub fn _print(args: fmt::Arguments) {
+    if CONSOLE.index() != 0 {
+        return;
+    }
     let mut console = Console;
     console.write_fmt(args).unwrap();
 }
 
+/// 无视 console=none 直接写串口（panic 路径使用，否则内核会无声地停住）
+pub fn emergency_print(args: fmt::Arguments) {
+    let _ = Console.write_fmt(args);
+}
+
 #[macro_export]
 macro_rules! print {
     ($($arg:tt)*) => ($crate::console::_print(format_args!($($arg)*)));
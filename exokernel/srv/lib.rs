@@ -15,6 +15,16 @@ pub mod console;
 
 use core::panic::PanicInfo;
 
+/// 空闲循环每轮后台清零的页数
+const IDLE_SCRUB_BATCH: usize = 64;
+
+/// 启动后运行的自检（逗号分隔；none 表示不运行）
+static SELFTEST: boot::cmdline::StrParam =
+    boot::cmdline::StrParam::new("selftest", "ownership", "self-tests to run (ownership, none)");
+
+/// selftest= 可用的名字
+const SELFTESTS: &[&str] = &["ownership", "none"];
+
 /// 全局初始化标志
 static INITIALIZED: core::sync::atomic::AtomicBool =
     core::sync::atomic::AtomicBool::new(false);
@@ -25,6 +35,9 @@ pub extern "C" fn kernel_main(boot_info: *const u8) -> ! {
     // 初始化控制台
     console::init();
 
+    // 固件表和高地址内存都按物理地址访问，先把直接映射扩展到完整范围
+    unsafe { arch::extend_direct_map(); }
+
     println!("\n╔═══════════════════════════════════════╗");
     println!("║   EXOKERNEL - Rust Ownership Model   ║");
     println!("║   Multi-Architecture Support System   ║");
@@ -37,6 +50,16 @@ pub extern "C" fn kernel_main(boot_info: *const u8) -> ! {
     let mem_regions = boot::parse_boot_info(boot_info);
     println!("[BOOT] Found {} memory regions", mem_regions.len());
 
+    // 各子系统登记选项后应用命令行
+    console::register_params();
+    mm::register_params();
+    capability::resource::register_params();
+    boot::cmdline::register(&SELFTEST);
+    boot::cmdline::apply();
+    check_selftests();
+    boot::cmdline::report();
+    boot::report(&mem_regions);
+
     // 初始化物理内存管理器（Rust所有权模型）
     mm::init(mem_regions);
     println!("[MM] Physical memory manager initialized");
@@ -51,12 +74,28 @@ pub extern "C" fn kernel_main(boot_info: *const u8) -> ! {
     println!("\n[OK] Kernel initialized successfully!\n");
 
     // 运行测试
-    test_ownership_model();
+    if SELFTEST.contains("ownership") {
+        test_ownership_model();
+    }
 
     // 主循环
     println!("[IDLE] Entering idle loop...");
     loop {
-        arch::halt();
+        // 推进内存回收协议（核实履约、处理超时）
+        mm::reclaim::tick();
+
+        // 空闲时预先清零脏页，之后的清零分配走快路径
+        if unsafe { mm::physical::scrub_idle(IDLE_SCRUB_BATCH) } == 0 {
+            arch::halt();
+        }
+    }
+}
+
+/// selftest= 中的未知名字会被忽略，打印出来以免拼错后无声地什么都不跑
+fn check_selftests() {
+    for name in SELFTEST.get().split(',').filter(|s| !s.is_empty() && !SELFTESTS.contains(s)) {
+        println!("  [CMDLINE] Warning: unknown self-test '{}' ignored (known: {})",
+                 name, SELFTESTS.join(", "));
     }
 }
 
@@ -115,8 +154,8 @@ fn test_ownership_model() {
 
 /// Panic处理器
 pub fn panic_handler(info: &PanicInfo) -> ! {
-    println!("\n!!! KERNEL PANIC !!!");
-    println!("{}", info);
+    // console=none 也要输出
+    console::emergency_print(format_args!("\n!!! KERNEL PANIC !!!\n{}\n", info));
 
     loop {
         arch::halt();
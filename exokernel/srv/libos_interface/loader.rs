@@ -0,0 +1,714 @@
+//! ELF64 加载器
+//!
+//! 特性：
+//! - 校验 ELF64 小端头与当前架构的 e_machine（ET_EXEC 与 ET_DYN）
+//! - 每个 PT_LOAD 段装入新分配的 `OwnedPage`，按段标志授予 READ/WRITE/EXECUTE 能力；
+//!   相邻段共用的边界页只分配一次，权限取两段之并
+//! - 建立用户栈：argv、空 envp 与 auxv（AT_PHDR/AT_ENTRY/AT_PAGESZ...）
+//! - 所有页都交给新进程（进程作用域能力持有帧），`on_process_exit` 时整体释放
+//!
+//! 外核不替 LibOS 建页表：加载结果给出 虚拟页 -> 物理帧 的映射表，
+//! 由 LibOS 按表和各页的能力位自行安装映射。
+
+use alloc::collections::btree_map::{BTreeMap, Entry};
+use alloc::vec::Vec;
+use crate::arch::PAGE_SIZE;
+use crate::capability::{ProcessId, CapError, caps, allocate_pid, on_process_exit};
+use super::module::{ModuleImage, ModuleError};
+use super::ownership_api::{OwnedPage, PhysicalAddr, AllocError, Syscall};
+
+#[cfg(target_arch = "x86_64")]
+const EM_CURRENT: u16 = 62;
+#[cfg(target_arch = "aarch64")]
+const EM_CURRENT: u16 = 183;
+#[cfg(target_arch = "riscv64")]
+const EM_CURRENT: u16 = 243;
+#[cfg(target_arch = "loongarch64")]
+const EM_CURRENT: u16 = 258;
+
+const ELF_MAGIC: [u8; 4] = [0x7f, b'E', b'L', b'F'];
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const EV_CURRENT: u32 = 1;
+const ET_EXEC: u16 = 2;
+const ET_DYN: u16 = 3;
+const EHDR_SIZE: usize = 64;
+const PHDR_SIZE: usize = 56;
+const PN_XNUM: u16 = 0xffff;
+
+const PT_LOAD: u32 = 1;
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+
+const AT_NULL: u64 = 0;
+const AT_PHDR: u64 = 3;
+const AT_PHENT: u64 = 4;
+const AT_PHNUM: u64 = 5;
+const AT_PAGESZ: u64 = 6;
+const AT_BASE: u64 = 7;
+const AT_ENTRY: u64 = 9;
+
+/// 用户栈顶（低于 256GiB，Sv39 下同样可用）
+pub const USER_STACK_TOP: usize = 0x3f_ffff_f000;
+/// 默认用户栈页数（64KiB）
+pub const USER_STACK_PAGES: usize = 16;
+/// ET_DYN 镜像的装载基址（镜像自行处理 RELATIVE 重定位）
+pub const DYN_LOAD_BASE: usize = 0x1000_0000;
+
+// ========== ELF 解析 ==========
+
+/// ELF 文件头中加载器关心的字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ElfHeader {
+    pub kind: u16,
+    pub machine: u16,
+    pub entry: u64,
+    pub phoff: u64,
+    pub phnum: u16,
+}
+
+/// 程序头
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgramHeader {
+    pub kind: u32,
+    pub flags: u32,
+    pub offset: u64,
+    pub vaddr: u64,
+    pub filesz: u64,
+    pub memsz: u64,
+    pub align: u64,
+}
+
+impl ProgramHeader {
+    /// 段标志对应的能力位
+    pub fn caps(&self) -> u32 {
+        let mut bits = caps::MAP;
+        if self.flags & PF_R != 0 { bits |= caps::READ; }
+        if self.flags & PF_W != 0 { bits |= caps::WRITE; }
+        if self.flags & PF_X != 0 { bits |= caps::EXECUTE; }
+        bits
+    }
+}
+
+/// 已校验头部的 ELF64 镜像（零拷贝）
+#[derive(Clone, Copy)]
+pub struct ElfFile<'a> {
+    data: &'a [u8],
+    header: ElfHeader,
+}
+
+impl<'a> ElfFile<'a> {
+    /// 校验文件头与程序头表的位置
+    pub fn parse(data: &'a [u8]) -> Result<Self, LoadError> {
+        if data.len() < EHDR_SIZE {
+            return Err(LoadError::Truncated);
+        }
+        if data[0..4] != ELF_MAGIC {
+            return Err(LoadError::BadMagic);
+        }
+        if data[4] != ELFCLASS64 {
+            return Err(LoadError::NotElf64);
+        }
+        if data[5] != ELFDATA2LSB {
+            return Err(LoadError::NotLittleEndian);
+        }
+        if data[6] as u32 != EV_CURRENT || read_u32(data, 20) != Some(EV_CURRENT) {
+            return Err(LoadError::BadVersion);
+        }
+
+        let header = ElfHeader {
+            kind: read_u16(data, 16).ok_or(LoadError::Truncated)?,
+            machine: read_u16(data, 18).ok_or(LoadError::Truncated)?,
+            entry: read_u64(data, 24).ok_or(LoadError::Truncated)?,
+            phoff: read_u64(data, 32).ok_or(LoadError::Truncated)?,
+            phnum: read_u16(data, 56).ok_or(LoadError::Truncated)?,
+        };
+        if header.kind != ET_EXEC && header.kind != ET_DYN {
+            return Err(LoadError::NotExecutable);
+        }
+        if header.machine != EM_CURRENT {
+            return Err(LoadError::WrongMachine(header.machine));
+        }
+
+        let phentsize = read_u16(data, 54).ok_or(LoadError::Truncated)?;
+        if phentsize as usize != PHDR_SIZE || header.phnum == PN_XNUM {
+            return Err(LoadError::BadProgramHeader);
+        }
+        let table_end = (header.phnum as u64 * PHDR_SIZE as u64).checked_add(header.phoff);
+        if table_end.map_or(true, |end| end > data.len() as u64) {
+            return Err(LoadError::Truncated);
+        }
+
+        Ok(Self { data, header })
+    }
+
+    pub fn header(&self) -> ElfHeader {
+        self.header
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        self.header.kind == ET_DYN
+    }
+
+    /// 全部程序头
+    pub fn program_headers(&self) -> impl Iterator<Item = ProgramHeader> + 'a {
+        let (data, phoff) = (self.data, self.header.phoff as usize);
+        (0..self.header.phnum as usize).map(move |i| {
+            let ph = &data[phoff + i * PHDR_SIZE..phoff + (i + 1) * PHDR_SIZE];
+            ProgramHeader {
+                kind: read_u32(ph, 0).unwrap_or(0),
+                flags: read_u32(ph, 4).unwrap_or(0),
+                offset: read_u64(ph, 8).unwrap_or(0),
+                vaddr: read_u64(ph, 16).unwrap_or(0),
+                filesz: read_u64(ph, 32).unwrap_or(0),
+                memsz: read_u64(ph, 40).unwrap_or(0),
+                align: read_u64(ph, 48).unwrap_or(0),
+            }
+        })
+    }
+
+    /// 需要装入内存的段（PT_LOAD 且 memsz 非零）
+    pub fn load_segments(&self) -> impl Iterator<Item = ProgramHeader> + 'a {
+        self.program_headers().filter(|ph| ph.kind == PT_LOAD && ph.memsz != 0)
+    }
+
+    /// 程序头表在镜像中的虚拟地址（未加偏移；不在任何段内时为 None）
+    pub fn phdr_vaddr(&self) -> Option<u64> {
+        let phoff = self.header.phoff;
+        self.load_segments()
+            .find(|ph| phoff >= ph.offset && phoff < ph.offset + ph.filesz)
+            .map(|ph| ph.vaddr + (phoff - ph.offset))
+    }
+}
+
+fn read_u16(data: &[u8], off: usize) -> Option<u16> {
+    data.get(off..off + 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u32(data: &[u8], off: usize) -> Option<u32> {
+    data.get(off..off + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_u64(data: &[u8], off: usize) -> Option<u64> {
+    data.get(off..off + 8).map(|b| {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(b);
+        u64::from_le_bytes(bytes)
+    })
+}
+
+// ========== 加载结果 ==========
+
+/// 一个虚拟页到物理帧的映射
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mapping {
+    pub vaddr: usize,
+    pub frame: PhysicalAddr,
+    /// 该页能力的权限位（READ/WRITE/EXECUTE | MAP）
+    pub caps: u32,
+}
+
+/// 已装入的进程镜像
+///
+/// 页由进程的能力持有：本结构只是描述，Drop 不释放任何内存，
+/// 进程退出（`Syscall::exit_process`）时全部释放
+#[derive(Debug, Clone)]
+pub struct LoadedImage {
+    pid: ProcessId,
+    entry: usize,
+    stack_pointer: usize,
+    load_bias: usize,
+    mappings: Vec<Mapping>,
+}
+
+impl LoadedImage {
+    pub fn pid(&self) -> ProcessId { self.pid }
+    /// 入口地址（已加装载偏移）
+    pub fn entry(&self) -> usize { self.entry }
+    /// 初始栈指针，指向 argc
+    pub fn stack_pointer(&self) -> usize { self.stack_pointer }
+    /// ET_DYN 的装载偏移（ET_EXEC 为 0）
+    pub fn load_bias(&self) -> usize { self.load_bias }
+    /// 全部映射，按虚拟地址排序
+    pub fn mappings(&self) -> &[Mapping] { &self.mappings }
+    pub fn page_count(&self) -> usize { self.mappings.len() }
+
+    /// 虚拟地址对应的物理地址
+    pub fn translate(&self, vaddr: usize) -> Option<PhysicalAddr> {
+        let page = vaddr & !(PAGE_SIZE - 1);
+        self.mappings
+            .binary_search_by_key(&page, |m| m.vaddr)
+            .ok()
+            .map(|i| PhysicalAddr::new(self.mappings[i].frame.as_usize() + (vaddr - page)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadError {
+    /// 文件比头部或程序头表声明的短
+    Truncated,
+    BadMagic,
+    NotElf64,
+    NotLittleEndian,
+    BadVersion,
+    /// 既不是 ET_EXEC 也不是 ET_DYN
+    NotExecutable,
+    /// e_machine 与当前架构不符
+    WrongMachine(u16),
+    BadProgramHeader,
+    /// 段的文件范围越界、filesz > memsz 或对齐不是 2 的幂
+    BadSegment,
+    /// 段超出用户地址空间（或与栈重叠）
+    SegmentOutOfRange,
+    /// 两个段的地址范围重叠（仅共用边界页是合法的）
+    SegmentOverlap,
+    NoLoadableSegment,
+    /// 入口不在可执行段内
+    EntryNotMapped,
+    /// 参数放不进栈顶页
+    ArgsTooLong,
+    AllocFailed(AllocError),
+    CapabilityError(CapError),
+    ModuleError(ModuleError),
+}
+
+impl From<AllocError> for LoadError {
+    fn from(e: AllocError) -> Self {
+        LoadError::AllocFailed(e)
+    }
+}
+
+impl From<CapError> for LoadError {
+    fn from(e: CapError) -> Self {
+        LoadError::CapabilityError(e)
+    }
+}
+
+impl From<ModuleError> for LoadError {
+    fn from(e: ModuleError) -> Self {
+        LoadError::ModuleError(e)
+    }
+}
+
+// ========== 加载 ==========
+
+/// 为新进程装入 ELF 镜像，args 成为 argv
+///
+/// 失败时新进程已获得的页全部释放
+pub fn load(data: &[u8], args: &[&str]) -> Result<LoadedImage, LoadError> {
+    in_new_process(|pid| load_into(pid, data, args))
+}
+
+// 分配 pid 并执行 f；失败时按进程退出回收
+fn in_new_process(f: impl FnOnce(ProcessId) -> Result<LoadedImage, LoadError>) -> Result<LoadedImage, LoadError> {
+    let pid = allocate_pid();
+    let result = f(pid);
+    if result.is_err() {
+        on_process_exit(pid);
+    }
+    result
+}
+
+fn load_into(pid: ProcessId, data: &[u8], args: &[&str]) -> Result<LoadedImage, LoadError> {
+    let elf = ElfFile::parse(data)?;
+    let bias = if elf.is_dynamic() { DYN_LOAD_BASE } else { 0 };
+    let segments = check_segments(&elf, bias)?;
+
+    let entry = elf.header().entry as usize + bias;
+    let entry_ok = segments.iter().any(|ph| {
+        let start = ph.vaddr as usize + bias;
+        ph.flags & PF_X != 0 && entry >= start && entry < start + ph.memsz as usize
+    });
+    if !entry_ok {
+        return Err(LoadError::EntryNotMapped);
+    }
+
+    // 先装入全部段再交给进程：共用的页要等所有段写完、权限合并后才能绑定能力
+    let mut pages = BTreeMap::new();
+    for ph in &segments {
+        load_segment(pid, data, ph, bias, &mut pages)?;
+    }
+    let mut mappings = Vec::with_capacity(pages.len() + USER_STACK_PAGES);
+    for (vaddr, (page, caps_bits)) in pages {
+        let frame = page.into_process(caps_bits)?;
+        mappings.push(Mapping { vaddr, frame, caps: caps_bits });
+    }
+
+    let auxv = [
+        (AT_PHDR, elf.phdr_vaddr().map_or(0, |v| v + bias as u64)),
+        (AT_PHENT, PHDR_SIZE as u64),
+        (AT_PHNUM, elf.header().phnum as u64),
+        (AT_PAGESZ, PAGE_SIZE as u64),
+        (AT_BASE, bias as u64),
+        (AT_ENTRY, entry as u64),
+    ];
+    let stack_pointer = setup_stack(pid, args, &auxv, &mut mappings)?;
+
+    mappings.sort_unstable_by_key(|m| m.vaddr);
+    Ok(LoadedImage { pid, entry, stack_pointer, load_bias: bias, mappings })
+}
+
+// 校验 PT_LOAD 段并按地址排序
+fn check_segments(elf: &ElfFile, bias: usize) -> Result<Vec<ProgramHeader>, LoadError> {
+    let stack_base = USER_STACK_TOP - USER_STACK_PAGES * PAGE_SIZE;
+    let mut segments: Vec<ProgramHeader> = elf.load_segments().collect();
+    if segments.is_empty() {
+        return Err(LoadError::NoLoadableSegment);
+    }
+
+    for ph in &segments {
+        let file_end = ph.offset.checked_add(ph.filesz);
+        if ph.filesz > ph.memsz || file_end.map_or(true, |end| end > elf.data.len() as u64) {
+            return Err(LoadError::BadSegment);
+        }
+        if ph.align > 1 && !ph.align.is_power_of_two() {
+            return Err(LoadError::BadSegment);
+        }
+        let end = ph.vaddr.checked_add(ph.memsz).and_then(|e| e.checked_add(bias as u64));
+        if end.map_or(true, |end| end > stack_base as u64) {
+            return Err(LoadError::SegmentOutOfRange);
+        }
+    }
+
+    // 段可以共用边界页（链接器常把 .text 的尾页与 .data 的首页放在同一页），但字节不能重叠
+    segments.sort_unstable_by_key(|ph| ph.vaddr);
+    for pair in segments.windows(2) {
+        if pair[1].vaddr < pair[0].vaddr + pair[0].memsz {
+            return Err(LoadError::SegmentOverlap);
+        }
+    }
+    Ok(segments)
+}
+
+// 按页复制文件内容（其余部分为零，即 .bss）；已由前一段分配的共用页直接复用并合并权限
+fn load_segment(
+    pid: ProcessId,
+    data: &[u8],
+    ph: &ProgramHeader,
+    bias: usize,
+    pages: &mut BTreeMap<usize, (OwnedPage, u32)>,
+) -> Result<(), LoadError> {
+    let start = ph.vaddr as usize + bias;
+    let file_end = start + ph.filesz as usize;
+
+    let mut vpage = page_down(start);
+    while vpage < start + ph.memsz as usize {
+        let (page, caps_bits) = match pages.entry(vpage) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert((OwnedPage::alloc_zeroed(pid)?, 0)),
+        };
+        *caps_bits |= ph.caps();
+        let (lo, hi) = (vpage.max(start), (vpage + PAGE_SIZE).min(file_end));
+        if lo < hi {
+            let src = ph.offset as usize + (lo - start);
+            let dst = unsafe { page.as_slice_mut() };
+            dst[lo - vpage..hi - vpage].copy_from_slice(&data[src..src + (hi - lo)]);
+        }
+        vpage += PAGE_SIZE;
+    }
+    Ok(())
+}
+
+// 分配用户栈并在栈顶页写入 argc/argv/envp/auxv，返回初始栈指针
+fn setup_stack(
+    pid: ProcessId,
+    args: &[&str],
+    auxv: &[(u64, u64)],
+    mappings: &mut Vec<Mapping>,
+) -> Result<usize, LoadError> {
+    let stack_base = USER_STACK_TOP - USER_STACK_PAGES * PAGE_SIZE;
+    let top_page = USER_STACK_TOP - PAGE_SIZE;
+
+    let mut pages = Vec::with_capacity(USER_STACK_PAGES);
+    for _ in 0..USER_STACK_PAGES {
+        pages.push(OwnedPage::alloc_zeroed(pid)?);
+    }
+
+    let frame = unsafe { pages[USER_STACK_PAGES - 1].as_slice_mut() };
+    let sp = write_initial_stack(frame, top_page, args, auxv)?;
+
+    let caps_bits = caps::RW | caps::MAP;
+    for (i, page) in pages.into_iter().enumerate() {
+        let frame = page.into_process(caps_bits)?;
+        mappings.push(Mapping { vaddr: stack_base + i * PAGE_SIZE, frame, caps: caps_bits });
+    }
+    Ok(sp)
+}
+
+// 栈顶页布局（自高向低）：参数字符串，对齐到 16 字节后依次为
+// argc、argv[..]、NULL、NULL（空 envp）、auxv 对、AT_NULL
+fn write_initial_stack(
+    frame: &mut [u8],
+    page_vaddr: usize,
+    args: &[&str],
+    auxv: &[(u64, u64)],
+) -> Result<usize, LoadError> {
+    let mut cursor = PAGE_SIZE;
+    let mut argv = Vec::with_capacity(args.len());
+    for arg in args.iter().rev() {
+        let len = arg.len() + 1;
+        cursor = cursor.checked_sub(len).ok_or(LoadError::ArgsTooLong)?;
+        frame[cursor..cursor + arg.len()].copy_from_slice(arg.as_bytes());
+        frame[cursor + arg.len()] = 0;
+        argv.push((page_vaddr + cursor) as u64);
+    }
+    argv.reverse();
+
+    let mut words = Vec::with_capacity(args.len() + 3 + auxv.len() * 2 + 2);
+    words.push(args.len() as u64);
+    words.extend_from_slice(&argv);
+    words.push(0);
+    words.push(0);
+    for &(key, value) in auxv {
+        words.extend_from_slice(&[key, value]);
+    }
+    words.extend_from_slice(&[AT_NULL, 0]);
+
+    let bytes = words.len() * 8;
+    let sp = cursor.checked_sub(bytes).ok_or(LoadError::ArgsTooLong)? & !15;
+    for (i, word) in words.iter().enumerate() {
+        frame[sp + i * 8..sp + i * 8 + 8].copy_from_slice(&word.to_le_bytes());
+    }
+    Ok(page_vaddr + sp)
+}
+
+fn page_down(addr: usize) -> usize {
+    addr & !(PAGE_SIZE - 1)
+}
+
+impl Syscall {
+    /// 把 ELF 镜像装入新进程
+    pub fn load_elf(data: &[u8], args: &[&str]) -> Result<LoadedImage, LoadError> {
+        load(data, args)
+    }
+
+    /// 从引导模块启动 LibOS：argv[0] 为模块名，其后是模块命令行参数
+    pub fn spawn_module(name: &str) -> Result<LoadedImage, LoadError> {
+        in_new_process(|pid| {
+            let module = ModuleImage::grant(pid, name)?;
+            let mut args = Vec::new();
+            args.push(module.name());
+            args.extend(module.args().split_whitespace());
+            load_into(pid, module.as_slice(), &args)
+        })
+    }
+
+    /// 进程退出：撤销其全部能力，镜像与栈的页随之释放，返回撤销的能力数
+    pub fn exit_process(pid: ProcessId) -> usize {
+        on_process_exit(pid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn put(data: &mut [u8], off: usize, bytes: &[u8]) {
+        data[off..off + bytes.len()].copy_from_slice(bytes);
+    }
+
+    // 最小的 ELF64 小端镜像：文件头之后紧跟程序头表
+    fn image(phdrs: &[ProgramHeader]) -> Vec<u8> {
+        let mut data = alloc::vec![0u8; EHDR_SIZE + phdrs.len() * PHDR_SIZE];
+        put(&mut data, 0, &ELF_MAGIC);
+        data[4] = ELFCLASS64;
+        data[5] = ELFDATA2LSB;
+        data[6] = EV_CURRENT as u8;
+        put(&mut data, 16, &ET_EXEC.to_le_bytes());
+        put(&mut data, 18, &EM_CURRENT.to_le_bytes());
+        put(&mut data, 20, &EV_CURRENT.to_le_bytes());
+        put(&mut data, 24, &0x40_1000u64.to_le_bytes());
+        put(&mut data, 32, &(EHDR_SIZE as u64).to_le_bytes());
+        put(&mut data, 52, &(EHDR_SIZE as u16).to_le_bytes());
+        put(&mut data, 54, &(PHDR_SIZE as u16).to_le_bytes());
+        put(&mut data, 56, &(phdrs.len() as u16).to_le_bytes());
+        for (i, ph) in phdrs.iter().enumerate() {
+            let off = EHDR_SIZE + i * PHDR_SIZE;
+            put(&mut data, off, &ph.kind.to_le_bytes());
+            put(&mut data, off + 4, &ph.flags.to_le_bytes());
+            put(&mut data, off + 8, &ph.offset.to_le_bytes());
+            put(&mut data, off + 16, &ph.vaddr.to_le_bytes());
+            put(&mut data, off + 32, &ph.filesz.to_le_bytes());
+            put(&mut data, off + 40, &ph.memsz.to_le_bytes());
+            put(&mut data, off + 48, &ph.align.to_le_bytes());
+        }
+        data
+    }
+
+    fn text() -> ProgramHeader {
+        ProgramHeader {
+            kind: PT_LOAD,
+            flags: PF_R | PF_X,
+            offset: 0,
+            vaddr: 0x40_0000,
+            filesz: (EHDR_SIZE + PHDR_SIZE) as u64,
+            memsz: 0x2000,
+            align: PAGE_SIZE as u64,
+        }
+    }
+
+    fn parse_err(data: &[u8]) -> LoadError {
+        ElfFile::parse(data).err().expect("malformed header accepted")
+    }
+
+    #[test]
+    fn test_parse_valid_image() {
+        let data = image(&[text()]);
+        let elf = ElfFile::parse(&data).expect("valid image");
+        assert_eq!(elf.header(), ElfHeader {
+            kind: ET_EXEC,
+            machine: EM_CURRENT,
+            entry: 0x40_1000,
+            phoff: EHDR_SIZE as u64,
+            phnum: 1,
+        });
+        assert!(!elf.is_dynamic());
+        assert_eq!(elf.program_headers().collect::<Vec<_>>(), [text()]);
+        assert_eq!(elf.phdr_vaddr(), Some(0x40_0000 + EHDR_SIZE as u64));
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_ident() {
+        let good = image(&[text()]);
+
+        assert_eq!(parse_err(&good[..EHDR_SIZE - 1]), LoadError::Truncated);
+        assert_eq!(parse_err(&[]), LoadError::Truncated);
+
+        let mut data = good.clone();
+        data[1] = b'e';
+        assert_eq!(parse_err(&data), LoadError::BadMagic);
+
+        let mut data = good.clone();
+        data[4] = 1;
+        assert_eq!(parse_err(&data), LoadError::NotElf64);
+
+        let mut data = good.clone();
+        data[5] = 2;
+        assert_eq!(parse_err(&data), LoadError::NotLittleEndian);
+
+        let mut data = good.clone();
+        data[6] = 0;
+        assert_eq!(parse_err(&data), LoadError::BadVersion);
+
+        let mut data = good.clone();
+        put(&mut data, 20, &2u32.to_le_bytes());
+        assert_eq!(parse_err(&data), LoadError::BadVersion);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_type_and_machine() {
+        let good = image(&[text()]);
+
+        // ET_REL、ET_CORE
+        for kind in [1u16, 4] {
+            let mut data = good.clone();
+            put(&mut data, 16, &kind.to_le_bytes());
+            assert_eq!(parse_err(&data), LoadError::NotExecutable);
+        }
+
+        let mut data = good.clone();
+        put(&mut data, 16, &ET_DYN.to_le_bytes());
+        assert!(ElfFile::parse(&data).unwrap().is_dynamic());
+
+        let other = EM_CURRENT.wrapping_add(1);
+        let mut data = good.clone();
+        put(&mut data, 18, &other.to_le_bytes());
+        assert_eq!(parse_err(&data), LoadError::WrongMachine(other));
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_program_header_table() {
+        let good = image(&[text()]);
+
+        let mut data = good.clone();
+        put(&mut data, 54, &32u16.to_le_bytes());
+        assert_eq!(parse_err(&data), LoadError::BadProgramHeader);
+
+        let mut data = good.clone();
+        put(&mut data, 56, &PN_XNUM.to_le_bytes());
+        assert_eq!(parse_err(&data), LoadError::BadProgramHeader);
+
+        // 程序头表超出文件末尾
+        assert_eq!(parse_err(&good[..good.len() - 1]), LoadError::Truncated);
+
+        let mut data = good.clone();
+        put(&mut data, 56, &2u16.to_le_bytes());
+        assert_eq!(parse_err(&data), LoadError::Truncated);
+
+        // phoff + 表长溢出 u64
+        let mut data = good.clone();
+        put(&mut data, 32, &u64::MAX.to_le_bytes());
+        assert_eq!(parse_err(&data), LoadError::Truncated);
+    }
+
+    fn word(frame: &[u8], off: usize) -> u64 {
+        read_u64(frame, off).unwrap()
+    }
+
+    fn cstr_at(frame: &[u8], page_vaddr: usize, ptr: u64) -> &[u8] {
+        let start = ptr as usize - page_vaddr;
+        let len = frame[start..].iter().position(|&b| b == 0).unwrap();
+        &frame[start..start + len]
+    }
+
+    #[test]
+    fn test_initial_stack_layout() {
+        let page_vaddr = USER_STACK_TOP - PAGE_SIZE;
+        let mut frame = alloc::vec![0u8; PAGE_SIZE];
+        let args = ["init", "-v", "x"];
+        let auxv = [(AT_PAGESZ, PAGE_SIZE as u64), (AT_ENTRY, 0x40_1000)];
+
+        let sp = write_initial_stack(&mut frame, page_vaddr, &args, &auxv).unwrap();
+        assert_eq!(sp % 16, 0);
+        assert!(sp >= page_vaddr && sp < USER_STACK_TOP);
+
+        let base = sp - page_vaddr;
+        let at = |i: usize| word(&frame, base + i * 8);
+        assert_eq!(at(0), args.len() as u64);
+        for (i, arg) in args.iter().enumerate() {
+            assert_eq!(cstr_at(&frame, page_vaddr, at(1 + i)), arg.as_bytes());
+        }
+        // argv、envp 各以 NULL 结尾
+        assert_eq!(at(4), 0);
+        assert_eq!(at(5), 0);
+        assert_eq!((at(6), at(7)), auxv[0]);
+        assert_eq!((at(8), at(9)), auxv[1]);
+        assert_eq!((at(10), at(11)), (AT_NULL, 0));
+
+        // 字符串整体位于向量区之上，且紧贴页顶
+        let lowest = (1..=args.len()).map(|i| at(i) as usize - page_vaddr).min().unwrap();
+        assert!(base + 12 * 8 <= lowest);
+        assert_eq!(lowest, PAGE_SIZE - args.iter().map(|a| a.len() + 1).sum::<usize>());
+    }
+
+    #[test]
+    fn test_initial_stack_without_args() {
+        let page_vaddr = USER_STACK_TOP - PAGE_SIZE;
+        let mut frame = alloc::vec![0u8; PAGE_SIZE];
+
+        let sp = write_initial_stack(&mut frame, page_vaddr, &[], &[]).unwrap();
+        assert_eq!(sp % 16, 0);
+        let base = sp - page_vaddr;
+        assert_eq!(word(&frame, base), 0);
+        assert_eq!(word(&frame, base + 8), 0);
+        assert_eq!(word(&frame, base + 16), 0);
+        assert_eq!(word(&frame, base + 24), AT_NULL);
+    }
+
+    #[test]
+    fn test_initial_stack_args_too_long() {
+        let page_vaddr = USER_STACK_TOP - PAGE_SIZE;
+        let mut frame = alloc::vec![0u8; PAGE_SIZE];
+
+        // 字符串本身放不下
+        let huge = "a".repeat(PAGE_SIZE);
+        assert_eq!(write_initial_stack(&mut frame, page_vaddr, &[&huge], &[]),
+                   Err(LoadError::ArgsTooLong));
+
+        // 字符串放得下，但向量区放不下
+        let long = "a".repeat(PAGE_SIZE - 16);
+        assert_eq!(write_initial_stack(&mut frame, page_vaddr, &[&long], &[]),
+                   Err(LoadError::ArgsTooLong));
+    }
+}
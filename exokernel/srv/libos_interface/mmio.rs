@@ -0,0 +1,127 @@
+//! 设备内存（MMIO）访问
+//!
+//! 特性：
+//! - 设备窗口以 `ResourceType::DeviceMemory` 能力委派给 LibOS
+//! - 寄存器访问一律为 volatile，并检查越界与自然对齐
+//! - Drop 时撤销能力，窗口可再次委派
+
+use crate::capability::{
+    ProcessId, CapabilityHandle, access, lifetime, CapError, revoke_capability,
+};
+use crate::mm::device::{self, DeviceKind};
+use super::ownership_api::{PhysicalAddr, Syscall};
+
+/// 独占持有的设备窗口
+pub struct MmioRegion {
+    handle: CapabilityHandle<access::Exclusive, lifetime::Process>,
+    base: PhysicalAddr,
+    size: usize,
+    kind: DeviceKind,
+    owner_pid: u32,
+}
+
+impl MmioRegion {
+    /// 把包含 addr 的设备窗口委派给 pid（需事先由内核 `device::grant` 授权）
+    pub fn map(pid: ProcessId, addr: PhysicalAddr) -> Result<Self, MmioError> {
+        let (region, handle) = device::delegate(pid, addr.as_usize()).map_err(|e| match e {
+            CapError::ResourceNotFound => MmioError::NotDevice,
+            CapError::PermissionDenied => MmioError::NotGranted,
+            CapError::AlreadyBound => MmioError::InUse,
+            CapError::Unsupported => MmioError::Unmappable,
+            e => MmioError::CapabilityError(e),
+        })?;
+        Ok(Self {
+            handle,
+            base: PhysicalAddr::new(region.base),
+            size: region.size,
+            kind: region.kind,
+            owner_pid: pid.as_u32(),
+        })
+    }
+
+    pub fn base(&self) -> PhysicalAddr { self.base }
+    pub fn size(&self) -> usize { self.size }
+    pub fn kind(&self) -> DeviceKind { self.kind }
+    pub fn owner(&self) -> ProcessId { ProcessId::new(self.owner_pid) }
+
+    pub fn read8(&self, offset: usize) -> Result<u8, MmioError> { self.read(offset) }
+    pub fn read16(&self, offset: usize) -> Result<u16, MmioError> { self.read(offset) }
+    pub fn read32(&self, offset: usize) -> Result<u32, MmioError> { self.read(offset) }
+    pub fn read64(&self, offset: usize) -> Result<u64, MmioError> { self.read(offset) }
+
+    pub fn write8(&mut self, offset: usize, value: u8) -> Result<(), MmioError> { self.write(offset, value) }
+    pub fn write16(&mut self, offset: usize, value: u16) -> Result<(), MmioError> { self.write(offset, value) }
+    pub fn write32(&mut self, offset: usize, value: u32) -> Result<(), MmioError> { self.write(offset, value) }
+    pub fn write64(&mut self, offset: usize, value: u64) -> Result<(), MmioError> { self.write(offset, value) }
+
+    /// 读-改-写一个 32 位寄存器
+    pub fn modify32(&mut self, offset: usize, f: impl FnOnce(u32) -> u32) -> Result<(), MmioError> {
+        let value = self.read32(offset)?;
+        self.write32(offset, f(value))
+    }
+
+    fn read<T: Copy>(&self, offset: usize) -> Result<T, MmioError> {
+        let ptr = self.register::<T>(offset)?;
+        // 安全：窗口由能力独占持有，且偏移已检查越界与对齐
+        Ok(unsafe { core::ptr::read_volatile(ptr) })
+    }
+
+    fn write<T: Copy>(&mut self, offset: usize, value: T) -> Result<(), MmioError> {
+        let ptr = self.register::<T>(offset)? as *mut T;
+        unsafe { core::ptr::write_volatile(ptr, value) };
+        Ok(())
+    }
+
+    fn register<T>(&self, offset: usize) -> Result<*const T, MmioError> {
+        let width = core::mem::size_of::<T>();
+        match offset.checked_add(width) {
+            Some(end) if end <= self.size => {}
+            _ => return Err(MmioError::OutOfBounds),
+        }
+        if offset % width != 0 {
+            return Err(MmioError::Misaligned);
+        }
+        Ok((self.base.as_usize() + offset) as *const T)
+    }
+}
+
+impl Drop for MmioRegion {
+    fn drop(&mut self) {
+        let _ = revoke_capability(&self.handle);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MmioError {
+    /// 地址不属于任何已登记的设备窗口
+    NotDevice,
+    /// 内核未授权本进程使用该窗口
+    NotGranted,
+    /// 窗口已被其他持有者占用
+    InUse,
+    /// 窗口超出直接映射，无法以不可缓存方式访问
+    Unmappable,
+    /// 访问超出窗口
+    OutOfBounds,
+    /// 偏移未按访问宽度对齐
+    Misaligned,
+    CapabilityError(CapError),
+}
+
+impl From<CapError> for MmioError {
+    fn from(e: CapError) -> Self {
+        MmioError::CapabilityError(e)
+    }
+}
+
+impl Syscall {
+    /// 申领包含 addr 的设备窗口（须已获内核授权，且窗口当前无人持有）
+    pub fn map_device(pid: ProcessId, addr: PhysicalAddr) -> Result<MmioRegion, MmioError> {
+        MmioRegion::map(pid, addr)
+    }
+
+    /// 遍历已登记的设备窗口
+    pub fn device_regions(f: impl FnMut(&device::DeviceRegion)) {
+        device::for_each(f)
+    }
+}
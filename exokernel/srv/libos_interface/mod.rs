@@ -1,5 +1,11 @@
 //! LibOS接口 - 提供Rust风格的资源管理API
 
 pub mod ownership_api;
+pub mod mmio;
+pub mod module;
+pub mod loader;
 
 pub use ownership_api::*;
+pub use mmio::{MmioRegion, MmioError};
+pub use module::{ModuleImage, ModuleError};
+pub use loader::{LoadedImage, LoadError};
@@ -0,0 +1,81 @@
+//! 引导模块访问
+//!
+//! 特性：
+//! - 引导程序加载的 LibOS 镜像、initrd 以只读能力授予进程
+//! - 授予由内核决定（`ModuleImage::grant`，如 `spawn_module`），能力随进程存在，
+//!   进程退出时才撤销；进程经 `Syscall::open_module` 只能打开内核已授予它的模块
+//! - `ModuleImage` 只是视图：打开时检查权限，不持有也不撤销能力
+//! - 内容以切片形式直接读取（模块内存已保留，不会被改写）
+
+use alloc::vec::Vec;
+use crate::boot::modules::{self, BootModule};
+use crate::capability::{ProcessId, CapError, caps, verify_capability};
+use super::ownership_api::{PhysicalAddr, Syscall};
+
+/// 进程可读的引导模块（非拥有的视图）
+pub struct ModuleImage {
+    index: usize,
+    module: BootModule,
+    owner_pid: u32,
+}
+
+impl ModuleImage {
+    /// 把模块授予 pid 并返回视图（仅供内核使用）
+    ///
+    /// 授予是进程作用域的：视图被丢弃后能力仍然有效，进程退出时由 on_process_exit 撤销
+    pub(crate) fn grant(pid: ProcessId, name: &str) -> Result<Self, ModuleError> {
+        let (index, module) = modules::find(name).ok_or(ModuleError::NotFound)?;
+        let _ = modules::grant(pid, index)?;
+        Ok(Self { index, module, owner_pid: pid.as_u32() })
+    }
+
+    /// 按模块名打开内核此前已授予 pid 的模块；没有授予时返回 NotGranted
+    pub fn open_granted(pid: ProcessId, name: &str) -> Result<Self, ModuleError> {
+        let (index, module) = modules::find(name).ok_or(ModuleError::NotFound)?;
+        if !verify_capability(pid, modules::resource_id(index), caps::READ) {
+            return Err(ModuleError::NotGranted);
+        }
+        Ok(Self { index, module, owner_pid: pid.as_u32() })
+    }
+
+    pub fn index(&self) -> usize { self.index }
+    pub fn name(&self) -> &str { self.module.name() }
+    pub fn args(&self) -> &str { self.module.args() }
+    pub fn cmdline(&self) -> &str { &self.module.cmdline }
+    pub fn addr(&self) -> PhysicalAddr { PhysicalAddr::new(self.module.start) }
+    pub fn len(&self) -> usize { self.module.size() }
+    pub fn is_empty(&self) -> bool { self.module.size() == 0 }
+    pub fn owner(&self) -> ProcessId { ProcessId::new(self.owner_pid) }
+
+    /// 模块内容
+    pub fn as_slice(&self) -> &[u8] {
+        modules::bytes(self.index).unwrap_or(&[])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleError {
+    /// 没有这个模块
+    NotFound,
+    /// 内核没有把该模块授予调用进程
+    NotGranted,
+    CapabilityError(CapError),
+}
+
+impl From<CapError> for ModuleError {
+    fn from(e: CapError) -> Self {
+        ModuleError::CapabilityError(e)
+    }
+}
+
+impl Syscall {
+    /// 引导模块列表
+    pub fn boot_modules() -> Vec<BootModule> {
+        modules::list()
+    }
+
+    /// 以只读方式打开内核已授予 pid 的引导模块
+    pub fn open_module(pid: ProcessId, name: &str) -> Result<ModuleImage, ModuleError> {
+        ModuleImage::open_granted(pid, name)
+    }
+}
@@ -10,13 +10,18 @@
 use crate::capability::{
     ProcessId, ThreadId, ResourceId, ResourceType, CapabilityHandle,
     access, lifetime, ScopeKind, CapError,
-    bind_resource_exclusive, bind_resource_readonly, bind_resource_scoped,
+    bind_resource_exclusive, bind_resource_readonly, bind_resource_scoped, bind_frame_owned,
     borrow_shared_ro, borrow_exclusive, release_shared, release_exclusive,
     freeze_exclusive, unfreeze_exclusive,
-    grant_readonly, grant_exclusive, transfer_resource,
-    revoke_capability, revoke_capability_deferred,
-    verify_capability_fast,
+    grant_readonly, grant_exclusive, grant_cow, promote_cow, capability_bits, caps,
+    transfer_resource,
+    revoke_capability, revoke_capability_at, revoke_capability_deferred,
+    release_capability_at,
+    verify_capability_fast, verify_page_access,
+    UserCapOp, UserCapOutput, BatchMode, execute_user_batch,
 };
+use crate::mm::physical::{call_site, CallSite, ClaimError, FrameSize, Zeroing};
+use crate::mm::reclaim::{ReclaimRequest, RequestState};
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::marker::PhantomData;
@@ -34,6 +39,40 @@ impl PhysicalAddr {
     pub const fn as_u64(self) -> u64 { self.0 as u64 }
 }
 
+// ========== 页大小 ==========
+
+/// 物理帧大小（类型参数）
+pub trait PageSize {
+    /// 对应的物理帧大小
+    const FRAME: FrameSize;
+    /// 字节数
+    const SIZE: usize = Self::FRAME.bytes();
+    /// 该大小的帧对应的资源 ID
+    fn resource_id(addr: PhysicalAddr) -> ResourceId;
+}
+
+/// 4KiB 普通页
+pub struct Size4KiB;
+/// 2MiB 大页
+pub struct Size2MiB;
+/// 1GiB 大页
+pub struct Size1GiB;
+
+impl PageSize for Size4KiB {
+    const FRAME: FrameSize = FrameSize::Size4K;
+    fn resource_id(addr: PhysicalAddr) -> ResourceId { ResourceId::from_page_addr(addr.as_usize()) }
+}
+
+impl PageSize for Size2MiB {
+    const FRAME: FrameSize = FrameSize::Size2M;
+    fn resource_id(addr: PhysicalAddr) -> ResourceId { ResourceId::from_huge_2m_addr(addr.as_usize()) }
+}
+
+impl PageSize for Size1GiB {
+    const FRAME: FrameSize = FrameSize::Size1G;
+    fn resource_id(addr: PhysicalAddr) -> ResourceId { ResourceId::from_huge_1g_addr(addr.as_usize()) }
+}
+
 // ========== 类型 1：独占所有的物理页 ==========
 
 /// 独占所有的物理页（Rust 所有权语义）
@@ -43,30 +82,105 @@ impl PhysicalAddr {
 /// - Drop 时自动撤销能力并释放页
 /// - 可降级为只读借用
 /// - 可转移给其他进程
-pub struct OwnedPage {
+/// - 类型参数 `S` 选择帧大小（默认 4KiB，大页为 `OwnedPage<Size2MiB>` 等）
+///
+/// Drop 拿不到调用位置，debug-poison 把隐式释放记在创建该页的调用位置名下；
+/// 需要准确的释放位置时显式调用 `free`
+pub struct OwnedPage<S: PageSize = Size4KiB> {
     handle: CapabilityHandle<access::Exclusive, lifetime::Process>,
     addr: PhysicalAddr,
     owner_pid: u32,
+    _size: PhantomData<S>,
+    /// 创建位置（仅 debug-poison 下非空）
+    site: CallSite,
 }
 
-impl OwnedPage {
-    /// 分配新物理页
+/// 2MiB 大页
+pub type HugePage2M = OwnedPage<Size2MiB>;
+/// 1GiB 大页
+pub type HugePage1G = OwnedPage<Size1GiB>;
+
+impl<S: PageSize> OwnedPage<S> {
+    /// 分配新物理帧（大页按自身大小对齐）
+    ///
+    /// 内容无所谓，但保证不含其他进程留下的数据
+    #[cfg_attr(feature = "debug-poison", track_caller)]
     pub fn alloc(pid: ProcessId) -> Result<Self, AllocError> {
-        let addr = alloc_physical_page().ok_or(AllocError::OutOfMemory)?;
-        let rid = ResourceId::from_page_addr(addr.as_usize());
-        let handle = bind_resource_exclusive(pid, rid)
-            .map_err(|e| AllocError::CapabilityError(e))?;
+        Self::alloc_with(pid, Zeroing::DontCare)
+    }
 
-        Ok(Self {
-            handle,
-            addr,
-            owner_pid: pid.as_u32(),
-        })
+    /// 分配保证全零的新物理帧
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub fn alloc_zeroed(pid: ProcessId) -> Result<Self, AllocError> {
+        Self::alloc_with(pid, Zeroing::Zeroed)
+    }
+
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    fn alloc_with(pid: ProcessId, zeroing: Zeroing) -> Result<Self, AllocError> {
+        let addr = match alloc_physical_frame(pid, S::FRAME, zeroing) {
+            Some(addr) => addr,
+            None => {
+                // 请 LibOS 归还内存；本次分配仍然失败，调用者可稍后重试
+                crate::mm::reclaim::on_allocation_failure(S::FRAME.pages());
+                return Err(AllocError::OutOfMemory);
+            }
+        };
+        Self::bind_new(pid, addr)
+    }
+
+    /// 申领指定物理地址处的空闲帧
+    ///
+    /// 与 from_addr 不同，不要求事先持有能力：帧空闲即可获得
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub fn alloc_at(pid: ProcessId, addr: PhysicalAddr) -> Result<Self, AllocError> {
+        let addr = unsafe {
+            crate::mm::physical::alloc_at(pid.as_u32(), addr.as_usize(), S::FRAME, Zeroing::DontCare)?
+        };
+        Self::bind_new(pid, PhysicalAddr::new(addr))
+    }
+
+    /// 在 [lo, hi) 内申领一个按 align 对齐的空闲帧
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub fn alloc_in_range(
+        pid: ProcessId,
+        lo: PhysicalAddr,
+        hi: PhysicalAddr,
+        align: usize,
+    ) -> Result<Self, AllocError> {
+        let addr = unsafe {
+            crate::mm::physical::alloc_in_range(
+                pid.as_u32(), lo.as_usize(), hi.as_usize(), align, S::FRAME, Zeroing::DontCare,
+            )?
+        };
+        Self::bind_new(pid, PhysicalAddr::new(addr))
+    }
+
+    // 为刚分配的帧绑定独占能力（分配者可授权、可转移）；失败时归还帧
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    fn bind_new(pid: ProcessId, addr: PhysicalAddr) -> Result<Self, AllocError> {
+        let bits = caps::RW | caps::MAP | caps::GRANT | caps::TRANSFER;
+        match bind_resource_scoped(pid, S::resource_id(addr), bits, ScopeKind::Process) {
+            Ok(handle) => Ok(Self {
+                handle,
+                addr,
+                owner_pid: pid.as_u32(),
+                _size: PhantomData,
+                site: call_site(),
+            }),
+            Err(e) => {
+                free_physical_frame(pid, addr, S::FRAME);
+                Err(AllocError::CapabilityError(e))
+            }
+        }
     }
 
     /// 从已有地址创建（需要验证权限）
+    #[cfg_attr(feature = "debug-poison", track_caller)]
     pub fn from_addr(pid: ProcessId, addr: PhysicalAddr) -> Result<Self, AllocError> {
-        let rid = ResourceId::from_page_addr(addr.as_usize());
+        if addr.as_usize() % S::SIZE != 0 {
+            return Err(AllocError::Misaligned);
+        }
+        let rid = S::resource_id(addr);
         // 验证是否已拥有此地址的能力
         if !verify_capability_fast(pid, rid, crate::capability::caps::RW | crate::capability::caps::MAP) {
             return Err(AllocError::PermissionDenied);
@@ -78,6 +192,8 @@ impl OwnedPage {
             handle,
             addr,
             owner_pid: pid.as_u32(),
+            _size: PhantomData,
+            site: call_site(),
         })
     }
 
@@ -86,6 +202,16 @@ impl OwnedPage {
         self.addr
     }
 
+    /// 帧大小（字节）
+    pub fn size(&self) -> usize {
+        S::SIZE
+    }
+
+    /// 该帧对应的资源 ID
+    pub fn resource_id(&self) -> ResourceId {
+        S::resource_id(self.addr)
+    }
+
     /// 获取底层 Capability 句柄（高级用法）
     pub fn capability(&self) -> &CapabilityHandle<access::Exclusive, lifetime::Process> {
         &self.handle
@@ -97,6 +223,7 @@ impl OwnedPage {
         Ok(BorrowedPageRO {
             handle: frozen,
             addr: self.addr,
+            size: S::SIZE,
             tid,
             _phantom: PhantomData,
         })
@@ -106,21 +233,43 @@ impl OwnedPage {
     pub unsafe fn as_slice_mut(&mut self) -> &mut [u8] {
         core::slice::from_raw_parts_mut(
             self.addr.as_usize() as *mut u8,
-            crate::arch::PAGE_SIZE
+            S::SIZE
         )
     }
 
     /// 转移给其他进程（消耗 self）
     pub fn transfer_to(self, to_pid: ProcessId) -> Result<(), CapError> {
         let from_pid = ProcessId::new(self.owner_pid);
-        let rid = ResourceId::from_page_addr(self.addr.as_usize());
-        transfer_resource(from_pid, to_pid, rid)?;
+        transfer_resource(from_pid, to_pid, self.resource_id())?;
         // self 会 drop，但已转移，避免二次释放
         core::mem::forget(self);
         Ok(())
     }
 
+    /// 释放本页（与 Drop 相同，但释放记在调用者名下）
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub fn free(self) {
+        self.release(call_site());
+        core::mem::forget(self);
+    }
+
+    // 写时复制页只放弃自己的引用，其他共享者的能力不受影响；
+    // 否则撤销能力（连同派生出的子能力）
+    fn release(&self, site: CallSite) {
+        let revoked = if self.is_cow() {
+            release_capability_at(&self.handle, site)
+        } else {
+            revoke_capability_at(&self.handle, site)
+        };
+        // 能力已失效（如被回收强制撤销）说明帧已由内核收回，可能已分配给别人，不能再释放；
+        // 能力持有帧引用（share_cow 等派生页）时撤销已放掉它，帧可能已空闲甚至被重新分配
+        if revoked == Ok(false) {
+            free_physical_frame_at(ProcessId::new(self.owner_pid), self.addr, S::FRAME, site);
+        }
+    }
+
     /// 立即撤销并释放（不等待 Drop）
+    #[cfg_attr(feature = "debug-poison", track_caller)]
     pub fn revoke_now(self) -> Result<(), CapError> {
         revoke_capability(&self.handle)?;
         // 防止 Drop 二次释放
@@ -128,6 +277,77 @@ impl OwnedPage {
         Ok(())
     }
 
+    /// 以写时复制方式与 grantee 共享本页
+    ///
+    /// 返回 grantee 持有的页；双方在首次独占借用（写）时各自复制出私有帧
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub fn share_cow(&self, grantee: ProcessId) -> Result<Self, CapError> {
+        let handle = grant_cow(ProcessId::new(self.owner_pid), grantee, self.resource_id())?;
+        Ok(Self {
+            handle,
+            addr: self.addr,
+            owner_pid: grantee.as_u32(),
+            _size: PhantomData,
+            site: call_site(),
+        })
+    }
+
+    /// 是否为尚未复制的写时复制页
+    pub fn is_cow(&self) -> bool {
+        capability_bits(&self.handle).map_or(false, |bits| bits & caps::COW != 0)
+    }
+
+    // 写入前解除写时复制：仍有其他持有者则复制出私有帧并重新绑定能力，否则直接恢复写权限
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    fn break_cow(&mut self) -> Result<(), CapError> {
+        if promote_cow(&self.handle)? {
+            return Ok(());
+        }
+
+        let pid = ProcessId::new(self.owner_pid);
+        let copy = alloc_physical_frame(pid, S::FRAME, Zeroing::DontCare).ok_or(CapError::OutOfMemory)?;
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                self.addr.as_usize() as *const u8,
+                copy.as_usize() as *mut u8,
+                S::SIZE,
+            );
+        }
+        let handle = match bind_resource_exclusive(pid, S::resource_id(copy)) {
+            Ok(h) => h,
+            Err(e) => {
+                free_physical_frame(pid, copy, S::FRAME);
+                return Err(e);
+            }
+        };
+
+        // 放弃原帧：其他共享者保留原内容；若本进程是分配者（能力不持有帧引用），同时交回分配引用
+        if release_capability_at(&self.handle, call_site()) == Ok(false) {
+            free_physical_frame(pid, self.addr, S::FRAME);
+        }
+        self.handle = handle;
+        self.addr = copy;
+        Ok(())
+    }
+
+    /// 把页交给所属进程：按 caps_bits 重新绑定进程作用域的能力，并由该能力持有帧
+    ///
+    /// 之后页不再随 Drop 释放，而是在进程退出（`on_process_exit`）时释放；返回物理地址
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub fn into_process(self, caps_bits: u32) -> Result<PhysicalAddr, CapError> {
+        let pid = ProcessId::new(self.owner_pid);
+        let (addr, rid) = (self.addr, self.resource_id());
+        revoke_capability(&self.handle)?;
+        core::mem::forget(self);
+        match bind_frame_owned::<access::Exclusive, lifetime::Process>(pid, rid, caps_bits, ScopeKind::Process) {
+            Ok(_) => Ok(addr),
+            Err(e) => {
+                free_physical_frame(pid, addr, S::FRAME);
+                Err(e)
+            }
+        }
+    }
+
     /// 延迟撤销（当前有借用时不会立即释放）
     pub fn revoke_deferred(self) -> Result<(), CapError> {
         revoke_capability_deferred(&self.handle)?;
@@ -136,11 +356,9 @@ impl OwnedPage {
     }
 }
 
-impl Drop for OwnedPage {
+impl<S: PageSize> Drop for OwnedPage<S> {
     fn drop(&mut self) {
-        // 尝试撤销能力并释放物理页
-        let _ = revoke_capability(&self.handle);
-        free_physical_page(self.addr);
+        self.release(self.site);
     }
 }
 
@@ -155,22 +373,25 @@ impl Drop for OwnedPage {
 pub struct BorrowedPageRO<'a> {
     handle: CapabilityHandle<access::FrozenShared>,
     addr: PhysicalAddr,
+    size: usize,
     tid: ThreadId,
     _phantom: PhantomData<&'a ()>,
 }
 
 impl<'a> BorrowedPageRO<'a> {
     /// 显式借用已有页
-    pub fn borrow(
-        page: &'a OwnedPage,
+    pub fn borrow<S: PageSize>(
+        page: &'a OwnedPage<S>,
         tid: ThreadId,
         scope: ScopeKind,
     ) -> Result<Self, CapError> {
         let frozen = freeze_exclusive(&page.handle, tid)?;
         borrow_shared_ro(&page.handle, tid, scope)?;
+        crate::mm::physical::map_frame(page.addr.as_usize());
         Ok(Self {
             handle: frozen,
             addr: page.addr,
+            size: S::SIZE,
             tid,
             _phantom: PhantomData,
         })
@@ -190,9 +411,11 @@ impl<'a> BorrowedPageRO<'a> {
             0, // 简化：从 RO_DATA 读取
         );
         borrow_shared_ro(&handle, tid, scope)?;
+        crate::mm::physical::map_frame(inner.addr.as_usize());
         Ok(Self {
             handle,
             addr: inner.addr,
+            size: crate::arch::PAGE_SIZE,
             tid,
             _phantom: PhantomData,
         })
@@ -207,7 +430,7 @@ impl<'a> BorrowedPageRO<'a> {
         unsafe {
             core::slice::from_raw_parts(
                 self.addr.as_usize() as *const u8,
-                crate::arch::PAGE_SIZE
+                self.size
             )
         }
     }
@@ -217,6 +440,7 @@ impl<'a> Drop for BorrowedPageRO<'a> {
     fn drop(&mut self) {
         // 使用 FrozenShared 的释放 API
         let _ = crate::capability::release_shared_frozen(&self.handle, self.tid);
+        crate::mm::physical::unmap_frame(self.addr.as_usize());
     }
 }
 
@@ -231,24 +455,33 @@ impl<'a> Drop for BorrowedPageRO<'a> {
 pub struct BorrowedPageRW<'a> {
     handle: CapabilityHandle<access::Exclusive>,
     addr: PhysicalAddr,
+    size: usize,
     tid: ThreadId,
     _phantom: PhantomData<&'a mut ()>,
 }
 
 impl<'a> BorrowedPageRW<'a> {
     /// 独占借用页
-    pub fn borrow_mut(
-        page: &'a mut OwnedPage,
+    ///
+    /// 写时复制页会在此先复制出私有帧（或在已无其他共享者时直接恢复写权限）
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub fn borrow_mut<S: PageSize>(
+        page: &'a mut OwnedPage<S>,
         tid: ThreadId,
         scope: ScopeKind,
     ) -> Result<Self, CapError> {
+        if page.is_cow() {
+            page.break_cow()?;
+        }
         borrow_exclusive(&page.handle, tid, scope)?;
+        crate::mm::physical::map_frame(page.addr.as_usize());
         // 这里不能移动 handle，需要用原始索引重建
         let (idx, gen) = page.handle.as_raw();
         let handle = CapabilityHandle::new(idx, gen, scope, 0);
         Ok(Self {
             handle,
             addr: page.addr,
+            size: S::SIZE,
             tid,
             _phantom: PhantomData,
         })
@@ -262,7 +495,7 @@ impl<'a> BorrowedPageRW<'a> {
         unsafe {
             core::slice::from_raw_parts_mut(
                 self.addr.as_usize() as *mut u8,
-                crate::arch::PAGE_SIZE
+                self.size
             )
         }
     }
@@ -271,6 +504,7 @@ impl<'a> BorrowedPageRW<'a> {
 impl<'a> Drop for BorrowedPageRW<'a> {
     fn drop(&mut self) {
         let _ = release_exclusive(&self.handle, self.tid);
+        crate::mm::physical::unmap_frame(self.addr.as_usize());
     }
 }
 
@@ -280,7 +514,7 @@ impl<'a> Drop for BorrowedPageRW<'a> {
 ///
 /// 特点：
 /// - 多个所有者共享
-/// - 最后一个所有者释放时回收
+/// - 最后一个持有者（含被授权的其他进程）离开时回收
 /// - 线程安全
 /// - 支持跨进程共享
 pub struct SharedPage {
@@ -291,6 +525,8 @@ struct SharedPageInner {
     handle: CapabilityHandle<access::ReadOnly, lifetime::Process>,
     addr: PhysicalAddr,
     owner_pid: u32,
+    /// 创建位置（Drop 时释放记在它名下）
+    site: CallSite,
 }
 
 impl SharedPage {
@@ -299,6 +535,7 @@ impl SharedPage {
         let handle = page.handle.downgrade();
         let addr = page.addr;
         let owner_pid = page.owner_pid;
+        let site = page.site;
         core::mem::forget(page); // 避免 drop
 
         Self {
@@ -306,11 +543,13 @@ impl SharedPage {
                 handle,
                 addr,
                 owner_pid,
+                site,
             })),
         }
     }
 
     /// 授权只读访问给其他进程
+    #[cfg_attr(feature = "debug-poison", track_caller)]
     pub fn grant_readonly(&self, grantee_pid: ProcessId) -> Result<Self, CapError> {
         let inner = self.inner.lock();
         let grantor_pid = ProcessId::new(inner.owner_pid);
@@ -322,6 +561,7 @@ impl SharedPage {
                 handle: new_handle,
                 addr: inner.addr,
                 owner_pid: grantee_pid.as_u32(),
+                site: call_site(),
             })),
         })
     }
@@ -343,10 +583,15 @@ impl SharedPage {
         }
     }
 
-    /// 获取引用计数
+    /// 获取引用计数（本进程内的句柄数）
     pub fn ref_count(&self) -> usize {
         Arc::strong_count(&self.inner)
     }
+
+    /// 物理帧的持有者数（所有者 + 所有被授权的进程）
+    pub fn holder_count(&self) -> u32 {
+        crate::mm::physical::frame_info(self.addr().as_usize()).map_or(0, |f| f.refs)
+    }
 }
 
 impl Clone for SharedPage {
@@ -358,10 +603,12 @@ impl Clone for SharedPage {
 impl Drop for SharedPage {
     fn drop(&mut self) {
         if Arc::strong_count(&self.inner) == 1 {
-            // 最后一个引用，撤销能力并释放
+            // 本进程最后一个引用：只放弃自己的能力，授权出去的能力继续有效；
+            // 物理帧在所有持有者都离开后才真正释放
             let inner = self.inner.lock();
-            let _ = revoke_capability(&inner.handle);
-            free_physical_page(inner.addr);
+            if release_capability_at(&inner.handle, inner.site) == Ok(false) {
+                free_physical_frame_at(ProcessId::new(inner.owner_pid), inner.addr, FrameSize::Size4K, inner.site);
+            }
         }
     }
 }
@@ -451,10 +698,51 @@ pub struct Syscall;
 
 impl Syscall {
     /// 分配单个物理页
+    #[cfg_attr(feature = "debug-poison", track_caller)]
     pub fn alloc_page(pid: ProcessId) -> Result<OwnedPage, AllocError> {
         OwnedPage::alloc(pid)
     }
 
+    /// 分配单个全零物理页
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub fn alloc_page_zeroed(pid: ProcessId) -> Result<OwnedPage, AllocError> {
+        OwnedPage::alloc_zeroed(pid)
+    }
+
+    /// 申领指定物理地址处的页
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub fn alloc_page_at(pid: ProcessId, addr: PhysicalAddr) -> Result<OwnedPage, AllocError> {
+        OwnedPage::alloc_at(pid, addr)
+    }
+
+    /// 在 [lo, hi) 内申领一个按 align 对齐的页
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub fn alloc_page_in_range(
+        pid: ProcessId,
+        lo: PhysicalAddr,
+        hi: PhysicalAddr,
+        align: usize,
+    ) -> Result<OwnedPage, AllocError> {
+        OwnedPage::alloc_in_range(pid, lo, hi, align)
+    }
+
+    /// 分配 2MiB 大页
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub fn alloc_huge_page_2m(pid: ProcessId) -> Result<HugePage2M, AllocError> {
+        HugePage2M::alloc(pid)
+    }
+
+    /// 分配 1GiB 大页
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub fn alloc_huge_page_1g(pid: ProcessId) -> Result<HugePage1G, AllocError> {
+        HugePage1G::alloc(pid)
+    }
+
+    /// 验证对物理地址的访问权限（4KiB / 2MiB / 1GiB 能力任一覆盖即可）
+    pub fn verify_page_access(pid: ProcessId, addr: PhysicalAddr, required: u32) -> bool {
+        verify_page_access(pid, addr.as_usize(), required)
+    }
+
     /// 批量分配物理页
     pub fn alloc_pages(pid: ProcessId, count: usize) -> Result<PageVec, AllocError> {
         let mut vec = PageVec::with_capacity(pid.as_u32(), count);
@@ -473,18 +761,34 @@ impl Syscall {
         Ok(vec)
     }
 
+    /// 批量能力操作（grant/verify），以 caller 的身份执行，整批只加锁一次
+    ///
+    /// 适用于建立地址空间等需要成千上万次能力操作的场景；
+    /// `mode` 选择遇错即停或尽力执行。只能授权 caller 已持有的资源，
+    /// 新建能力仍走 `alloc_pages` 等分配接口
+    pub fn capability_batch(
+        caller: ProcessId,
+        ops: &[UserCapOp],
+        mode: BatchMode,
+    ) -> Vec<Result<UserCapOutput, CapError>> {
+        execute_user_batch(caller, ops, mode)
+    }
+
     /// 分配共享页
+    #[cfg_attr(feature = "debug-poison", track_caller)]
     pub fn alloc_shared_page(pid: ProcessId) -> Result<SharedPage, AllocError> {
         let page = OwnedPage::alloc(pid)?;
         Ok(SharedPage::from_owned(page))
     }
 
     /// 从地址创建页（需验证权限）
+    #[cfg_attr(feature = "debug-poison", track_caller)]
     pub fn page_from_addr(pid: ProcessId, addr: PhysicalAddr) -> Result<OwnedPage, AllocError> {
         OwnedPage::from_addr(pid, addr)
     }
 
     /// 授权页给其他进程（只读）
+    #[cfg_attr(feature = "debug-poison", track_caller)]
     pub fn grant_page_readonly(
         grantor_pid: ProcessId,
         grantee_pid: ProcessId,
@@ -498,10 +802,13 @@ impl Syscall {
             handle,
             addr,
             owner_pid: grantee_pid.as_u32(),
+            _size: PhantomData,
+            site: call_site(),
         })
     }
 
     /// 授权页给其他进程（独占）
+    #[cfg_attr(feature = "debug-poison", track_caller)]
     pub fn grant_page_exclusive(
         grantor_pid: ProcessId,
         grantee_pid: ProcessId,
@@ -515,9 +822,17 @@ impl Syscall {
             handle,
             addr,
             owner_pid: grantee_pid.as_u32(),
+            _size: PhantomData,
+            site: call_site(),
         })
     }
 
+    /// 以写时复制方式共享页给其他进程
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub fn share_page_cow(page: &OwnedPage, grantee_pid: ProcessId) -> Result<OwnedPage, AllocError> {
+        page.share_cow(grantee_pid).map_err(AllocError::CapabilityError)
+    }
+
     /// 转移页所有权
     pub fn transfer_page(
         page: OwnedPage,
@@ -526,13 +841,34 @@ impl Syscall {
         page.transfer_to(to_pid).map_err(|e| AllocError::CapabilityError(e))
     }
 
+    /// 查询内核发给本进程的内存回收请求
+    pub fn reclaim_requests(pid: ProcessId) -> Vec<ReclaimRequest> {
+        crate::mm::reclaim::pending_for(pid)
+    }
+
+    /// 确认已按请求释放页面（内核会按实际占用量核实）
+    pub fn reclaim_ack(pid: ProcessId, request_id: u64) -> bool {
+        crate::mm::reclaim::acknowledge(pid, request_id)
+    }
+
+    /// 查询回收请求的结果（履约或被中止；只保留最近的若干条）
+    pub fn reclaim_outcome(pid: ProcessId, request_id: u64) -> Option<RequestState> {
+        crate::mm::reclaim::outcome(pid, request_id)
+    }
+
     /// 系统信息
+    ///
+    /// 内存部分需要扫描物理位图，供容量规划等慢路径使用
     pub fn system_info() -> SystemInfo {
         let stats = crate::capability::get_stats();
+        let memory = crate::mm::physical::memory_stats();
         SystemInfo {
-            free_pages: unsafe { crate::mm::physical::free_pages() },
+            free_pages: memory.free_pages,
+            total_pages: memory.total_pages,
             page_size: crate::arch::PAGE_SIZE,
             capability_stats: stats,
+            alloc_counters: crate::mm::physical::counter_stats(),
+            memory,
         }
     }
 }
@@ -543,6 +879,14 @@ impl Syscall {
 pub enum AllocError {
     OutOfMemory,
     PermissionDenied,
+    /// 地址未按帧大小对齐
+    Misaligned,
+    /// 指定的帧已被占用
+    InUse,
+    /// 指定的地址不在受管内存中
+    OutOfRange,
+    /// 指定的地址属于保留内存
+    Reserved,
     CapabilityError(CapError),
 }
 
@@ -552,25 +896,57 @@ impl From<CapError> for AllocError {
     }
 }
 
+impl From<ClaimError> for AllocError {
+    fn from(e: ClaimError) -> Self {
+        match e {
+            ClaimError::OutOfRange => AllocError::OutOfRange,
+            ClaimError::Reserved => AllocError::Reserved,
+            ClaimError::InUse => AllocError::InUse,
+            ClaimError::Misaligned => AllocError::Misaligned,
+            ClaimError::OutsideBudget => AllocError::PermissionDenied,
+        }
+    }
+}
+
 // ========== 系统信息 ==========
 
 #[derive(Debug, Clone)]
 pub struct SystemInfo {
     pub free_pages: usize,
+    pub total_pages: usize,
     pub page_size: usize,
     pub capability_stats: crate::capability::CapabilityStats,
+    /// 分配次数、失败数与延迟
+    pub alloc_counters: crate::mm::physical::AllocCounterStats,
+    /// 按进程、按区域的用量与空闲段直方图
+    pub memory: crate::mm::physical::MemoryStats,
 }
 
-// ========== 底层物理内存函数（需实现） ==========
+impl SystemInfo {
+    /// 某进程持有的页数
+    pub fn pages_used_by(&self, pid: ProcessId) -> usize {
+        self.memory.usage_by_pid.get(&pid.as_u32()).copied().unwrap_or(0)
+    }
+}
 
-fn alloc_physical_page() -> Option<PhysicalAddr> {
-    // 调用物理内存分配器
-    unsafe { crate::mm::physical::alloc_page().map(PhysicalAddr::new) }
+// ========== 底层物理内存函数 ==========
+
+#[cfg_attr(feature = "debug-poison", track_caller)]
+fn alloc_physical_frame(pid: ProcessId, size: FrameSize, zeroing: Zeroing) -> Option<PhysicalAddr> {
+    // 调用物理内存分配器（大页自然对齐，按需清零）
+    unsafe { crate::mm::physical::alloc_frame_with(pid.as_u32(), size, zeroing).map(PhysicalAddr::new) }
 }
 
-fn free_physical_page(addr: PhysicalAddr) {
+#[cfg_attr(feature = "debug-poison", track_caller)]
+fn free_physical_frame(pid: ProcessId, addr: PhysicalAddr, size: FrameSize) {
+    free_physical_frame_at(pid, addr, size, call_site())
+}
+
+fn free_physical_frame_at(pid: ProcessId, addr: PhysicalAddr, size: FrameSize, site: CallSite) {
     // 调用物理内存分配器释放
-    unsafe { crate::mm::physical::free_page(addr.as_usize()) }
+    unsafe {
+        let _ = crate::mm::physical::free_frame_at(pid.as_u32(), addr.as_usize(), size, site);
+    }
 }
 
 // ========== 使用示例 ==========
@@ -635,6 +1011,27 @@ mod examples {
         Ok(())
     }
 
+    fn example_cow() -> Result<(), AllocError> {
+        let pid1 = ProcessId::new(1);
+        let pid2 = ProcessId::new(2);
+        let tid = ThreadId::new(1);
+
+        let page = Syscall::alloc_page(pid1)?;
+
+        // 写时复制共享：两边暂时都只能读同一帧
+        let mut child = Syscall::share_page_cow(&page, pid2)?;
+        assert_eq!(child.addr(), page.addr());
+
+        // 首次写入时 child 得到私有副本，page 保持原内容
+        {
+            let mut borrowed = BorrowedPageRW::borrow_mut(&mut child, tid, ScopeKind::Thread(tid))?;
+            borrowed.as_slice_mut()[0] = 42;
+        }
+        assert_ne!(child.addr(), page.addr());
+
+        Ok(())
+    }
+
     fn example_transfer() -> Result<(), AllocError> {
         let pid1 = ProcessId::new(1);
         let pid2 = ProcessId::new(2);
@@ -649,3 +1046,32 @@ mod examples {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mm::physical::frame_info;
+
+    #[test]
+    fn test_transfer_frees_on_receiver_drop() {
+        let pid1 = ProcessId::new(1);
+        let pid2 = ProcessId::new(2);
+
+        let page = Syscall::alloc_page(pid1).expect("Failed to allocate");
+        let addr = page.addr();
+        Syscall::transfer_page(page, pid2).expect("Failed to transfer");
+
+        // 帧随能力一起转给 pid2
+        let info = frame_info(addr.as_usize()).unwrap();
+        assert_eq!(info.owner, pid2.as_u32());
+        assert_eq!(info.refs, 1);
+        assert!(!verify_capability_fast(pid1, ResourceId::from_page_addr(addr.as_usize()), caps::READ));
+
+        // 接收方丢弃后帧回到空闲状态
+        let received = Syscall::page_from_addr(pid2, addr).expect("Receiver has no capability");
+        drop(received);
+        let info = frame_info(addr.as_usize()).unwrap();
+        assert_eq!(info.owner, 0);
+        assert_eq!(info.refs, 0);
+    }
+}
+
@@ -6,8 +6,13 @@
 //! 2. 自动生命周期管理
 //! 3. 借用检查器友好的API
 //! 4. 零成本抽象
+//!
+//! 分配方法带 `#[track_caller]`，debug-poison 的分配记录指向 LibOS 中的调用者；
+//! 页在 Drop 中释放时记在同一位置名下（Drop 拿不到自己的调用位置）。
 
 use super::ownership::{OwnedPage, PageVec, BorrowedPage};
+use super::color::ColorError;
+use super::physical::{call_site, CallSite, ColorSet, FrameSize, NodePolicy, Zeroing};
 use core::marker::PhantomData;
 use core::ptr::NonNull;
 
@@ -43,37 +48,97 @@ impl<'libos> Allocator<'libos> {
     /// // 使用 page...
     /// // page 在这里自动释放
     /// ```
+    #[cfg_attr(feature = "debug-poison", track_caller)]
     pub fn alloc_page(&self) -> Result<OwnedPage, AllocError> {
         OwnedPage::alloc(self.pid).ok_or(AllocError::OutOfMemory)
     }
 
-    /// 分配多个连续页面
+    /// 分配单个全零页面
     ///
-    /// 返回 PageVec，它管理一组页面的所有权
+    /// alloc_page 返回的页也不会包含其他进程的数据，
+    /// 但可能残留本进程此前写入的内容
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub fn alloc_page_zeroed(&self) -> Result<OwnedPage, AllocError> {
+        OwnedPage::alloc_zeroed(self.pid).ok_or(AllocError::OutOfMemory)
+    }
+
+    /// 分配多个物理连续页面
+    ///
+    /// 返回 PageVec，它管理一组页面的所有权；
+    /// 其中的页按地址递增且物理连续
+    #[cfg_attr(feature = "debug-poison", track_caller)]
     pub fn alloc_pages(&self, count: usize) -> Result<PageVec, AllocError> {
         if count == 0 {
             return Err(AllocError::InvalidSize);
         }
 
+        let base = unsafe { super::physical::alloc_contiguous(self.pid, count) }
+            .ok_or(AllocError::OutOfMemory)?;
+
         let mut vec = PageVec::new(self.pid);
+        for i in 0..count {
+            // 每页单独接管，drop 时逐页释放
+            vec.push(unsafe { OwnedPage::from_raw(base + i * crate::arch::PAGE_SIZE, self.pid) });
+        }
 
-        for _ in 0..count {
-            match OwnedPage::alloc(self.pid) {
-                Some(page) => vec.push(page),
-                None => {
-                    // 分配失败，已分配的页会在 vec drop 时自动释放
-                    return Err(AllocError::OutOfMemory);
-                }
-            }
+        Ok(vec)
+    }
+
+    /// 分配 2^order 个物理连续页面，起始地址按区域大小对齐
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub fn alloc_region(&self, order: usize) -> Result<PageRegion, AllocError> {
+        if order > super::physical::MAX_ORDER {
+            return Err(AllocError::InvalidSize);
+        }
+
+        let base_addr = unsafe { super::physical::alloc_block(self.pid, order) }
+            .ok_or(AllocError::OutOfMemory)?;
+
+        Ok(PageRegion {
+            base_addr,
+            page_count: 1 << order,
+            pid: self.pid,
+            site: call_site(),
+        })
+    }
+
+    /// 在指定 NUMA 节点上分配单个页面
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub fn alloc_page_on_node(&self, node: u32, policy: NodePolicy) -> Result<OwnedPage, AllocError> {
+        let addr = unsafe {
+            super::physical::alloc_on_node(self.pid, node, FrameSize::Size4K, Zeroing::DontCare, policy)
         }
+        .ok_or(AllocError::OutOfMemory)?;
+        Ok(unsafe { OwnedPage::from_raw(addr, self.pid) })
+    }
+
+    /// 分配颜色属于 colors 的单个页面（受进程颜色预算限制）
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub fn alloc_page_colored(&self, colors: ColorSet) -> Result<OwnedPage, AllocError> {
+        OwnedPage::alloc_colored(self.pid, colors).map_err(AllocError::from)
+    }
 
+    /// 分配 count 个颜色属于 colors 的页面（不保证连续）
+    ///
+    /// 全有或全无：任一页分配失败时已分配的页随 PageVec 一起释放
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub fn alloc_pages_colored(&self, count: usize, colors: ColorSet) -> Result<PageVec, AllocError> {
+        if count == 0 {
+            return Err(AllocError::InvalidSize);
+        }
+
+        let mut vec = PageVec::new(self.pid);
+        for _ in 0..count {
+            vec.push(self.alloc_page_colored(colors)?);
+        }
         Ok(vec)
     }
 
     /// 尝试分配多个页面，返回实际分配的数量
     ///
     /// 与 alloc_pages 不同，这个函数会尽可能多地分配，
-    /// 而不是全有或全无
+    /// 而不是全有或全无；返回的页不保证连续
+    #[cfg_attr(feature = "debug-poison", track_caller)]
     pub fn try_alloc_pages(&self, count: usize) -> PageVec {
         let mut vec = PageVec::new(self.pid);
 
@@ -89,15 +154,24 @@ impl<'libos> Allocator<'libos> {
     }
 
     /// 获取分配器的统计信息
+    ///
+    /// 只读计数器，开销与内存大小无关；按进程用量与碎片情况见 memory_stats
     pub fn stats(&self) -> AllocatorStats {
         unsafe {
             AllocatorStats {
                 free_pages: super::physical::free_pages(),
-                total_pages: 65536, // MAX_PAGES
+                total_pages: super::physical::total_pages(),
                 page_size: crate::arch::PAGE_SIZE,
             }
         }
     }
+
+    /// 全局用量与碎片详情（按区域、按进程、空闲段直方图）
+    ///
+    /// 需要扫描全部位图与帧描述符，不要在快路径上调用
+    pub fn memory_stats(&self) -> super::physical::MemoryStats {
+        super::physical::memory_stats()
+    }
 }
 
 /// 分配错误类型
@@ -109,6 +183,18 @@ pub enum AllocError {
     InvalidSize,
     /// 对齐错误
     InvalidAlignment,
+    /// 请求的颜色不在进程的颜色预算内
+    OutsideColorBudget,
+}
+
+impl From<ColorError> for AllocError {
+    fn from(e: ColorError) -> Self {
+        match e {
+            ColorError::OutsideBudget => AllocError::OutsideColorBudget,
+            // TableFull 只由 set_budget 返回
+            ColorError::OutOfMemory | ColorError::TableFull => AllocError::OutOfMemory,
+        }
+    }
 }
 
 impl core::fmt::Display for AllocError {
@@ -117,6 +203,7 @@ impl core::fmt::Display for AllocError {
             AllocError::OutOfMemory => write!(f, "Out of memory"),
             AllocError::InvalidSize => write!(f, "Invalid allocation size"),
             AllocError::InvalidAlignment => write!(f, "Invalid alignment"),
+            AllocError::OutsideColorBudget => write!(f, "Color outside budget"),
         }
     }
 }
@@ -150,26 +237,46 @@ impl AllocatorStats {
     }
 }
 
-/// 页面区域 - 表示一段连续的物理内存
+/// 页面区域 - 表示一段物理连续的内存
 ///
-/// 这个类型提供了对多个连续页面的所有权管理
+/// 区域整体拥有 [base, base + size) 内的所有页，drop 时一次性释放
 pub struct PageRegion {
-    pages: PageVec,
     base_addr: usize,
+    page_count: usize,
+    pid: u32,
+    /// 创建位置（Drop 时释放记在它名下）
+    site: CallSite,
 }
 
 impl PageRegion {
     /// 从 PageVec 创建区域
-    pub fn from_pages(pages: PageVec) -> Option<Self> {
-        if pages.len() == 0 {
-            return None;
+    ///
+    /// 只有当页按地址递增且物理连续时才成功；否则原样返回 PageVec
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub fn from_pages(mut pages: PageVec) -> Result<Self, PageVec> {
+        let base_addr = match pages.get(0) {
+            Some(page) => page.address(),
+            None => return Err(pages),
+        };
+
+        let contiguous = (0..pages.len()).all(|i| {
+            pages.get(i).map(|p| p.address()) == Some(base_addr + i * crate::arch::PAGE_SIZE)
+        });
+        if !contiguous {
+            return Err(pages);
         }
 
-        let base_addr = pages.get(0)?.address();
+        let page_count = pages.len();
+        let pid = pages.get(0).map(|p| p.owner()).unwrap_or(0);
+        while let Some(page) = pages.pop() {
+            page.into_raw();
+        }
 
-        Some(Self {
-            pages,
+        Ok(Self {
             base_addr,
+            page_count,
+            pid,
+            site: call_site(),
         })
     }
 
@@ -180,17 +287,29 @@ impl PageRegion {
 
     /// 获取大小（字节）
     pub fn size(&self) -> usize {
-        self.pages.len() * crate::arch::PAGE_SIZE
+        self.page_count * crate::arch::PAGE_SIZE
     }
 
     /// 获取页数
     pub fn page_count(&self) -> usize {
-        self.pages.len()
+        self.page_count
     }
 
-    /// 获取指定索引的页
-    pub fn get_page(&self, index: usize) -> Option<&OwnedPage> {
-        self.pages.get(index)
+    /// 获取指定索引页的物理地址
+    pub fn page_address(&self, index: usize) -> Option<usize> {
+        if index < self.page_count {
+            Some(self.base_addr + index * crate::arch::PAGE_SIZE)
+        } else {
+            None
+        }
+    }
+}
+
+impl Drop for PageRegion {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = super::physical::free_range_at(self.pid, self.base_addr, self.page_count, self.site);
+        }
     }
 }
 
@@ -239,11 +358,14 @@ impl Drop for AllocationScope {
 
 /// 页面池 - 用于频繁分配/释放的场景
 ///
-/// 维护一个页面缓存，减少分配开销
+/// 维护一个页面缓存，减少分配开销；未命中时走 physical 的每 CPU 弹匣，
+/// 一般也不会触及全局位图
 pub struct PagePool {
     allocator: Allocator<'static>,
     cache: PageVec,
     max_cache_size: usize,
+    /// 若设置，池中只保留这些颜色的页
+    colors: Option<ColorSet>,
 }
 
 impl PagePool {
@@ -253,9 +375,23 @@ impl PagePool {
             allocator: unsafe { Allocator::new(pid) },
             cache: PageVec::new(pid),
             max_cache_size,
+            colors: None,
         }
     }
 
+    /// 创建只分配和缓存 colors 中颜色的页面池
+    pub fn with_colors(pid: u32, max_cache_size: usize, colors: ColorSet) -> Self {
+        Self {
+            colors: Some(colors),
+            ..Self::new(pid, max_cache_size)
+        }
+    }
+
+    /// 池的颜色限制
+    pub fn colors(&self) -> Option<ColorSet> {
+        self.colors
+    }
+
     /// 从池中获取一页（优先使用缓存）
     pub fn acquire(&mut self) -> Result<OwnedPage, AllocError> {
         // 先尝试从缓存获取
@@ -264,16 +400,20 @@ impl PagePool {
         }
 
         // 缓存为空，分配新页
-        self.allocator.alloc_page()
+        match self.colors {
+            Some(colors) => self.allocator.alloc_page_colored(colors),
+            None => self.allocator.alloc_page(),
+        }
     }
 
     /// 归还页面到池中（可能进入缓存）
     pub fn release(&mut self, page: OwnedPage) {
-        if self.cache.len() < self.max_cache_size {
+        let color_ok = self.colors.map_or(true, |c| c & (1 << page.color()) != 0);
+        if color_ok && self.cache.len() < self.max_cache_size {
             self.cache.push(page);
             // page 不会被 drop，保留在缓存中
         } else {
-            // 缓存已满，让 page 自动 drop
+            // 缓存已满或颜色不符，让 page 自动 drop
             drop(page);
         }
     }
@@ -316,6 +456,30 @@ mod tests {
         // 所有页在 pages drop 时自动释放
     }
 
+    #[test]
+    fn test_region_is_contiguous() {
+        let alloc = unsafe { Allocator::new(4) };
+
+        let region = alloc.alloc_region(3).expect("Failed to allocate");
+        assert_eq!(region.page_count(), 8);
+        assert_eq!(region.base_address() % region.size(), 0);
+
+        let pages = alloc.alloc_pages(5).expect("Failed to allocate");
+        let region = PageRegion::from_pages(pages).ok().expect("Pages not contiguous");
+        assert_eq!(region.page_address(4), Some(region.base_address() + 4 * crate::arch::PAGE_SIZE));
+    }
+
+    #[test]
+    fn test_colored_allocation() {
+        let alloc = unsafe { Allocator::new(5) };
+        let colors: ColorSet = 1;
+
+        let pages = alloc.alloc_pages_colored(4, colors).expect("Failed to allocate");
+        for i in 0..pages.len() {
+            assert_eq!(pages.get(i).unwrap().color(), 0);
+        }
+    }
+
     #[test]
     fn test_allocation_scope() {
         let mut scope = AllocationScope::new(3, 5).expect("Failed to create scope");
@@ -0,0 +1,169 @@
+// src/mm/color.rs
+//! 页着色 - 按末级缓存（LLC）划分物理页
+//!
+//! 物理地址中同时属于 LLC 组索引和页号的那几位决定页的“颜色”，
+//! 颜色不同的页不会在 LLC 中互相驱逐。颜色数 = LLC 大小 / (相联度 × 页大小)。
+//!
+//! 每个进程有一个颜色预算（ColorSet），延迟敏感的 LibOS 与批处理 LibOS
+//! 分到互不相交的颜色即可隔离 LLC。未设置预算的进程可使用全部颜色。
+//! 预算由物理分配器在所有分配路径上执行（见 `restriction`），不只是本模块的着色分配。
+
+use core::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use spin::Mutex;
+use crate::arch::PAGE_SIZE;
+use crate::capability::ProcessId;
+use super::physical::{self, ColorSet, Zeroing, KERNEL_PID};
+use crate::boot::cmdline::{self, UintParam};
+
+/// 命令行覆盖的 LLC 大小（字节）与相联度；两者都非零时代替硬件探测
+pub static LLC_SIZE: UintParam = UintParam::new("llc_size", 0, 0, 1 << 32, "LLC size override for page coloring");
+pub static LLC_WAYS: UintParam = UintParam::new("llc_ways", 0, 0, 64, "LLC associativity override");
+
+pub fn register_params() {
+    cmdline::register(&LLC_SIZE);
+    cmdline::register(&LLC_WAYS);
+}
+
+/// 末级缓存几何参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheGeometry {
+    /// 总大小（字节）
+    pub size: usize,
+    /// 相联度
+    pub ways: usize,
+}
+
+impl CacheGeometry {
+    /// 该缓存对应的页颜色数（未向 2 的幂取整）
+    pub fn colors(&self) -> usize {
+        if self.ways == 0 {
+            return 1;
+        }
+        (self.size / (self.ways * PAGE_SIZE)).max(1)
+    }
+}
+
+/// 着色分配失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorError {
+    /// 请求的颜色与进程预算没有交集
+    OutsideBudget,
+    /// 所允许的颜色中没有空闲页
+    OutOfMemory,
+    /// 预算表已满（最多 MAX_BUDGETS 个进程）
+    TableFull,
+}
+
+/// 可同时设置预算的进程数
+pub const MAX_BUDGETS: usize = 64;
+
+// 预算表是定长数组：物理分配器在每次分配时查表，查表不能加锁也不能分配内存
+// （堆扩展会经物理分配器回到这里）。pid 为 0 表示空槽
+struct BudgetSlot {
+    pid: AtomicU32,
+    colors: AtomicU64,
+}
+
+static BUDGETS: [BudgetSlot; MAX_BUDGETS] =
+    [const { BudgetSlot { pid: AtomicU32::new(0), colors: AtomicU64::new(0) } }; MAX_BUDGETS];
+
+// 串行化预算的修改；持有期间不分配内存
+static BUDGETS_WRITE: Mutex<()> = Mutex::new(());
+
+// 设置了预算的进程数；为 0 时分配路径不必查表
+static BUDGETED: AtomicUsize = AtomicUsize::new(0);
+
+fn find_budget(pid: u32) -> Option<ColorSet> {
+    BUDGETS.iter()
+        .find(|slot| slot.pid.load(Ordering::Acquire) == pid)
+        .map(|slot| slot.colors.load(Ordering::Acquire))
+}
+
+/// 由命令行或硬件探测得到 LLC 参数并设置颜色数；都没有时不启用着色
+pub fn init() {
+    let (size, ways) = (LLC_SIZE.get() as usize, LLC_WAYS.get() as usize);
+    if size != 0 && ways != 0 {
+        configure(CacheGeometry { size, ways });
+        return;
+    }
+
+    match crate::arch::llc_geometry() {
+        Some((size, ways)) => {
+            configure(CacheGeometry { size, ways });
+        }
+        None => crate::println!("  [COLOR] LLC geometry unknown, page coloring disabled"),
+    }
+}
+
+/// 按给定的缓存参数设置颜色数（用于配置覆盖探测结果）
+pub fn configure(llc: CacheGeometry) -> usize {
+    let colors = physical::set_color_count(llc.colors());
+    crate::println!("  [COLOR] LLC {}KB {}-way -> {} page colors",
+                    llc.size / 1024, llc.ways, colors);
+    colors
+}
+
+/// 设置进程的颜色预算（KERNEL_PID 的预算不生效，内核分配从不着色）
+pub fn set_budget(pid: ProcessId, colors: ColorSet) -> Result<(), ColorError> {
+    let pid = pid.as_u32();
+    debug_assert!(pid != 0, "pid 0 marks a free budget slot");
+    let colors = colors & physical::all_colors();
+
+    let _guard = BUDGETS_WRITE.lock();
+    if let Some(slot) = BUDGETS.iter().find(|s| s.pid.load(Ordering::Relaxed) == pid) {
+        slot.colors.store(colors, Ordering::Release);
+        return Ok(());
+    }
+    let slot = BUDGETS.iter()
+        .find(|s| s.pid.load(Ordering::Relaxed) == 0)
+        .ok_or(ColorError::TableFull)?;
+    // 先写颜色再发布 pid，查表者看到 pid 时颜色已就绪
+    slot.colors.store(colors, Ordering::Release);
+    slot.pid.store(pid, Ordering::Release);
+    BUDGETED.fetch_add(1, Ordering::Release);
+    Ok(())
+}
+
+/// 清除进程的颜色预算（恢复为全部颜色）
+pub fn clear_budget(pid: ProcessId) {
+    let pid = pid.as_u32();
+    let _guard = BUDGETS_WRITE.lock();
+    if let Some(slot) = BUDGETS.iter().find(|s| s.pid.load(Ordering::Relaxed) == pid) {
+        slot.pid.store(0, Ordering::Release);
+        BUDGETED.fetch_sub(1, Ordering::Release);
+    }
+}
+
+/// 进程可使用的颜色
+pub fn budget(pid: ProcessId) -> ColorSet {
+    find_budget(pid.as_u32()).unwrap_or_else(physical::all_colors) & physical::all_colors()
+}
+
+/// 物理分配器使用：pid 受预算限制时返回允许的颜色
+///
+/// 未设置预算、或预算包含全部颜色时返回 None，分配走不着色的快路径
+/// 不加锁、不分配内存：堆扩展（KERNEL_PID）等路径会在持有其他锁时到达这里
+pub(crate) fn restriction(pid: u32) -> Option<ColorSet> {
+    if pid == KERNEL_PID || BUDGETED.load(Ordering::Acquire) == 0 {
+        return None;
+    }
+    let all = physical::all_colors();
+    let colors = find_budget(pid)? & all;
+    (colors != all).then_some(colors)
+}
+
+/// 在进程预算内分配一个颜色属于 colors 的页
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub fn alloc(pid: u32, colors: ColorSet, zeroing: Zeroing) -> Result<usize, ColorError> {
+    let allowed = colors & budget(ProcessId::new(pid));
+    if allowed == 0 {
+        return Err(ColorError::OutsideBudget);
+    }
+    unsafe { physical::alloc_colored(pid, allowed, zeroing) }.ok_or(ColorError::OutOfMemory)
+}
+
+/// 在进程预算内分配任意颜色的页
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub fn alloc_in_budget(pid: u32, zeroing: Zeroing) -> Result<usize, ColorError> {
+    alloc(pid, physical::all_colors(), zeroing)
+}
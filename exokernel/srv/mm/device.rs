@@ -0,0 +1,218 @@
+// src/mm/device.rs
+//! 设备内存（MMIO）窗口
+//!
+//! UART、中断控制器、PCI 配置空间/BAR、帧缓冲等窗口不是 RAM：
+//! 它们必须用不可缓存的方式映射，并且永远不能进入物理页分配器。
+//! 启动代码在解析引导信息时调用 `register` 登记，`mm::init` 把它们从
+//! 可用内存中扣除；LibOS 通过 `ResourceType::DeviceMemory` 能力获得访问权。
+//! 与 reserved 一样使用定长静态数组，因为登记发生在堆可用之前。
+//!
+//! 委派需要两步：内核先用 `grant` 指定哪个进程可以使用某个窗口，
+//! 该进程再经 `delegate` 取得能力。同一窗口同一时刻只有一个持有者，
+//! 委派时窗口被映射为不可缓存。
+
+use spin::Mutex;
+use crate::arch::PAGE_SIZE;
+use crate::capability::{
+    ProcessId, ResourceId, CapabilityHandle, CapError, access, bind_resource_unique,
+};
+
+/// 最多登记的设备窗口数
+pub const MAX_DEVICE_REGIONS: usize = 64;
+
+/// 设备窗口类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceKind {
+    /// 串口
+    Uart,
+    /// 中断控制器（GIC、PLIC、APIC 等）
+    InterruptController,
+    /// PCI 配置空间（ECAM）
+    PciConfig,
+    /// PCI BAR 窗口
+    PciBar,
+    /// 帧缓冲
+    Framebuffer,
+    /// 定时器等其他设备
+    Other,
+}
+
+impl DeviceKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            DeviceKind::Uart => "uart",
+            DeviceKind::InterruptController => "intc",
+            DeviceKind::PciConfig => "pci-ecam",
+            DeviceKind::PciBar => "pci-bar",
+            DeviceKind::Framebuffer => "framebuffer",
+            DeviceKind::Other => "device",
+        }
+    }
+}
+
+/// 一段设备窗口（按页对齐）
+#[derive(Debug, Clone, Copy)]
+pub struct DeviceRegion {
+    pub base: usize,
+    pub size: usize,
+    pub kind: DeviceKind,
+}
+
+impl DeviceRegion {
+    const fn empty() -> Self {
+        Self { base: 0, size: 0, kind: DeviceKind::Other }
+    }
+
+    pub fn end(&self) -> usize {
+        self.base + self.size
+    }
+
+    pub fn contains(&self, addr: usize) -> bool {
+        addr >= self.base && addr < self.end()
+    }
+
+    /// 该窗口对应的能力资源 ID
+    pub fn resource_id(&self) -> ResourceId {
+        ResourceId::from_device_addr(self.base)
+    }
+}
+
+struct DeviceList {
+    regions: [DeviceRegion; MAX_DEVICE_REGIONS],
+    /// 内核授权使用各窗口的进程（0 表示未授权）
+    granted: [u32; MAX_DEVICE_REGIONS],
+    count: usize,
+}
+
+static DEVICES: Mutex<DeviceList> = Mutex::new(DeviceList {
+    regions: [DeviceRegion::empty(); MAX_DEVICE_REGIONS],
+    granted: [0; MAX_DEVICE_REGIONS],
+    count: 0,
+});
+
+/// 登记设备窗口（向外扩展到页边界）；与已有窗口重叠时合并
+///
+/// 列表已满时返回 false
+pub fn register(base: usize, size: usize, kind: DeviceKind) -> bool {
+    if size == 0 {
+        return true;
+    }
+
+    let start = base & !(PAGE_SIZE - 1);
+    let end = (base + size + PAGE_SIZE - 1) & !(PAGE_SIZE - 1);
+
+    let mut list = DEVICES.lock();
+    let count = list.count;
+    if let Some(r) = list.regions[..count].iter_mut().find(|r| start < r.end() && r.base < end) {
+        let merged_end = end.max(r.end());
+        r.base = r.base.min(start);
+        r.size = merged_end - r.base;
+        return true;
+    }
+
+    if count >= MAX_DEVICE_REGIONS {
+        crate::println!("  [DEV] Device list full, dropping 0x{:x}-0x{:x} ({})",
+                        start, end, kind.name());
+        return false;
+    }
+    list.regions[count] = DeviceRegion { base: start, size: end - start, kind };
+    list.count += 1;
+    true
+}
+
+/// 查找包含该地址的设备窗口
+pub fn find(addr: usize) -> Option<DeviceRegion> {
+    let list = DEVICES.lock();
+    list.regions[..list.count].iter().copied().find(|r| r.contains(addr))
+}
+
+/// 地址是否属于设备内存
+pub fn is_device(addr: usize) -> bool {
+    find(addr).is_some()
+}
+
+/// 遍历所有设备窗口（按登记顺序）
+pub fn for_each(mut f: impl FnMut(&DeviceRegion)) {
+    let (regions, count) = snapshot();
+    regions[..count].iter().for_each(|r| f(r));
+}
+
+/// 从 [base, base+size) 中扣除所有设备窗口，对剩余的每个片段调用 f
+pub fn subtract(base: usize, size: usize, mut f: impl FnMut(usize, usize)) {
+    let (mut regions, count) = snapshot();
+    let regions = &mut regions[..count];
+    regions.sort_unstable_by_key(|r| r.base);
+
+    let end = base + size;
+    let mut cursor = base;
+    for r in regions.iter() {
+        if r.end() <= cursor || r.base >= end {
+            continue;
+        }
+        if r.base > cursor {
+            f(cursor, r.base - cursor);
+        }
+        cursor = r.end();
+        if cursor >= end {
+            return;
+        }
+    }
+
+    if cursor < end {
+        f(cursor, end - cursor);
+    }
+}
+
+/// 内核授权 pid 使用包含 addr 的设备窗口（覆盖之前的授权，已委派出去的能力不受影响）
+pub fn grant(pid: ProcessId, addr: usize) -> Result<DeviceRegion, CapError> {
+    let mut list = DEVICES.lock();
+    let count = list.count;
+    let i = list.regions[..count].iter().position(|r| r.contains(addr)).ok_or(CapError::ResourceNotFound)?;
+    list.granted[i] = pid.as_u32();
+    Ok(list.regions[i])
+}
+
+/// 收回包含 addr 的设备窗口的授权
+pub fn revoke_grant(addr: usize) {
+    let mut list = DEVICES.lock();
+    let count = list.count;
+    if let Some(i) = list.regions[..count].iter().position(|r| r.contains(addr)) {
+        list.granted[i] = 0;
+    }
+}
+
+/// 把包含 addr 的设备窗口委派给 pid（读写 + 映射）
+///
+/// pid 必须已获内核授权（否则 PermissionDenied），且窗口当前没有持有者（否则 AlreadyBound）；
+/// 窗口无法以不可缓存方式映射时返回 Unsupported
+pub fn delegate(pid: ProcessId, addr: usize) -> Result<(DeviceRegion, CapabilityHandle<access::Exclusive>), CapError> {
+    let region = {
+        let list = DEVICES.lock();
+        let i = list.regions[..list.count].iter().position(|r| r.contains(addr))
+            .ok_or(CapError::ResourceNotFound)?;
+        if list.granted[i] != pid.as_u32() {
+            return Err(CapError::PermissionDenied);
+        }
+        list.regions[i]
+    };
+    if !unsafe { crate::arch::map_uncached(region.base, region.size) } {
+        return Err(CapError::Unsupported);
+    }
+    let handle = bind_resource_unique(pid, region.resource_id())?;
+    Ok((region, handle))
+}
+
+/// 打印设备窗口
+pub fn report() {
+    crate::println!("  [DEV] Device memory:");
+    for_each(|r| {
+        crate::println!("  [DEV]   0x{:016x} - 0x{:016x} {:>12} ({}KB)",
+                        r.base, r.end(), r.kind.name(), r.size / 1024);
+    });
+}
+
+// 复制一份后释放锁，回调里可以安全地再次查询
+fn snapshot() -> ([DeviceRegion; MAX_DEVICE_REGIONS], usize) {
+    let list = DEVICES.lock();
+    (list.regions, list.count)
+}
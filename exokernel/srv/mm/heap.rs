@@ -0,0 +1,275 @@
+// src/mm/heap.rs
+//! 内核堆 - #[global_allocator]
+//!
+//! - 小对象：按大小分级的 slab，每个 slab 是一页，对象链在空闲链表上
+//! - 大对象：直接向 physical 申请物理连续的整页（不持堆锁：块分配是线性扫描）
+//! - 按需增长：空闲链表为空时再取一页切分
+//! - 早期启动（physical 尚未初始化）时从静态引导区取页，
+//!   保证解析引导信息时的 Vec 也能工作
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::ptr::{self, NonNull};
+use spin::Mutex;
+use crate::arch::PAGE_SIZE;
+use super::physical::{self, KERNEL_PID};
+
+/// 大小级别（字节），也是该级别对象的对齐
+const SIZE_CLASSES: [usize; 8] = [16, 32, 64, 128, 256, 512, 1024, 2048];
+const NUM_CLASSES: usize = SIZE_CLASSES.len();
+
+/// 引导区大小：physical 可用之前的全部堆内存
+const EARLY_ARENA_SIZE: usize = 256 * 1024;
+
+#[repr(C, align(4096))]
+struct EarlyArena([u8; EARLY_ARENA_SIZE]);
+
+static mut EARLY_ARENA: EarlyArena = EarlyArena([0; EARLY_ARENA_SIZE]);
+
+/// 空闲对象（侵入式链表节点）
+struct FreeObject {
+    next: *mut FreeObject,
+}
+
+/// 单个大小级别的统计
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SizeClassStats {
+    pub object_size: usize,
+    pub slab_pages: usize,
+    pub objects_in_use: usize,
+    pub objects_free: usize,
+    pub allocs: u64,
+    pub frees: u64,
+}
+
+/// 堆统计
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeapStats {
+    pub classes: [SizeClassStats; NUM_CLASSES],
+    pub large_allocs: usize,
+    pub large_pages: usize,
+    pub early_bytes_used: usize,
+    pub failures: u64,
+}
+
+impl HeapStats {
+    /// 堆占用的总页数（slab + 大对象）
+    pub fn total_pages(&self) -> usize {
+        self.classes.iter().map(|c| c.slab_pages).sum::<usize>() + self.large_pages
+    }
+}
+
+struct SizeClass {
+    free_list: *mut FreeObject,
+    stats: SizeClassStats,
+}
+
+struct HeapInner {
+    classes: [SizeClass; NUM_CLASSES],
+    early_used: usize,
+    large_allocs: usize,
+    large_pages: usize,
+    failures: u64,
+}
+
+unsafe impl Send for HeapInner {}
+
+pub struct KernelHeap {
+    inner: Mutex<HeapInner>,
+}
+
+impl KernelHeap {
+    const fn new() -> Self {
+        const EMPTY: SizeClass = SizeClass {
+            free_list: ptr::null_mut(),
+            stats: SizeClassStats {
+                object_size: 0, slab_pages: 0, objects_in_use: 0,
+                objects_free: 0, allocs: 0, frees: 0,
+            },
+        };
+        Self {
+            inner: Mutex::new(HeapInner {
+                classes: [EMPTY; NUM_CLASSES],
+                early_used: 0,
+                large_allocs: 0,
+                large_pages: 0,
+                failures: 0,
+            }),
+        }
+    }
+}
+
+#[global_allocator]
+static HEAP: KernelHeap = KernelHeap::new();
+
+// 选择能同时满足大小与对齐的最小级别
+fn class_index(layout: &Layout) -> Option<usize> {
+    let need = layout.size().max(layout.align());
+    SIZE_CLASSES.iter().position(|&sz| sz >= need)
+}
+
+// 大对象所需页数；对齐超过一页时按对齐放大（alloc_contiguous 按 2 的幂对齐）
+fn large_pages(layout: &Layout) -> usize {
+    let pages = (layout.size() + PAGE_SIZE - 1) / PAGE_SIZE;
+    pages.max(layout.align() / PAGE_SIZE).max(1)
+}
+
+fn early_arena_range() -> (usize, usize) {
+    let start = unsafe { ptr::addr_of!(EARLY_ARENA) as usize };
+    (start, start + EARLY_ARENA_SIZE)
+}
+
+impl HeapInner {
+    // 取 count 个连续页：physical 就绪后走 physical，否则从引导区切
+    unsafe fn grab_pages(&mut self, count: usize) -> Option<NonNull<u8>> {
+        if physical::total_pages() > 0 {
+            return physical::alloc_contiguous(KERNEL_PID, count)
+                .and_then(|addr| NonNull::new(addr as *mut u8));
+        }
+        self.grab_early(count)
+    }
+
+    // 从引导区切 count 页
+    fn grab_early(&mut self, count: usize) -> Option<NonNull<u8>> {
+        let bytes = count * PAGE_SIZE;
+        if self.early_used + bytes > EARLY_ARENA_SIZE {
+            return None;
+        }
+        let (start, _) = early_arena_range();
+        let p = (start + self.early_used) as *mut u8;
+        self.early_used += bytes;
+        NonNull::new(p)
+    }
+
+    // 为级别 idx 增加一个 slab
+    unsafe fn grow(&mut self, idx: usize) -> bool {
+        let page = match self.grab_pages(1) {
+            Some(p) => p.as_ptr(),
+            None => return false,
+        };
+
+        let size = SIZE_CLASSES[idx];
+        let class = &mut self.classes[idx];
+        let count = PAGE_SIZE / size;
+        for i in (0..count).rev() {
+            let obj = page.add(i * size) as *mut FreeObject;
+            (*obj).next = class.free_list;
+            class.free_list = obj;
+        }
+        class.stats.slab_pages += 1;
+        class.stats.objects_free += count;
+        true
+    }
+
+    unsafe fn alloc_small(&mut self, idx: usize) -> *mut u8 {
+        if self.classes[idx].free_list.is_null() && !self.grow(idx) {
+            self.failures += 1;
+            return ptr::null_mut();
+        }
+
+        let class = &mut self.classes[idx];
+        let obj = class.free_list;
+        class.free_list = (*obj).next;
+        class.stats.objects_free -= 1;
+        class.stats.objects_in_use += 1;
+        class.stats.allocs += 1;
+        obj as *mut u8
+    }
+
+    unsafe fn free_small(&mut self, idx: usize, p: *mut u8) {
+        let class = &mut self.classes[idx];
+        let obj = p as *mut FreeObject;
+        (*obj).next = class.free_list;
+        class.free_list = obj;
+        class.stats.objects_free += 1;
+        class.stats.objects_in_use -= 1;
+        class.stats.frees += 1;
+    }
+
+}
+
+impl KernelHeap {
+    // 大对象：向 physical 申请时不持堆锁（块分配最坏 O(区域页数)，
+    // 持锁会让其他 CPU 的小对象分配一直自旋），只在更新计数时加锁
+    unsafe fn alloc_large(&self, layout: &Layout) -> *mut u8 {
+        let pages = large_pages(layout);
+        let page = if physical::total_pages() > 0 {
+            physical::alloc_contiguous(KERNEL_PID, pages).and_then(|addr| NonNull::new(addr as *mut u8))
+        } else {
+            self.inner.lock().grab_early(pages)
+        };
+
+        let mut inner = self.inner.lock();
+        match page {
+            Some(p) => {
+                inner.large_allocs += 1;
+                inner.large_pages += pages;
+                p.as_ptr()
+            }
+            None => {
+                inner.failures += 1;
+                ptr::null_mut()
+            }
+        }
+    }
+
+    unsafe fn free_large(&self, p: *mut u8, layout: &Layout) {
+        let pages = large_pages(layout);
+        {
+            let mut inner = self.inner.lock();
+            inner.large_allocs -= 1;
+            inner.large_pages -= pages;
+        }
+
+        // 引导区中的大对象不回收
+        let (start, end) = early_arena_range();
+        let addr = p as usize;
+        if addr >= start && addr < end {
+            return;
+        }
+        let _ = physical::free_range(KERNEL_PID, addr, pages);
+    }
+}
+
+unsafe impl GlobalAlloc for KernelHeap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        match class_index(&layout) {
+            Some(idx) => self.inner.lock().alloc_small(idx),
+            None => self.alloc_large(&layout),
+        }
+    }
+
+    unsafe fn dealloc(&self, p: *mut u8, layout: Layout) {
+        match class_index(&layout) {
+            Some(idx) => self.inner.lock().free_small(idx, p),
+            None => self.free_large(p, &layout),
+        }
+    }
+}
+
+/// 获取堆统计
+pub fn stats() -> HeapStats {
+    let inner = HEAP.inner.lock();
+    let mut stats = HeapStats {
+        classes: [SizeClassStats::default(); NUM_CLASSES],
+        large_allocs: inner.large_allocs,
+        large_pages: inner.large_pages,
+        early_bytes_used: inner.early_used,
+        failures: inner.failures,
+    };
+    for (i, class) in inner.classes.iter().enumerate() {
+        stats.classes[i] = SizeClassStats { object_size: SIZE_CLASSES[i], ..class.stats };
+    }
+    stats
+}
+
+/// 打印各级别统计
+pub fn report() {
+    let stats = stats();
+    crate::println!("  [HEAP] {} pages ({} large allocs / {} pages), early arena {}KB",
+                    stats.total_pages(), stats.large_allocs, stats.large_pages,
+                    stats.early_bytes_used / 1024);
+    for c in stats.classes.iter().filter(|c| c.slab_pages > 0) {
+        crate::println!("  [HEAP]   {:>5}B: {} slabs, {} used, {} free",
+                        c.object_size, c.slab_pages, c.objects_in_use, c.objects_free);
+    }
+}
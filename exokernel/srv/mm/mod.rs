@@ -4,35 +4,85 @@
 pub mod physical;
 pub mod ownership;
 pub mod allocator;
+pub mod reserved;
+pub mod heap;
+pub mod reclaim;
+pub mod color;
+pub mod device;
+#[cfg(feature = "debug-poison")]
+pub mod poison;
 
 // 重新导出常用类型
-pub use allocator::{Allocator, AllocError, AllocatorStats, PagePool, AllocationScope};
+pub use allocator::{Allocator, AllocError, AllocatorStats, PagePool, AllocationScope, PageRegion};
 pub use ownership::{OwnedPage, PageVec, BorrowedPage};
 
 use alloc::vec::Vec;
 use crate::boot::MemoryRegion;
+use crate::boot::cmdline::{self, UintParam};
+
+/// 交给分配器的内存上限（字节，0 表示不限）
+pub static MEM_LIMIT: UintParam = UintParam::new("mem", 0, 0, u64::MAX, "usable memory limit (0 = all)");
+
+pub fn register_params() {
+    cmdline::register(&MEM_LIMIT);
+    color::register_params();
+}
 
 pub fn init(regions: Vec<MemoryRegion>) {
-    // 找到最大的可用内存区域
-    let mut best_region: Option<&MemoryRegion> = None;
-
-    for region in &regions {
-        if region.available {
-            if let Some(best) = best_region {
-                if region.size > best.size {
-                    best_region = Some(region);
-                }
-            } else {
-                best_region = Some(region);
-            }
-        }
+    unsafe {
+        physical::init();
     }
 
-    if let Some(region) = best_region {
-        unsafe {
-            physical::init(region.base, region.size);
+    // 内核映像与零页；引导信息、模块和 DTB 已由 boot 解析时登记。
+    // 必须在任何 add_region 之前完成：add_region 会清零区域开头的元数据页
+    let (kernel_start, kernel_end) = crate::arch::boot::kernel_phys_range();
+    reserved::reserve(kernel_start, kernel_end - kernel_start, reserved::ReservedKind::Kernel);
+    reserved::reserve(0, crate::arch::PAGE_SIZE, reserved::ReservedKind::Firmware);
+    reserved::report();
+    device::report();
+
+    // 使用内存图中的每一个可分配区域，并扣除保留范围和设备窗口
+    let mut budget = match MEM_LIMIT.get() as usize {
+        0 => usize::MAX,
+        limit => limit,
+    };
+    // 分配器按物理地址直接访问帧（元数据、清零），超出直接映射的部分无法使用
+    let mapped = crate::arch::direct_map_limit();
+    for region in regions.iter().filter(|r| r.kind.is_allocatable()) {
+        if region.base + region.size > mapped {
+            crate::println!("  [MM] Ignoring memory above direct map: 0x{:x} - 0x{:x}",
+                            region.base.max(mapped), region.base + region.size);
         }
-        crate::println!("  [MM] Using region: 0x{:x} + {}MB",
-                        region.base, region.size / (1024 * 1024));
+        if region.base >= mapped {
+            continue;
+        }
+        let size = region.size.min(mapped - region.base);
+        reserved::subtract(region.base, size, |base, size| {
+            device::subtract(base, size, |base, size| {
+                if budget == 0 {
+                    return;
+                }
+                let size = size.min(budget);
+                budget -= size;
+                let pages = unsafe { physical::add_region(base, size, region.node) };
+                if pages == 0 {
+                    crate::println!("  [MM] Skipped region: 0x{:x} + {}KB", base, size / 1024);
+                    return;
+                }
+                if crate::console::log_enabled(crate::console::LOG_DEBUG) {
+                    crate::println!("  [MM] Using region: 0x{:x} + {}MB ({} pages, node {})",
+                                    base, size / (1024 * 1024), pages, region.node);
+                }
+            });
+        });
+    }
+
+    crate::println!("  [MM] {} regions, {} pages total, {}KB reserved",
+                    physical::region_count(), unsafe { physical::total_pages() },
+                    reserved::total_bytes() / 1024);
+    for zone in physical::zones() {
+        crate::println!("  [MM] Node {}: {} regions, {} pages", zone.node, zone.regions, zone.total_pages);
     }
+    color::init();
+    heap::report();
 }
@@ -3,26 +3,87 @@
 
 use core::ptr::NonNull;
 use core::marker::PhantomData;
+use super::physical::{call_site, CallSite};
 
 /// 物理页 - 拥有所有权
+///
+/// Drop 拿不到调用位置，debug-poison 把隐式释放记在分配该页的位置名下
 pub struct OwnedPage {
     addr: usize,
     pid: u32,
+    site: CallSite,
     _marker: PhantomData<*mut u8>, // 不是Send/Sync
 }
 
 impl OwnedPage {
     /// 分配新页（获取所有权）
+    #[cfg_attr(feature = "debug-poison", track_caller)]
     pub fn alloc(pid: u32) -> Option<Self> {
+        // 闭包不在 track_caller 链上，调用位置须在外面取
+        let site = call_site();
         unsafe {
             super::physical::alloc_raw(pid).map(|addr| Self {
                 addr,
                 pid,
+                site,
                 _marker: PhantomData,
             })
         }
     }
 
+    /// 分配一个保证全零的新页
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub fn alloc_zeroed(pid: u32) -> Option<Self> {
+        let site = call_site();
+        unsafe {
+            super::physical::alloc_raw_zeroed(pid).map(|addr| Self {
+                addr,
+                pid,
+                site,
+                _marker: PhantomData,
+            })
+        }
+    }
+
+    /// 在进程颜色预算内分配一个颜色属于 colors 的页
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub fn alloc_colored(pid: u32, colors: super::physical::ColorSet) -> Result<Self, super::color::ColorError> {
+        let site = call_site();
+        super::color::alloc(pid, colors, super::physical::Zeroing::DontCare).map(|addr| Self {
+            addr,
+            pid,
+            site,
+            _marker: PhantomData,
+        })
+    }
+
+    /// 页的颜色
+    pub fn color(&self) -> usize {
+        super::physical::page_color(self.addr)
+    }
+
+    /// 接管一个已由 physical 层分配给 pid 的页
+    ///
+    /// # Safety
+    ///
+    /// addr 必须已分配给 pid，且没有其他 OwnedPage 指向它
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    pub(crate) unsafe fn from_raw(addr: usize, pid: u32) -> Self {
+        Self {
+            addr,
+            pid,
+            site: call_site(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// 放弃所有权但不释放页，返回物理地址
+    pub(crate) fn into_raw(self) -> usize {
+        let addr = self.addr;
+        core::mem::forget(self);
+        addr
+    }
+
     /// 获取物理地址（不可变借用）
     pub fn address(&self) -> usize {
         self.addr
@@ -55,7 +116,7 @@ impl OwnedPage {
 impl Drop for OwnedPage {
     fn drop(&mut self) {
         unsafe {
-            let _ = super::physical::free_raw(self.pid, self.addr);
+            let _ = super::physical::free_raw_at(self.pid, self.addr, self.site);
         }
     }
 }
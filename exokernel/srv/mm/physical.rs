@@ -1,124 +1,1449 @@
 // src/mm/physical.rs
 //! 底层物理内存分配器
+//!
+//! 管理启动内存图中的每一个可用区域。每个区域在自身起始处存放
+//! 元数据（分配位图 + 每帧描述符），因此没有固定的页数上限。
+//!
+//! 除单页分配外，还提供 2 的幂次连续分配（接口与 buddy 相同，实现不同）：
+//! `alloc_block(pid, order)` 返回 2^order 个物理连续的页，
+//! 起始地址按 `PAGE_SIZE << order` 对齐。
+//! 没有按阶的空闲链表：块分配在位图上按块大小逐个对齐地址尝试申领，
+//! 最坏 O(区域页数)（每个候选块检查 2^order 位），`alloc_contiguous` 同理。
+//! 块分配不在单页快路径上，主要用于大页、DMA 缓冲区与内核堆的大块。
+//!
+//! 页清零（防止跨 LibOS 数据泄漏）：释放时只标记为“脏”，
+//! 重新分配给不同的所有者之前一定会清零；已知为零的页走快路径。
+//! 空闲时 `scrub_idle` 在后台预先清零脏页。
+//!
+//! 单页分配的快路径：两级位图（摘要位图中每一位表示一个位图字是否已满）
+//! 加上每 CPU 的 next-fit 游标，避免每次都从第 0 个字线性扫描。
+//!
+//! 启用 `debug-poison` 时，分配/释放路径带 `#[track_caller]`，
+//! 帧描述符记录调用位置，释放的页被投毒（见 `poison` 模块）。
+//! `#[track_caller]` 链在 Drop 和能力表的撤销路径上会断开，
+//! 这些路径改为显式传递 `CallSite`（见 `put_frames_at`、`free_raw_at`）。
 
-use core::sync::atomic::{AtomicUsize, Ordering};
+use core::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use spin::Mutex;
 use crate::arch::PAGE_SIZE;
 
-const MAX_PAGES: usize = 65536;
-const BITMAP_SIZE: usize = MAX_PAGES / 64;
+/// 分配/释放的调用位置（启用 debug-poison 时为源码位置，否则为空）
+#[cfg(feature = "debug-poison")]
+pub type CallSite = &'static core::panic::Location<'static>;
+#[cfg(not(feature = "debug-poison"))]
+pub type CallSite = ();
 
-struct PhysicalAllocator {
+/// 当前调用位置（在 `#[track_caller]` 链上时为链外最近的调用者）
+#[cfg(feature = "debug-poison")]
+#[track_caller]
+#[inline(always)]
+pub fn call_site() -> CallSite {
+    core::panic::Location::caller()
+}
+
+#[cfg(not(feature = "debug-poison"))]
+#[inline(always)]
+pub fn call_site() -> CallSite {}
+
+/// 最多管理的区域数（固件内存图通常只有十几项）
+pub const MAX_REGIONS: usize = 64;
+
+/// 最大分配阶：2^18 页 = 1GiB
+pub const MAX_ORDER: usize = 18;
+
+/// next-fit 游标的 CPU 数上限
+const MAX_CPUS: usize = 256;
+
+/// 内核自身（堆等）持有的页的所有者；0 表示空闲
+pub const KERNEL_PID: u32 = u32::MAX;
+
+/// 帧大小：4KiB 普通页与 2MiB / 1GiB 大页
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FrameSize {
+    Size4K,
+    Size2M,
+    Size1G,
+}
+
+impl FrameSize {
+    /// 对应的分配阶
+    pub const fn order(self) -> usize {
+        match self {
+            FrameSize::Size4K => 0,
+            FrameSize::Size2M => 9,
+            FrameSize::Size1G => 18,
+        }
+    }
+
+    /// 字节数（同时也是对齐要求）
+    pub const fn bytes(self) -> usize {
+        PAGE_SIZE << self.order()
+    }
+
+    /// 4KiB 页数
+    pub const fn pages(self) -> usize {
+        1 << self.order()
+    }
+}
+
+/// 分配时对页内容的要求
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zeroing {
+    /// 必须全零
+    Zeroed,
+    /// 内容无所谓（仍然保证看不到其他所有者留下的数据）
+    DontCare,
+}
+
+/// 帧标志：内容已知全零
+const FRAME_ZEROED: u32 = 1 << 0;
+/// 帧标志：所有者已释放，但仍有其他持有者（授权、共享）
+const FRAME_ORPHANED: u32 = 1 << 1;
+/// 帧标志：空闲且已填入投毒图案（仅 debug-poison）
+#[cfg(feature = "debug-poison")]
+const FRAME_POISONED: u32 = 1 << 2;
+#[cfg(feature = "debug-poison")]
+const FRAME_POISON_MASK: u32 = FRAME_POISONED;
+#[cfg(not(feature = "debug-poison"))]
+const FRAME_POISON_MASK: u32 = 0;
+
+/// 每帧描述符
+#[repr(C)]
+struct FrameMeta {
+    owner: AtomicU32,
+    /// 最近一次释放前的所有者（0 表示从未分配或已清零）
+    last_owner: AtomicU32,
+    flags: AtomicU32,
+    /// 持有者数：所有者 + 每个派生的页能力；降到 0 时帧被释放
+    refs: AtomicU32,
+    /// 当前映射（借用访问）数
+    maps: AtomicU32,
+    /// 最近一次分配与释放的 pid 和调用位置
+    #[cfg(feature = "debug-poison")]
+    history: super::poison::FrameHistory,
+}
+
+impl FrameMeta {
+    // 分配后、交给调用者前：按需清零
+    #[cfg_attr(feature = "debug-poison", track_caller)]
+    unsafe fn prepare(&self, addr: usize, pid: u32, zeroing: Zeroing) {
+        #[cfg(feature = "debug-poison")]
+        {
+            if self.flags.load(Ordering::Acquire) & FRAME_POISONED != 0 {
+                if let Some(offset) = super::poison::check(addr) {
+                    super::poison::report_use_after_free(addr, offset, &self.history);
+                }
+            }
+            self.history.record_alloc(pid, core::panic::Location::caller());
+        }
+
+        let flags = self.flags.load(Ordering::Acquire);
+        let last = self.last_owner.load(Ordering::Acquire);
+
+        let known_zero = flags & FRAME_ZEROED != 0;
+        let must_zero = match zeroing {
+            Zeroing::Zeroed => !known_zero,
+            Zeroing::DontCare => !known_zero && last != 0 && last != pid,
+        };
+
+        if must_zero {
+            core::ptr::write_bytes(addr as *mut u8, 0, PAGE_SIZE);
+            SCRUB_STATS.zeroed_on_alloc.fetch_add(1, Ordering::Relaxed);
+        } else if known_zero {
+            SCRUB_STATS.known_zero_hits.fetch_add(1, Ordering::Relaxed);
+        }
+
+        // 交给所有者后内容随时会变
+        self.flags.fetch_and(!(FRAME_ZEROED | FRAME_ORPHANED | FRAME_POISON_MASK), Ordering::AcqRel);
+        self.refs.store(1, Ordering::Release);
+        self.maps.store(0, Ordering::Release);
+        self.owner.store(pid, Ordering::Release);
+    }
+
+    // 释放：记录原所有者并标记为脏
+    fn retire(&self, addr: usize, pid: u32, site: CallSite) {
+        self.last_owner.store(pid, Ordering::Release);
+        self.flags.fetch_and(!(FRAME_ZEROED | FRAME_ORPHANED), Ordering::AcqRel);
+
+        #[cfg(feature = "debug-poison")]
+        {
+            self.history.record_free(pid, site);
+            unsafe { super::poison::fill(addr); }
+            self.flags.fetch_or(FRAME_POISONED, Ordering::AcqRel);
+        }
+        #[cfg(not(feature = "debug-poison"))]
+        let _ = (addr, site);
+
+        self.owner.store(0, Ordering::Release);
+    }
+
+    // 释放者不再持有该帧：已空闲，或自己已放弃（仅剩其他持有者）
+    #[cfg(feature = "debug-poison")]
+    fn already_freed_by(&self, pid: u32) -> bool {
+        let owner = self.owner.load(Ordering::Acquire);
+        owner == 0 || (owner == pid && self.flags.load(Ordering::Acquire) & FRAME_ORPHANED != 0)
+    }
+
+    // 所有者仍持有该帧（已分配且未放弃）
+    fn owned_by(&self, pid: u32) -> bool {
+        self.owner.load(Ordering::Acquire) == pid
+            && self.flags.load(Ordering::Acquire) & FRAME_ORPHANED == 0
+    }
+
+    // 增加一个持有者；帧已空闲时失败
+    fn get(&self) -> bool {
+        let mut refs = self.refs.load(Ordering::Acquire);
+        loop {
+            if refs == 0 {
+                return false;
+            }
+            match self.refs.compare_exchange_weak(refs, refs + 1, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => return true,
+                Err(current) => refs = current,
+            }
+        }
+    }
+
+    // 减少一个持有者；返回是否为最后一个
+    //
+    // 启用 debug-poison 时检查下溢：对已无持有者的帧再放弃引用
+    // 会把计数绕回到 u32::MAX，帧从此再也不会被释放
+    fn put(&self, addr: usize, site: CallSite) -> bool {
+        #[cfg(feature = "debug-poison")]
+        {
+            match self.refs.fetch_update(Ordering::AcqRel, Ordering::Acquire, |r| r.checked_sub(1)) {
+                Ok(prev) => prev == 1,
+                Err(_) => {
+                    super::poison::report_underflow(addr, site, &self.history);
+                    false
+                }
+            }
+        }
+        #[cfg(not(feature = "debug-poison"))]
+        {
+            let _ = (addr, site);
+            self.refs.fetch_sub(1, Ordering::AcqRel) == 1
+        }
+    }
+}
+
+/// 清零统计
+struct ScrubStats {
+    zeroed_on_alloc: AtomicU64,
+    zeroed_idle: AtomicU64,
+    known_zero_hits: AtomicU64,
+}
+
+static SCRUB_STATS: ScrubStats = ScrubStats {
+    zeroed_on_alloc: AtomicU64::new(0),
+    zeroed_idle: AtomicU64::new(0),
+    known_zero_hits: AtomicU64::new(0),
+};
+
+/// 后台清零的扫描位置（全局页序号）
+static SCRUB_CURSOR: AtomicUsize = AtomicUsize::new(0);
+
+/// 每 CPU 的 next-fit 游标：(区域索引 << 32) | 位图字索引
+static NEXT_FIT: [AtomicUsize; MAX_CPUS] = [const { AtomicUsize::new(0) }; MAX_CPUS];
+
+/// 分配计数器（每 CPU 一份，读取时汇总）
+#[repr(align(64))]
+struct AllocCounters {
+    allocs: AtomicU64,
+    pages_allocated: AtomicU64,
+    frees: AtomicU64,
+    failures: AtomicU64,
+    latency_total: AtomicU64,
+    latency_max: AtomicU64,
+}
+
+impl AllocCounters {
+    const fn new() -> Self {
+        Self {
+            allocs: AtomicU64::new(0),
+            pages_allocated: AtomicU64::new(0),
+            frees: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+            latency_total: AtomicU64::new(0),
+            latency_max: AtomicU64::new(0),
+        }
+    }
+
+    fn record_alloc(&self, t0: u64, pages: usize) {
+        let dt = crate::arch::timestamp().wrapping_sub(t0);
+        self.allocs.fetch_add(1, Ordering::Relaxed);
+        self.pages_allocated.fetch_add(pages as u64, Ordering::Relaxed);
+        self.latency_total.fetch_add(dt, Ordering::Relaxed);
+        self.latency_max.fetch_max(dt, Ordering::Relaxed);
+    }
+
+    fn record_failure(&self) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+static COUNTERS: [AllocCounters; MAX_CPUS] = [const { AllocCounters::new() }; MAX_CPUS];
+
+// 当前 CPU 在每 CPU 数组中的下标
+//
+// arch::cpu_id 在 x86_64 上是 CPUID（序列化指令，虚拟机中还会陷出），
+// 所以每次操作只读一次，再传给 counters/magazine/refill
+fn this_cpu() -> usize {
+    crate::arch::cpu_id() % MAX_CPUS
+}
+
+fn counters(cpu: usize) -> &'static AllocCounters {
+    &COUNTERS[cpu]
+}
+
+/// 每 CPU 弹匣容量（页）
+const MAGAZINE_SIZE: usize = 64;
+
+/// 弹匣与全局位图之间一次搬运的页数
+const MAGAZINE_BATCH: usize = 32;
+
+/// 每 CPU 的空闲帧弹匣
+///
+/// 弹匣中的帧在位图中已被占用（所有者为 0），不计入区域的空闲页数。
+/// 单页分配/释放只访问本 CPU 的弹匣；空了从全局位图批量补充，
+/// 满了批量归还，全局位图上的 CAS 因此被摊薄到每 MAGAZINE_BATCH 次一次。
+///
+/// 弹匣锁是普通自旋锁，持锁期间不关中断：中断处理程序不得分配或释放物理帧，
+/// 否则在同一 CPU 上打断持锁的代码会自旋死锁。需要在中断上下文分配时，
+/// 必须先把这里改为持锁期间关中断。
+#[repr(align(64))]
+struct Magazine {
+    inner: Mutex<MagazineInner>,
+    /// 弹匣内的帧数（供 free_pages 无锁读取）
+    count: AtomicUsize,
+}
+
+struct MagazineInner {
+    addrs: [usize; MAGAZINE_SIZE],
+    len: usize,
+}
+
+impl Magazine {
+    const fn new() -> Self {
+        Self {
+            inner: Mutex::new(MagazineInner { addrs: [0; MAGAZINE_SIZE], len: 0 }),
+            count: AtomicUsize::new(0),
+        }
+    }
+}
+
+static MAGAZINES: [Magazine; MAX_CPUS] = [const { Magazine::new() }; MAX_CPUS];
+
+fn magazine(cpu: usize) -> &'static Magazine {
+    &MAGAZINES[cpu]
+}
+
+/// 分配器计数（延迟单位为 arch::timestamp 的滴答）
+#[derive(Debug, Clone, Copy)]
+pub struct AllocCounterStats {
+    pub allocs: u64,
+    pub pages_allocated: u64,
+    pub pages_freed: u64,
+    pub failures: u64,
+    pub avg_latency: u64,
+    pub max_latency: u64,
+}
+
+/// 单个物理区域
+struct Region {
+    /// 第一个可分配页的物理地址（元数据页之后）
     base: usize,
     total_pages: usize,
+    /// 所属 NUMA 节点
+    node: u32,
     free_pages: AtomicUsize,
-    bitmap: [AtomicUsize; BITMAP_SIZE],
-    owners: [AtomicU32; MAX_PAGES],
+    bitmap: *const AtomicUsize,
+    /// 摘要位图：第 i 位置位表示 bitmap[i] 已满（仅作提示，满字可能未置位）
+    summary: *const AtomicUsize,
+    frames: *const FrameMeta,
 }
 
-struct AtomicU32(core::sync::atomic::AtomicU32);
+impl Region {
+    const fn empty() -> Self {
+        Self {
+            base: 0,
+            total_pages: 0,
+            node: 0,
+            free_pages: AtomicUsize::new(0),
+            bitmap: core::ptr::null(),
+            summary: core::ptr::null(),
+            frames: core::ptr::null(),
+        }
+    }
+
+    fn bitmap_words(&self) -> usize {
+        (self.total_pages + 63) / 64
+    }
+
+    fn bitmap(&self) -> &[AtomicUsize] {
+        unsafe { core::slice::from_raw_parts(self.bitmap, self.bitmap_words()) }
+    }
+
+    fn summary(&self) -> &[AtomicUsize] {
+        unsafe { core::slice::from_raw_parts(self.summary, (self.bitmap_words() + 63) / 64) }
+    }
+
+    // 某个位图字刚被填满
+    fn mark_full(&self, word_idx: usize) {
+        let s = &self.summary()[word_idx / 64];
+        let b = 1usize << (word_idx % 64);
+        s.fetch_or(b, Ordering::AcqRel);
+        // 重新检查：并发释放可能已经让该字不满
+        if self.bitmap()[word_idx].load(Ordering::Acquire) != usize::MAX {
+            s.fetch_and(!b, Ordering::AcqRel);
+        }
+    }
+
+    // 某个位图字有位被清除
+    fn mark_not_full(&self, word_idx: usize) {
+        self.summary()[word_idx / 64].fetch_and(!(1usize << (word_idx % 64)), Ordering::AcqRel);
+    }
+
+    fn frames(&self) -> &[FrameMeta] {
+        unsafe { core::slice::from_raw_parts(self.frames, self.total_pages) }
+    }
+
+    fn contains(&self, addr: usize) -> bool {
+        addr >= self.base && addr < self.base + self.total_pages * PAGE_SIZE
+    }
+
+    fn page_index(&self, addr: usize) -> usize {
+        (addr - self.base) / PAGE_SIZE
+    }
+
+    fn end(&self) -> usize {
+        self.base + self.total_pages * PAGE_SIZE
+    }
+}
+
+struct PhysicalAllocator {
+    regions: [Region; MAX_REGIONS],
+    region_count: usize,
+    total_pages: usize,
+    free_pages: AtomicUsize,
+}
 
 unsafe impl Sync for PhysicalAllocator {}
 
 static mut ALLOCATOR: PhysicalAllocator = PhysicalAllocator {
-    base: 0,
+    regions: [const { Region::empty() }; MAX_REGIONS],
+    region_count: 0,
     total_pages: 0,
     free_pages: AtomicUsize::new(0),
-    bitmap: [const { AtomicUsize::new(0) }; BITMAP_SIZE],
-    owners: [const { AtomicU32(core::sync::atomic::AtomicU32::new(0)) }; MAX_PAGES],
 };
 
-pub unsafe fn init(base: usize, size: usize) {
-    ALLOCATOR.base = base;
-    ALLOCATOR.total_pages = (size / PAGE_SIZE).min(MAX_PAGES);
-    ALLOCATOR.free_pages.store(ALLOCATOR.total_pages, Ordering::Release);
+fn regions() -> &'static [Region] {
+    unsafe { &ALLOCATOR.regions[..ALLOCATOR.region_count] }
+}
 
-    for i in 0..BITMAP_SIZE {
-        ALLOCATOR.bitmap[i].store(0, Ordering::Release);
+fn find_region(addr: usize) -> Option<&'static Region> {
+    regions().iter().find(|r| r.contains(addr))
+}
+
+/// 清空所有区域（启动时调用一次，随后逐个 add_region）
+pub unsafe fn init() {
+    ALLOCATOR.region_count = 0;
+    ALLOCATOR.total_pages = 0;
+    ALLOCATOR.free_pages.store(0, Ordering::Release);
+}
+
+/// 将一段可用物理内存加入分配器
+///
+/// 区域头部会被切出若干页用于存放位图和帧描述符。
+/// 返回实际可分配的页数（区域太小或区域表已满时为 0）。
+///
+/// 超出直接映射的部分被截掉；与保留范围（内核映像等）重叠的区域被拒绝，
+/// 因此保留范围必须在加入区域之前登记。
+///
+/// # Safety
+///
+/// 调用者必须保证该范围是未被使用的 RAM
+pub unsafe fn add_region(base: usize, size: usize, node: u32) -> usize {
+    if ALLOCATOR.region_count >= MAX_REGIONS {
+        return 0;
     }
 
-    for i in 0..MAX_PAGES {
-        ALLOCATOR.owners[i].0.store(0, Ordering::Release);
+    let start = (base + PAGE_SIZE - 1) & !(PAGE_SIZE - 1);
+    let end = (base + size).min(crate::arch::direct_map_limit()) & !(PAGE_SIZE - 1);
+    if end <= start {
+        return 0;
+    }
+    // 下面会清零区域开头：绝不能覆盖内核映像或其他保留内容
+    if super::reserved::overlaps(start, end - start) {
+        return 0;
+    }
+
+    let pages = (end - start) / PAGE_SIZE;
+    let words = (pages + 63) / 64;
+    let bitmap_bytes = words * core::mem::size_of::<AtomicUsize>();
+    let summary_bytes = ((words + 63) / 64) * core::mem::size_of::<AtomicUsize>();
+    let frames_bytes = pages * core::mem::size_of::<FrameMeta>();
+    let meta_pages = (bitmap_bytes + summary_bytes + frames_bytes + PAGE_SIZE - 1) / PAGE_SIZE;
+    if pages <= meta_pages {
+        return 0;
+    }
+
+    // 元数据放在区域开头，全部清零即为“空闲、无所有者”
+    core::ptr::write_bytes(start as *mut u8, 0, meta_pages * PAGE_SIZE);
+
+    let usable = pages - meta_pages;
+    let region = &mut ALLOCATOR.regions[ALLOCATOR.region_count];
+    region.base = start + meta_pages * PAGE_SIZE;
+    region.total_pages = usable;
+    region.node = node;
+    region.free_pages.store(usable, Ordering::Release);
+    region.bitmap = start as *const AtomicUsize;
+    region.summary = (start + bitmap_bytes) as *const AtomicUsize;
+    region.frames = (start + bitmap_bytes + summary_bytes) as *const FrameMeta;
+
+    // 最后一个字中超出区域的位预先置为已占用，扫描永远不会选中它们
+    let tail = usable % 64;
+    if tail != 0 {
+        region.bitmap()[usable / 64].store(!run_mask(0, tail), Ordering::Release);
+    }
+
+    ALLOCATOR.region_count += 1;
+    ALLOCATOR.total_pages += usable;
+    ALLOCATOR.free_pages.fetch_add(usable, Ordering::AcqRel);
+
+    usable
+}
+
+// 从 start_word 开始（绕回一圈）在区域内分配一页，返回 (地址, 所在位图字)
+#[cfg_attr(feature = "debug-poison", track_caller)]
+unsafe fn alloc_in_region(
+    region: &Region,
+    start_word: usize,
+    pid: u32,
+    zeroing: Zeroing,
+) -> Option<(usize, usize)> {
+    let (addr, word_idx) = claim_in_region(region, start_word)?;
+    region.frames()[region.page_index(addr)].prepare(addr, pid, zeroing);
+    Some((addr, word_idx))
+}
+
+// 从 start_word 开始（绕回一圈）在区域内占用一页但不交给任何所有者，返回 (地址, 所在位图字)
+unsafe fn claim_in_region(region: &Region, start_word: usize) -> Option<(usize, usize)> {
+    let words = region.bitmap_words();
+    let summary = region.summary();
+    let start = start_word % words;
+    let first_sw = start / 64;
+
+    // 第 0 轮只看起始摘要字中 start 及之后的字，最后一轮补上之前的字
+    for n in 0..=summary.len() {
+        let sw = (first_sw + n) % summary.len();
+        let mut candidates = !summary[sw].load(Ordering::Acquire) & run_mask(0, (words - sw * 64).min(64));
+        if n == 0 {
+            candidates &= !run_mask(0, start % 64);
+        } else if n == summary.len() {
+            candidates &= run_mask(0, start % 64);
+        }
+
+        while candidates != 0 {
+            let word_idx = sw * 64 + candidates.trailing_zeros() as usize;
+            candidates &= candidates - 1;
+
+            if let Some(page_idx) = claim_in_word(region, word_idx) {
+                let addr = region.base + page_idx * PAGE_SIZE;
+                region.free_pages.fetch_sub(1, Ordering::AcqRel);
+                ALLOCATOR.free_pages.fetch_sub(1, Ordering::AcqRel);
+                return Some((addr, word_idx));
+            }
+        }
     }
+
+    None
 }
 
+// 在单个位图字中占用最低的空闲位
+fn claim_in_word(region: &Region, word_idx: usize) -> Option<usize> {
+    let slot = &region.bitmap()[word_idx];
+    let mut word = slot.load(Ordering::Acquire);
+
+    while word != usize::MAX {
+        let bit = (!word).trailing_zeros() as usize;
+        let new_word = word | (1 << bit);
+
+        match slot.compare_exchange_weak(
+            word,
+            new_word,
+            Ordering::AcqRel,
+            Ordering::Acquire
+        ) {
+            Ok(_) => {
+                if new_word == usize::MAX {
+                    region.mark_full(word_idx);
+                }
+                return Some(word_idx * 64 + bit);
+            }
+            Err(current) => word = current,
+        }
+    }
+
+    region.mark_full(word_idx);
+    None
+}
+
+#[cfg_attr(feature = "debug-poison", track_caller)]
 pub unsafe fn alloc_raw(pid: u32) -> Option<usize> {
-    let allocator = &ALLOCATOR;
-
-    for word_idx in 0..BITMAP_SIZE {
-        let mut word = allocator.bitmap[word_idx].load(Ordering::Acquire);
-
-        while word != usize::MAX {
-            for bit in 0..64 {
-                if (word & (1 << bit)) == 0 {
-                    let new_word = word | (1 << bit);
-
-                    match allocator.bitmap[word_idx].compare_exchange(
-                        word,
-                        new_word,
-                        Ordering::AcqRel,
-                        Ordering::Acquire
-                    ) {
-                        Ok(_) => {
-                            let page_idx = word_idx * 64 + bit;
-                            if page_idx >= allocator.total_pages {
-                                return None;
-                            }
-
-                            allocator.owners[page_idx].0.store(pid, Ordering::Release);
-                            allocator.free_pages.fetch_sub(1, Ordering::AcqRel);
-
-                            return Some(allocator.base + page_idx * PAGE_SIZE);
-                        }
-                        Err(current) => {
-                            word = current;
-                            break;
-                        }
-                    }
+    alloc_raw_with(pid, Zeroing::DontCare)
+}
+
+/// 分配一个保证全零的页
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub unsafe fn alloc_raw_zeroed(pid: u32) -> Option<usize> {
+    alloc_raw_with(pid, Zeroing::Zeroed)
+}
+
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub unsafe fn alloc_raw_with(pid: u32, zeroing: Zeroing) -> Option<usize> {
+    // 受颜色预算限制的进程不走弹匣：弹匣里的帧颜色任意
+    if let Some(colors) = super::color::restriction(pid) {
+        return alloc_colored(pid, colors, zeroing);
+    }
+
+    let t0 = crate::arch::timestamp();
+    let cpu = this_cpu();
+
+    let addr = match magazine_pop(cpu) {
+        Some(addr) => addr,
+        // 全局位图也空了：其他 CPU 的弹匣里可能还有帧
+        None if drain_magazines() > 0 => match magazine_pop(cpu) {
+            Some(addr) => addr,
+            None => {
+                counters(cpu).record_failure();
+                return None;
+            }
+        },
+        None => {
+            counters(cpu).record_failure();
+            return None;
+        }
+    };
+
+    let region = find_region(addr)?;
+    region.frames()[region.page_index(addr)].prepare(addr, pid, zeroing);
+    counters(cpu).record_alloc(t0, 1);
+    Some(addr)
+}
+
+// 从本 CPU 弹匣取一帧，弹匣空时先从全局位图补充一批
+unsafe fn magazine_pop(cpu: usize) -> Option<usize> {
+    let mag = magazine(cpu);
+    let mut inner = mag.inner.lock();
+    if inner.len == 0 {
+        refill(&mut inner, cpu);
+    }
+    if inner.len == 0 {
+        return None;
+    }
+
+    inner.len -= 1;
+    let addr = inner.addrs[inner.len];
+    mag.count.store(inner.len, Ordering::Relaxed);
+    Some(addr)
+}
+
+// 从全局位图按 next-fit 占用至多 MAGAZINE_BATCH 个帧装入弹匣
+unsafe fn refill(inner: &mut MagazineInner, cpu: usize) {
+    let regions = regions();
+    if regions.is_empty() {
+        return;
+    }
+    let cursor = &NEXT_FIT[cpu];
+    let packed = cursor.load(Ordering::Relaxed);
+    let (start_region, start_word) = (packed >> 32, packed & 0xffff_ffff);
+
+    for k in 0..regions.len() {
+        let region_idx = (start_region + k) % regions.len();
+        let region = &regions[region_idx];
+        let mut from = if k == 0 { start_word } else { 0 };
+
+        while inner.len < MAGAZINE_BATCH && region.free_pages.load(Ordering::Acquire) > 0 {
+            match claim_in_region(region, from) {
+                Some((addr, word_idx)) => {
+                    inner.addrs[inner.len] = addr;
+                    inner.len += 1;
+                    from = word_idx;
+                    cursor.store((region_idx << 32) | word_idx, Ordering::Relaxed);
                 }
+                None => break,
             }
+        }
+        if inner.len >= MAGAZINE_BATCH {
+            return;
+        }
+    }
+}
+
+// 把已退役的帧放回本 CPU 弹匣，弹匣满时先归还一批到全局位图
+unsafe fn magazine_push(addr: usize, cpu: usize) {
+    let mag = magazine(cpu);
+    let mut inner = mag.inner.lock();
+    if inner.len == MAGAZINE_SIZE {
+        // 归还最早放入的一批，保留最近释放（缓存更热）的帧
+        for i in 0..MAGAZINE_BATCH {
+            return_to_bitmap(inner.addrs[i]);
+        }
+        inner.addrs.copy_within(MAGAZINE_BATCH.., 0);
+        inner.len -= MAGAZINE_BATCH;
+    }
+    let len = inner.len;
+    inner.addrs[len] = addr;
+    inner.len += 1;
+    mag.count.store(inner.len, Ordering::Relaxed);
+}
 
-            if word == usize::MAX {
-                break;
+// 弹匣中的一帧回到全局位图
+unsafe fn return_to_bitmap(addr: usize) {
+    if let Some(region) = find_region(addr) {
+        clear_bits(region, region.page_index(addr), 1);
+        region.free_pages.fetch_add(1, Ordering::AcqRel);
+        ALLOCATOR.free_pages.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+/// 把所有 CPU 弹匣中的帧归还全局位图，返回归还的页数
+///
+/// 在连续块或指定地址分配失败时调用，让被缓存的帧重新可见
+pub fn drain_magazines() -> usize {
+    let mut drained = 0;
+    for mag in MAGAZINES.iter() {
+        if mag.count.load(Ordering::Relaxed) == 0 {
+            continue;
+        }
+        let mut inner = mag.inner.lock();
+        for i in 0..inner.len {
+            unsafe { return_to_bitmap(inner.addrs[i]); }
+        }
+        drained += inner.len;
+        inner.len = 0;
+        mag.count.store(0, Ordering::Relaxed);
+    }
+    drained
+}
+
+/// 各 CPU 弹匣中缓存的空闲页总数
+pub fn magazine_pages() -> usize {
+    MAGAZINES.iter().map(|m| m.count.load(Ordering::Relaxed)).sum()
+}
+
+/// 分配 2^order 个物理连续页，起始地址按块大小自然对齐
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub unsafe fn alloc_block(pid: u32, order: usize) -> Option<usize> {
+    alloc_block_with(pid, order, Zeroing::DontCare)
+}
+
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub unsafe fn alloc_block_with(pid: u32, order: usize, zeroing: Zeroing) -> Option<usize> {
+    if order > MAX_ORDER {
+        counters(this_cpu()).record_failure();
+        return None;
+    }
+    if order == 0 {
+        return alloc_raw_with(pid, zeroing);
+    }
+
+    let t0 = crate::arch::timestamp();
+    let count = 1usize << order;
+    let colors = budget_of(pid);
+
+    // 第二轮在清空各 CPU 弹匣后重试：缓存的单页可能正好补齐一个块
+    for attempt in 0..2 {
+        if attempt == 1 && drain_magazines() == 0 {
+            break;
+        }
+        for region in regions() {
+            if let Some(addr) = alloc_block_in_region(region, order, pid, zeroing, colors) {
+                counters(this_cpu()).record_alloc(t0, count);
+                return Some(addr);
             }
         }
     }
 
+    counters(this_cpu()).record_failure();
     None
 }
 
-pub unsafe fn free_raw(pid: u32, addr: usize) -> Result<(), &'static str> {
-    let allocator = &ALLOCATOR;
+// 在单个区域内分配按块大小自然对齐的 2^order 页，块内每页的颜色都须属于 colors
+//
+// 线性扫描对齐的候选块，最坏 O(区域页数)
+#[cfg_attr(feature = "debug-poison", track_caller)]
+unsafe fn alloc_block_in_region(
+    region: &Region,
+    order: usize,
+    pid: u32,
+    zeroing: Zeroing,
+    colors: ColorSet,
+) -> Option<usize> {
+    let count = 1usize << order;
+    let block_size = PAGE_SIZE << order;
+    if region.free_pages.load(Ordering::Acquire) < count {
+        return None;
+    }
+    // 块覆盖全部颜色时，受限的预算不可能满足
+    if count >= color_count() && colors & all_colors() != all_colors() {
+        return None;
+    }
+
+    // 按物理地址对齐（而不是区域内索引）
+    let mut addr = (region.base + block_size - 1) & !(block_size - 1);
+    while addr + block_size <= region.end() {
+        if colors_fit(addr, count, colors) && claim_frames(region, addr, count, pid, zeroing) {
+            return Some(addr);
+        }
+        addr += block_size;
+    }
+    None
+}
+
+/// 节点放置策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodePolicy {
+    /// 优先在指定节点分配，不足时回退到其他节点
+    Preferred,
+    /// 只在指定节点分配
+    Bind,
+}
+
+/// 一个 NUMA 节点上的内存（由该节点的所有区域汇总）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeZone {
+    pub node: u32,
+    pub regions: usize,
+    pub total_pages: usize,
+    pub free_pages: usize,
+}
+
+/// 在指定 NUMA 节点上分配一个帧
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub unsafe fn alloc_on_node(
+    pid: u32,
+    node: u32,
+    size: FrameSize,
+    zeroing: Zeroing,
+    policy: NodePolicy,
+) -> Option<usize> {
+    let t0 = crate::arch::timestamp();
+    let cpu = this_cpu();
+    let restriction = super::color::restriction(pid);
+
+    // 第二轮在清空各 CPU 弹匣后重试：本节点的空闲页可能都缓存在弹匣里
+    for attempt in 0..2 {
+        if attempt == 1 && drain_magazines() == 0 {
+            break;
+        }
+        let local = regions().iter().enumerate().filter(|(_, r)| r.node == node);
+        let remote = regions().iter().enumerate().filter(|(_, r)| r.node != node && policy == NodePolicy::Preferred);
+
+        for (region_idx, region) in local.chain(remote) {
+            let addr = match (size, restriction) {
+                (FrameSize::Size4K, _) if region.free_pages.load(Ordering::Acquire) == 0 => None,
+                (FrameSize::Size4K, Some(colors)) => claim_colored_in_region(region, colors, pid, zeroing),
+                (FrameSize::Size4K, None) => alloc_next_fit(region_idx, region, cpu, pid, zeroing),
+                _ => alloc_block_in_region(region, size.order(), pid, zeroing, budget_of(pid)),
+            };
+            if let Some(addr) = addr {
+                counters(cpu).record_alloc(t0, size.pages());
+                return Some(addr);
+            }
+        }
+    }
+
+    counters(cpu).record_failure();
+    None
+}
+
+// 沿本 CPU 的 next-fit 游标在指定区域内分配一页并推进游标
+//
+// 游标停在其他区域时从区域开头扫描；不经过弹匣，因为弹匣里的帧可能属于任何节点
+#[cfg_attr(feature = "debug-poison", track_caller)]
+unsafe fn alloc_next_fit(region_idx: usize, region: &Region, cpu: usize, pid: u32, zeroing: Zeroing) -> Option<usize> {
+    let cursor = &NEXT_FIT[cpu];
+    let packed = cursor.load(Ordering::Relaxed);
+    let start_word = if packed >> 32 == region_idx { packed & 0xffff_ffff } else { 0 };
+
+    let (addr, word_idx) = alloc_in_region(region, start_word, pid, zeroing)?;
+    cursor.store((region_idx << 32) | word_idx, Ordering::Relaxed);
+    Some(addr)
+}
+
+/// 物理地址所在的 NUMA 节点（不在受管区域内时为 None）
+pub fn page_node(addr: usize) -> Option<u32> {
+    find_region(addr).map(|r| r.node)
+}
+
+/// 按节点汇总的内存区（按节点号排序）
+pub fn zones() -> Vec<NodeZone> {
+    let mut zones: Vec<NodeZone> = Vec::new();
+    for region in regions() {
+        let idx = match zones.binary_search_by_key(&region.node, |z| z.node) {
+            Ok(idx) => idx,
+            Err(idx) => {
+                zones.insert(idx, NodeZone { node: region.node, regions: 0, total_pages: 0, free_pages: 0 });
+                idx
+            }
+        };
+        let zone = &mut zones[idx];
+        zone.regions += 1;
+        zone.total_pages += region.total_pages;
+        zone.free_pages += region.free_pages.load(Ordering::Relaxed);
+    }
+    zones
+}
+
+/// NUMA 节点数
+pub fn node_count() -> usize {
+    zones().len()
+}
 
-    if addr < allocator.base {
-        return Err("Invalid address");
+/// 释放 alloc_block 分配的块
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub unsafe fn free_block(pid: u32, addr: usize, order: usize) -> Result<(), &'static str> {
+    free_block_at(pid, addr, order, call_site())
+}
+
+unsafe fn free_block_at(pid: u32, addr: usize, order: usize, site: CallSite) -> Result<(), &'static str> {
+    if order > MAX_ORDER {
+        return Err("Invalid order");
+    }
+    if addr & ((PAGE_SIZE << order) - 1) != 0 {
+        return Err("Misaligned block");
+    }
+    free_range_at(pid, addr, 1 << order, site)
+}
+
+/// 分配一个指定大小的帧（大页按自身大小对齐）
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub unsafe fn alloc_frame(pid: u32, size: FrameSize) -> Option<usize> {
+    alloc_block(pid, size.order())
+}
+
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub unsafe fn alloc_frame_with(pid: u32, size: FrameSize, zeroing: Zeroing) -> Option<usize> {
+    alloc_block_with(pid, size.order(), zeroing)
+}
+
+/// 释放 alloc_frame 分配的帧
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub unsafe fn free_frame(pid: u32, addr: usize, size: FrameSize) -> Result<(), &'static str> {
+    free_block_at(pid, addr, size.order(), call_site())
+}
+
+/// 同 `free_frame`，释放记录在 site 名下（用于 Drop 等拿不到调用位置的地方）
+pub unsafe fn free_frame_at(pid: u32, addr: usize, size: FrameSize, site: CallSite) -> Result<(), &'static str> {
+    free_block_at(pid, addr, size.order(), site)
+}
+
+/// 最多支持的页颜色数（ColorSet 为 64 位掩码）
+pub const MAX_COLORS: usize = 64;
+
+/// 页颜色集合：第 i 位表示颜色 i
+pub type ColorSet = u64;
+
+// 当前页颜色数（2 的幂）；1 表示未启用着色
+static COLOR_COUNT: AtomicUsize = AtomicUsize::new(1);
+
+/// 设置页颜色数（向下取整到 2 的幂并限制在 [1, MAX_COLORS]）
+pub fn set_color_count(colors: usize) -> usize {
+    let colors = match colors.clamp(1, MAX_COLORS) {
+        n if n.is_power_of_two() => n,
+        n => n.next_power_of_two() >> 1,
+    };
+    COLOR_COUNT.store(colors, Ordering::Release);
+    colors
+}
+
+/// 当前页颜色数
+pub fn color_count() -> usize {
+    COLOR_COUNT.load(Ordering::Acquire)
+}
+
+/// 全部颜色的集合
+pub fn all_colors() -> ColorSet {
+    match color_count() {
+        MAX_COLORS => ColorSet::MAX,
+        n => (1 << n) - 1,
+    }
+}
+
+/// 物理地址所在页的颜色（LLC 组索引中超出页内偏移的那几位）
+pub fn page_color(addr: usize) -> usize {
+    (addr / PAGE_SIZE) & (color_count() - 1)
+}
+
+// pid 可使用的颜色（未设置预算时为全部颜色）
+fn budget_of(pid: u32) -> ColorSet {
+    super::color::restriction(pid).unwrap_or_else(all_colors)
+}
+
+// 从 addr 开始的 count 页的颜色是否都属于 colors
+fn colors_fit(addr: usize, count: usize, colors: ColorSet) -> bool {
+    let all = all_colors();
+    colors & all == all
+        || (0..count.min(color_count())).all(|i| colors & (1 << page_color(addr + i * PAGE_SIZE)) != 0)
+}
+
+/// 分配一个颜色属于 colors 的页（与 pid 的颜色预算取交集）
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub unsafe fn alloc_colored(pid: u32, colors: ColorSet, zeroing: Zeroing) -> Option<usize> {
+    let colors = colors & budget_of(pid);
+    if colors == 0 {
+        counters(this_cpu()).record_failure();
+        return None;
+    }
+    if colors == all_colors() {
+        return alloc_raw_with(pid, zeroing);
+    }
+
+    let t0 = crate::arch::timestamp();
+
+    // 第二轮在清空各 CPU 弹匣后重试：所需颜色的空闲页可能都缓存在弹匣里
+    for attempt in 0..2 {
+        if attempt == 1 && drain_magazines() == 0 {
+            break;
+        }
+        for region in regions() {
+            if region.free_pages.load(Ordering::Acquire) == 0 {
+                continue;
+            }
+            if let Some(addr) = claim_colored_in_region(region, colors, pid, zeroing) {
+                counters(this_cpu()).record_alloc(t0, 1);
+                return Some(addr);
+            }
+        }
+    }
+
+    counters(this_cpu()).record_failure();
+    None
+}
+
+// 在单个区域内按位图扫描并申领一个颜色属于 colors 的空闲页
+#[cfg_attr(feature = "debug-poison", track_caller)]
+unsafe fn claim_colored_in_region(region: &Region, colors: ColorSet, pid: u32, zeroing: Zeroing) -> Option<usize> {
+    let n = color_count();
+    let base_pfn = region.base / PAGE_SIZE;
+    for (word_idx, word) in region.bitmap().iter().enumerate() {
+        let used = word.load(Ordering::Acquire);
+        if used == usize::MAX {
+            continue;
+        }
+
+        // 本字中颜色合格的位
+        let first_color = (base_pfn + word_idx * 64) & (n - 1);
+        let mut allowed = 0usize;
+        for bit in 0..64 {
+            if colors & (1 << ((first_color + bit) & (n - 1))) != 0 {
+                allowed |= 1 << bit;
+            }
+        }
+
+        let mut candidates = !used & allowed;
+        while candidates != 0 {
+            let bit = candidates.trailing_zeros() as usize;
+            let addr = region.base + (word_idx * 64 + bit) * PAGE_SIZE;
+            if claim_frames(region, addr, 1, pid, zeroing) {
+                return Some(addr);
+            }
+            candidates &= !(1 << bit);
+        }
+    }
+    None
+}
+
+/// 各颜色的空闲页数
+pub fn free_pages_by_color() -> [usize; MAX_COLORS] {
+    let mut counts = [0; MAX_COLORS];
+    for region in regions() {
+        let bitmap = region.bitmap();
+        for idx in 0..region.total_pages {
+            if bitmap[idx / 64].load(Ordering::Relaxed) & (1 << (idx % 64)) == 0 {
+                counts[page_color(region.base + idx * PAGE_SIZE)] += 1;
+            }
+        }
+    }
+    counts
+}
+
+/// 按地址申领帧失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClaimError {
+    /// 地址不在任何受管区域内
+    OutOfRange,
+    /// 地址属于保留内存（内核映像、模块等）
+    Reserved,
+    /// 帧已被占用（范围申领时：范围内没有满足条件的空闲帧）
+    InUse,
+    /// 地址未按帧大小对齐，或对齐参数不是 2 的幂
+    Misaligned,
+    /// 帧的页颜色不在进程的颜色预算内
+    OutsideBudget,
+}
+
+/// 申领指定物理地址处的帧（用于页着色、DMA 可达范围等）
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub unsafe fn alloc_at(
+    pid: u32,
+    addr: usize,
+    size: FrameSize,
+    zeroing: Zeroing,
+) -> Result<usize, ClaimError> {
+    if addr % size.bytes() != 0 {
+        return Err(ClaimError::Misaligned);
+    }
+
+    let region = match find_region(addr) {
+        Some(r) => r,
+        None if super::reserved::is_reserved(addr) => return Err(ClaimError::Reserved),
+        None => return Err(ClaimError::OutOfRange),
+    };
+    if addr + size.bytes() > region.end() {
+        return Err(ClaimError::OutOfRange);
+    }
+    if !colors_fit(addr, size.pages(), budget_of(pid)) {
+        return Err(ClaimError::OutsideBudget);
+    }
+
+    let t0 = crate::arch::timestamp();
+    if !claim_frames(region, addr, size.pages(), pid, zeroing)
+        && !(drain_magazines() > 0 && claim_frames(region, addr, size.pages(), pid, zeroing))
+    {
+        counters(this_cpu()).record_failure();
+        return Err(ClaimError::InUse);
+    }
+    counters(this_cpu()).record_alloc(t0, size.pages());
+    Ok(addr)
+}
+
+/// 在 [lo, hi) 内申领一个按 align 对齐的帧（align 至少为帧大小）
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub unsafe fn alloc_in_range(
+    pid: u32,
+    lo: usize,
+    hi: usize,
+    align: usize,
+    size: FrameSize,
+    zeroing: Zeroing,
+) -> Result<usize, ClaimError> {
+    let align = align.max(size.bytes());
+    if !align.is_power_of_two() {
+        return Err(ClaimError::Misaligned);
+    }
+
+    let t0 = crate::arch::timestamp();
+    let colors = budget_of(pid);
+    let mut overlapped = false;
+
+    // 第二轮在清空各 CPU 弹匣后重试：范围内的空闲页可能缓存在弹匣里
+    for attempt in 0..2 {
+        if attempt == 1 && drain_magazines() == 0 {
+            break;
+        }
+        for region in regions() {
+            let end = hi.min(region.end());
+            let mut addr = (lo.max(region.base) + align - 1) & !(align - 1);
+            if addr >= end {
+                continue;
+            }
+            overlapped = true;
+
+            if region.free_pages.load(Ordering::Acquire) < size.pages() {
+                continue;
+            }
+
+            while addr + size.bytes() <= end {
+                if colors_fit(addr, size.pages(), colors) && claim_frames(region, addr, size.pages(), pid, zeroing) {
+                    counters(this_cpu()).record_alloc(t0, size.pages());
+                    return Ok(addr);
+                }
+                addr += align;
+            }
+        }
+        // 范围与任何区域都不相交时，清空弹匣也无济于事
+        if !overlapped {
+            break;
+        }
     }
 
-    let page_idx = (addr - allocator.base) / PAGE_SIZE;
-    if page_idx >= allocator.total_pages {
-        return Err("Page index out of range");
+    counters(this_cpu()).record_failure();
+    if overlapped { Err(ClaimError::InUse) } else { Err(ClaimError::OutOfRange) }
+}
+
+// 占用从 addr 开始的 count 个帧并交给 pid
+#[cfg_attr(feature = "debug-poison", track_caller)]
+unsafe fn claim_frames(region: &Region, addr: usize, count: usize, pid: u32, zeroing: Zeroing) -> bool {
+    let first = region.page_index(addr);
+    if !try_claim(region, first, count) {
+        return false;
+    }
+    for (i, frame) in region.frames()[first..first + count].iter().enumerate() {
+        frame.prepare(addr + i * PAGE_SIZE, pid, zeroing);
     }
+    region.free_pages.fetch_sub(count, Ordering::AcqRel);
+    ALLOCATOR.free_pages.fetch_sub(count, Ordering::AcqRel);
+    true
+}
 
-    let owner = allocator.owners[page_idx].0.load(Ordering::Acquire);
-    if owner != pid {
+/// 分配 count 个物理连续页（按不小于 count 的 2 的幂对齐）
+///
+/// 先分配整块，再把尾部多余的页归还；复杂度同 `alloc_block`，最坏 O(区域页数)
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub unsafe fn alloc_contiguous(pid: u32, count: usize) -> Option<usize> {
+    if count == 0 {
+        return None;
+    }
+
+    let order = order_for(count);
+    let addr = alloc_block(pid, order)?;
+    let excess = (1usize << order) - count;
+    if excess > 0 {
+        let _ = free_range(pid, addr + count * PAGE_SIZE, excess);
+    }
+    Some(addr)
+}
+
+/// 释放 count 个连续页（必须全部属于 pid 且位于同一区域）
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub unsafe fn free_range(pid: u32, addr: usize, count: usize) -> Result<(), &'static str> {
+    free_range_at(pid, addr, count, call_site())
+}
+
+/// 同 `free_range`，释放记录在 site 名下
+pub unsafe fn free_range_at(pid: u32, addr: usize, count: usize, site: CallSite) -> Result<(), &'static str> {
+    let region = find_region(addr).ok_or("Invalid address")?;
+    if addr + count * PAGE_SIZE > region.end() {
+        return Err("Range crosses region boundary");
+    }
+
+    let first = region.page_index(addr);
+    let frames = &region.frames()[first..first + count];
+    if let Some(i) = frames.iter().position(|f| !f.owned_by(pid)) {
+        #[cfg(feature = "debug-poison")]
+        if frames[i].already_freed_by(pid) {
+            super::poison::report_double_free(addr + i * PAGE_SIZE, pid, site, &frames[i].history);
+        }
+        #[cfg(not(feature = "debug-poison"))]
+        let _ = i;
         return Err("Permission denied");
     }
 
-    allocator.owners[page_idx].0.store(0, Ordering::Release);
+    // 所有者放弃自己的引用；仍有其他持有者的帧保留到最后一个持有者离开
+    for frame in frames {
+        frame.flags.fetch_or(FRAME_ORPHANED, Ordering::AcqRel);
+    }
+    release_frames(region, first, count, site);
+    Ok(())
+}
+
+// 对 [first, first+count) 各帧放弃一个引用，释放降到 0 的帧，返回释放的页数
+unsafe fn release_frames(region: &Region, first: usize, count: usize, site: CallSite) -> usize {
+    let frames = region.frames();
+    let mut freed = 0;
+    let mut run: Option<usize> = None;
 
-    let word_idx = page_idx / 64;
-    let bit = page_idx % 64;
+    for idx in first..=first + count {
+        let addr = region.base + idx * PAGE_SIZE;
+        let last = idx < first + count && frames[idx].put(addr, site);
+        if last {
+            frames[idx].retire(addr, frames[idx].owner.load(Ordering::Acquire), site);
+            run.get_or_insert(idx);
+        } else if let Some(start) = run.take() {
+            // 连续释放的帧一次性清位
+            clear_bits(region, start, idx - start);
+            freed += idx - start;
+        }
+    }
+
+    if freed > 0 {
+        region.free_pages.fetch_add(freed, Ordering::AcqRel);
+        ALLOCATOR.free_pages.fetch_add(freed, Ordering::AcqRel);
+        counters(this_cpu()).frees.fetch_add(freed as u64, Ordering::Relaxed);
+    }
+    freed
+}
 
-    allocator.bitmap[word_idx].fetch_and(!(1 << bit), Ordering::AcqRel);
-    allocator.free_pages.fetch_add(1, Ordering::AcqRel);
+/// 为 [addr, addr + count 页) 的每个帧增加一个持有者（授权、共享时调用）
+///
+/// 任一帧已空闲则回滚并返回 false
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub fn get_frames(addr: usize, count: usize) -> bool {
+    let region = match find_region(addr) {
+        Some(r) if addr + count * PAGE_SIZE <= r.end() => r,
+        _ => return false,
+    };
+    let first = region.page_index(addr);
+    let frames = &region.frames()[first..first + count];
 
+    for (i, frame) in frames.iter().enumerate() {
+        if !frame.get() {
+            unsafe { release_frames(region, first, i, call_site()); }
+            return false;
+        }
+    }
+    true
+}
+
+/// 为 [addr, addr + count 页) 的每个帧减少一个持有者，最后一个持有者离开时释放帧
+///
+/// 返回释放的页数
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub unsafe fn put_frames(addr: usize, count: usize) -> usize {
+    put_frames_at(addr, count, call_site())
+}
+
+/// 同 `put_frames`，释放记录在 site 名下（用于撤销路径等 `#[track_caller]` 传不到的地方）
+pub unsafe fn put_frames_at(addr: usize, count: usize, site: CallSite) -> usize {
+    match find_region(addr) {
+        Some(r) if addr + count * PAGE_SIZE <= r.end() => release_frames(r, r.page_index(addr), count, site),
+        _ => 0,
+    }
+}
+
+/// 帧被映射（借用访问）一次
+pub fn map_frame(addr: usize) {
+    if let Some(frame) = frame_meta(addr) {
+        frame.maps.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+/// 帧的一个映射被撤除
+pub fn unmap_frame(addr: usize) {
+    if let Some(frame) = frame_meta(addr) {
+        let _ = frame.maps.fetch_update(Ordering::AcqRel, Ordering::Acquire, |m| m.checked_sub(1));
+    }
+}
+
+/// 单个帧的持有情况
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameInfo {
+    /// 分配者（0 表示空闲）
+    pub owner: u32,
+    /// 分配者是否已放弃该帧（仅剩其他持有者）
+    pub orphaned: bool,
+    pub refs: u32,
+    pub maps: u32,
+}
+
+/// 查询帧的所有者、引用数与映射数
+pub fn frame_info(addr: usize) -> Option<FrameInfo> {
+    frame_meta(addr).map(|f| FrameInfo {
+        owner: f.owner.load(Ordering::Acquire),
+        orphaned: f.flags.load(Ordering::Acquire) & FRAME_ORPHANED != 0,
+        refs: f.refs.load(Ordering::Acquire),
+        maps: f.maps.load(Ordering::Acquire),
+    })
+}
+
+fn frame_meta(addr: usize) -> Option<&'static FrameMeta> {
+    find_region(addr).map(|r| &r.frames()[r.page_index(addr)])
+}
+
+/// 容纳 count 页所需的最小阶
+pub fn order_for(count: usize) -> usize {
+    count.max(1).next_power_of_two().trailing_zeros() as usize
+}
+
+// 位图中 [bit, bit+n) 的掩码
+#[inline(always)]
+fn run_mask(bit: usize, n: usize) -> usize {
+    if n >= 64 { usize::MAX } else { ((1usize << n) - 1) << bit }
+}
+
+// 原子地占用 [first, first+count)；任一位已被占用则回滚并返回 false
+unsafe fn try_claim(region: &Region, first: usize, count: usize) -> bool {
+    let bitmap = region.bitmap();
+    let end = first + count;
+    let mut idx = first;
+
+    while idx < end {
+        let word_idx = idx / 64;
+        let bit = idx % 64;
+        let n = (64 - bit).min(end - idx);
+        let mask = run_mask(bit, n);
+
+        let mut word = bitmap[word_idx].load(Ordering::Acquire);
+        loop {
+            if word & mask != 0 {
+                clear_bits(region, first, idx - first);
+                return false;
+            }
+            match bitmap[word_idx].compare_exchange_weak(
+                word,
+                word | mask,
+                Ordering::AcqRel,
+                Ordering::Acquire
+            ) {
+                Ok(_) => {
+                    if word | mask == usize::MAX {
+                        region.mark_full(word_idx);
+                    }
+                    break;
+                }
+                Err(current) => word = current,
+            }
+        }
+
+        idx += n;
+    }
+
+    true
+}
+
+unsafe fn clear_bits(region: &Region, first: usize, count: usize) {
+    let bitmap = region.bitmap();
+    let end = first + count;
+    let mut idx = first;
+
+    while idx < end {
+        let bit = idx % 64;
+        let n = (64 - bit).min(end - idx);
+        bitmap[idx / 64].fetch_and(!run_mask(bit, n), Ordering::AcqRel);
+        region.mark_not_full(idx / 64);
+        idx += n;
+    }
+}
+
+#[cfg_attr(feature = "debug-poison", track_caller)]
+pub unsafe fn free_raw(pid: u32, addr: usize) -> Result<(), &'static str> {
+    free_raw_at(pid, addr, call_site())
+}
+
+/// 同 `free_raw`，释放记录在 site 名下（用于 Drop 等拿不到调用位置的地方）
+pub unsafe fn free_raw_at(pid: u32, addr: usize, site: CallSite) -> Result<(), &'static str> {
+    let region = find_region(addr).ok_or("Invalid address")?;
+    let page_idx = region.page_index(addr);
+
+    let frame = &region.frames()[page_idx];
+    if !frame.owned_by(pid) {
+        #[cfg(feature = "debug-poison")]
+        if frame.already_freed_by(pid) {
+            super::poison::report_double_free(addr, pid, site, &frame.history);
+        }
+        return Err("Permission denied");
+    }
+
+    frame.flags.fetch_or(FRAME_ORPHANED, Ordering::AcqRel);
+    if frame.put(addr, site) {
+        frame.retire(addr, pid, site);
+        let cpu = this_cpu();
+        magazine_push(addr, cpu);
+        counters(cpu).frees.fetch_add(1, Ordering::Relaxed);
+    }
     Ok(())
 }
 
 pub unsafe fn change_owner(addr: usize, old_pid: u32, new_pid: u32) -> Result<(), &'static str> {
-    let allocator = &ALLOCATOR;
-    let page_idx = (addr - allocator.base) / PAGE_SIZE;
+    let region = find_region(addr).ok_or("Invalid address")?;
+    let page_idx = region.page_index(addr);
 
-    match allocator.owners[page_idx].0.compare_exchange(
+    match region.frames()[page_idx].owner.compare_exchange(
         old_pid,
         new_pid,
         Ordering::AcqRel,
@@ -129,6 +1454,324 @@ pub unsafe fn change_owner(addr: usize, old_pid: u32, new_pid: u32) -> Result<()
     }
 }
 
+/// 把 [addr, addr + count 页) 的分配者从 from 换成 to（能力转移时调用）
+///
+/// 要求每帧都仍由 from 持有（未放弃）；任一帧不满足则回滚并返回 false。
+/// 引用数不变：转移的是分配引用本身，不是新增持有者
+pub fn transfer_frames(addr: usize, count: usize, from: u32, to: u32) -> bool {
+    let region = match find_region(addr) {
+        Some(r) if addr + count * PAGE_SIZE <= r.end() => r,
+        _ => return false,
+    };
+    let first = region.page_index(addr);
+    let frames = &region.frames()[first..first + count];
+
+    for (i, frame) in frames.iter().enumerate() {
+        let moved = frame.owned_by(from)
+            && frame.owner.compare_exchange(from, to, Ordering::AcqRel, Ordering::Acquire).is_ok();
+        if !moved {
+            for f in &frames[..i] {
+                f.owner.store(from, Ordering::Release);
+            }
+            return false;
+        }
+    }
+    true
+}
+
 pub unsafe fn free_pages() -> usize {
-    ALLOCATOR.free_pages.load(Ordering::Acquire)
+    ALLOCATOR.free_pages.load(Ordering::Acquire) + magazine_pages()
+}
+
+/// 所有区域可分配页的总数
+pub unsafe fn total_pages() -> usize {
+    ALLOCATOR.total_pages
+}
+
+/// 已管理的区域数
+pub fn region_count() -> usize {
+    regions().len()
+}
+
+/// 各所有者持有的页数（不含内核自身及所有者已放弃的共享帧）
+///
+/// 需要扫描全部帧描述符，只应在慢路径（回收、统计）中使用
+pub fn usage_by_pid() -> BTreeMap<u32, usize> {
+    let mut usage = BTreeMap::new();
+    for region in regions() {
+        for frame in region.frames() {
+            let owner = frame.owner.load(Ordering::Acquire);
+            if owner != 0 && owner != KERNEL_PID && frame.owned_by(owner) {
+                *usage.entry(owner).or_insert(0) += 1;
+            }
+        }
+    }
+    usage
+}
+
+/// 空闲段长度直方图的桶数：第 i 桶统计长度在 [2^i, 2^(i+1)) 页的空闲段
+pub const RUN_HISTOGRAM_BUCKETS: usize = MAX_ORDER + 2;
+
+/// 单个区域的用量
+#[derive(Debug, Clone, Copy)]
+pub struct RegionStats {
+    pub base: usize,
+    pub node: u32,
+    pub total_pages: usize,
+    pub free_pages: usize,
+    /// 最长的连续空闲页数
+    pub largest_free_run: usize,
+}
+
+/// 物理内存用量与碎片情况
+#[derive(Debug, Clone)]
+pub struct MemoryStats {
+    pub total_pages: usize,
+    pub free_pages: usize,
+    /// 连续空闲段数
+    pub free_runs: usize,
+    pub largest_free_run: usize,
+    pub run_histogram: [usize; RUN_HISTOGRAM_BUCKETS],
+    pub regions: Vec<RegionStats>,
+    /// 各进程持有的页数（不含内核）
+    pub usage_by_pid: BTreeMap<u32, usize>,
+    pub kernel_pages: usize,
+    /// 缓存在各 CPU 弹匣中的空闲页（已计入 free_pages，但不在空闲段中）
+    pub magazine_pages: usize,
+}
+
+impl MemoryStats {
+    /// 外部碎片率：1 - 最长空闲段 / 位图中的空闲页数（0 表示空闲内存完全连续）
+    ///
+    /// 弹匣中的页不属于任何空闲段，不计入分母
+    pub fn fragmentation(&self) -> f32 {
+        let in_bitmap = self.free_pages - self.magazine_pages;
+        if in_bitmap == 0 {
+            return 0.0;
+        }
+        1.0 - self.largest_free_run as f32 / in_bitmap as f32
+    }
+
+    /// 最大可连续分配的字节数
+    pub fn largest_free_block(&self) -> usize {
+        self.largest_free_run * PAGE_SIZE
+    }
+}
+
+/// 扫描全部位图与帧描述符，汇总用量和碎片情况
+///
+/// 开销与物理内存大小成正比，只应在慢路径中使用
+pub fn memory_stats() -> MemoryStats {
+    let mut stats = MemoryStats {
+        total_pages: 0,
+        free_pages: 0,
+        free_runs: 0,
+        largest_free_run: 0,
+        run_histogram: [0; RUN_HISTOGRAM_BUCKETS],
+        regions: Vec::new(),
+        usage_by_pid: usage_by_pid(),
+        kernel_pages: 0,
+        magazine_pages: magazine_pages(),
+    };
+
+    for region in regions() {
+        let mut largest = 0;
+        let mut free = 0;
+        let mut run = 0;
+        let mut end_run = |run: &mut usize, stats: &mut MemoryStats| {
+            if *run > 0 {
+                let bucket = (usize::BITS - 1 - run.leading_zeros()) as usize;
+                stats.run_histogram[bucket.min(RUN_HISTOGRAM_BUCKETS - 1)] += 1;
+                stats.free_runs += 1;
+                largest = largest.max(*run);
+                *run = 0;
+            }
+        };
+
+        for (word_idx, word) in region.bitmap().iter().enumerate() {
+            let used = word.load(Ordering::Relaxed);
+            match used {
+                0 => run += 64,
+                usize::MAX => end_run(&mut run, &mut stats),
+                _ => {
+                    let bits = 64.min(region.total_pages - word_idx * 64);
+                    for bit in 0..bits {
+                        if used & (1 << bit) == 0 {
+                            run += 1;
+                        } else {
+                            end_run(&mut run, &mut stats);
+                        }
+                    }
+                }
+            }
+            free += (!used).count_ones() as usize;
+        }
+        end_run(&mut run, &mut stats);
+
+        stats.kernel_pages += region.frames().iter().filter(|f| f.owned_by(KERNEL_PID)).count();
+        stats.total_pages += region.total_pages;
+        stats.free_pages += free;
+        stats.largest_free_run = stats.largest_free_run.max(largest);
+        stats.regions.push(RegionStats {
+            base: region.base,
+            node: region.node,
+            total_pages: region.total_pages,
+            free_pages: free,
+            largest_free_run: largest,
+        });
+    }
+
+    stats.free_pages += stats.magazine_pages;
+    stats
+}
+
+/// 打印用量与碎片报告
+pub fn report_memory_stats() {
+    let stats = memory_stats();
+    crate::println!("  [MM] {}/{} pages free in {} runs, largest run {} pages ({:.1}% fragmented)",
+                    stats.free_pages, stats.total_pages, stats.free_runs,
+                    stats.largest_free_run, stats.fragmentation() * 100.0);
+    for r in stats.regions.iter() {
+        crate::println!("  [MM]   region 0x{:x} node {}: {}/{} free, largest run {}",
+                        r.base, r.node, r.free_pages, r.total_pages, r.largest_free_run);
+    }
+    for (pid, pages) in stats.usage_by_pid.iter() {
+        crate::println!("  [MM]   pid {}: {} pages", pid, pages);
+    }
+}
+
+/// 某所有者当前持有的页数
+pub fn pages_owned_by(pid: u32) -> usize {
+    regions()
+        .iter()
+        .flat_map(|r| r.frames().iter())
+        .filter(|f| f.owned_by(pid))
+        .count()
+}
+
+/// 列出某所有者持有的至多 max 个页的地址
+pub fn frames_owned_by(pid: u32, max: usize) -> Vec<usize> {
+    let mut out = Vec::new();
+    for region in regions() {
+        for (idx, frame) in region.frames().iter().enumerate() {
+            if out.len() >= max {
+                return out;
+            }
+            if frame.owned_by(pid) {
+                out.push(region.base + idx * PAGE_SIZE);
+            }
+        }
+    }
+    out
+}
+
+/// 后台清零：最多处理 budget 个脏的空闲页，返回实际清零的页数
+///
+/// 由空闲循环调用；被清零的页之后分配时走“已知为零”的快路径
+pub unsafe fn scrub_idle(budget: usize) -> usize {
+    let total = ALLOCATOR.total_pages;
+    if total == 0 {
+        return 0;
+    }
+
+    let mut scrubbed = 0;
+    let mut scanned = 0;
+    let mut pos = SCRUB_CURSOR.load(Ordering::Relaxed) % total;
+
+    while scrubbed < budget && scanned < total {
+        let (region, idx) = match locate(pos) {
+            Some(r) => r,
+            None => break,
+        };
+        let frame = &region.frames()[idx];
+        let dirty = frame.flags.load(Ordering::Acquire) & FRAME_ZEROED == 0;
+
+        // 临时占用该页，防止清零时被分配出去
+        if dirty && try_claim(region, idx, 1) {
+            if frame.owner.load(Ordering::Acquire) == 0 {
+                let addr = region.base + idx * PAGE_SIZE;
+                // 清零会抹掉投毒图案，先检查一次
+                #[cfg(feature = "debug-poison")]
+                if frame.flags.fetch_and(!FRAME_POISONED, Ordering::AcqRel) & FRAME_POISONED != 0 {
+                    if let Some(offset) = super::poison::check(addr) {
+                        super::poison::report_use_after_free(addr, offset, &frame.history);
+                    }
+                }
+                core::ptr::write_bytes(addr as *mut u8, 0, PAGE_SIZE);
+                frame.last_owner.store(0, Ordering::Release);
+                frame.flags.fetch_or(FRAME_ZEROED, Ordering::AcqRel);
+                SCRUB_STATS.zeroed_idle.fetch_add(1, Ordering::Relaxed);
+                scrubbed += 1;
+            }
+            clear_bits(region, idx, 1);
+        }
+
+        pos = (pos + 1) % total;
+        scanned += 1;
+    }
+
+    SCRUB_CURSOR.store(pos, Ordering::Relaxed);
+    scrubbed
+}
+
+// 全局页序号 -> (区域, 区域内索引)
+fn locate(mut pos: usize) -> Option<(&'static Region, usize)> {
+    for region in regions() {
+        if pos < region.total_pages {
+            return Some((region, pos));
+        }
+        pos -= region.total_pages;
+    }
+    None
+}
+
+/// 报告泄漏的帧：仍归 pid 所有，但 pid 已没有覆盖它的能力
+///
+/// 进程退出、能力全部撤销后调用；返回泄漏的页数
+#[cfg(feature = "debug-poison")]
+pub fn report_leaks(pid: u32) -> usize {
+    let owner = crate::capability::ProcessId::new(pid);
+    let mut leaked = 0;
+    for region in regions() {
+        for (idx, frame) in region.frames().iter().enumerate() {
+            let addr = region.base + idx * PAGE_SIZE;
+            if !frame.owned_by(pid) || crate::capability::verify_page_access(owner, addr, 0) {
+                continue;
+            }
+            if leaked < super::poison::MAX_LEAK_REPORTS {
+                super::poison::report_leak(addr, &frame.history);
+            }
+            leaked += 1;
+        }
+    }
+    if leaked > super::poison::MAX_LEAK_REPORTS {
+        crate::println!("  [POISON] ... {} leaked frames for pid {} in total", leaked, pid);
+    }
+    leaked
+}
+
+/// 清零统计：(分配时清零, 后台清零, 已知为零命中)
+pub fn scrub_stats() -> (u64, u64, u64) {
+    (
+        SCRUB_STATS.zeroed_on_alloc.load(Ordering::Relaxed),
+        SCRUB_STATS.zeroed_idle.load(Ordering::Relaxed),
+        SCRUB_STATS.known_zero_hits.load(Ordering::Relaxed),
+    )
+}
+
+/// 分配延迟与失败计数
+pub fn counter_stats() -> AllocCounterStats {
+    let sum = |f: fn(&AllocCounters) -> &AtomicU64| -> u64 {
+        COUNTERS.iter().map(|c| f(c).load(Ordering::Relaxed)).sum()
+    };
+    let allocs = sum(|c| &c.allocs);
+    let total = sum(|c| &c.latency_total);
+    AllocCounterStats {
+        allocs,
+        pages_allocated: sum(|c| &c.pages_allocated),
+        pages_freed: sum(|c| &c.frees),
+        failures: sum(|c| &c.failures),
+        avg_latency: if allocs > 0 { total / allocs } else { 0 },
+        max_latency: COUNTERS.iter().map(|c| c.latency_max.load(Ordering::Relaxed)).max().unwrap_or(0),
+    }
 }
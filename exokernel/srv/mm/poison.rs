@@ -0,0 +1,159 @@
+// src/mm/poison.rs
+//! 调试用页投毒（feature = "debug-poison"）
+//!
+//! 帧被释放时整页填入 `POISON`，再次分配时检查图案是否完好：
+//! 被改写说明有人在释放后仍然写入（use-after-free）。
+//! 每帧记录最近一次分配与释放的 pid 和调用位置（`#[track_caller]`），
+//! 重复释放与泄漏都连同这段历史一起打印。
+//!
+//! ownership_api 在转移、撤销、`SharedPage::from_owned` 等处使用
+//! `core::mem::forget` 跳过 Drop，这类错误在发布构建中不会有任何征兆。
+
+use core::panic::Location;
+use core::sync::atomic::{AtomicPtr, AtomicU32, AtomicU64, Ordering};
+use core::fmt;
+use crate::arch::PAGE_SIZE;
+
+/// 投毒图案（与 Linux slab 的 POISON_FREE 相同）
+pub const POISON: u64 = 0x6b6b_6b6b_6b6b_6b6b;
+
+/// 每次检查最多打印的泄漏帧数
+pub const MAX_LEAK_REPORTS: usize = 16;
+
+/// 一次分配或释放
+#[derive(Debug, Clone, Copy)]
+pub struct Event {
+    pub pid: u32,
+    pub site: Option<&'static Location<'static>>,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.site {
+            Some(site) => write!(f, "pid {} at {}:{}", self.pid, site.file(), site.line()),
+            None => write!(f, "never"),
+        }
+    }
+}
+
+/// 每帧的分配/释放历史（嵌在帧描述符中，全零即“无记录”）
+pub struct FrameHistory {
+    alloc_pid: AtomicU32,
+    alloc_site: AtomicPtr<Location<'static>>,
+    free_pid: AtomicU32,
+    free_site: AtomicPtr<Location<'static>>,
+}
+
+impl FrameHistory {
+    pub fn record_alloc(&self, pid: u32, site: &'static Location<'static>) {
+        self.alloc_pid.store(pid, Ordering::Relaxed);
+        self.alloc_site.store(site as *const _ as *mut _, Ordering::Release);
+    }
+
+    pub fn record_free(&self, pid: u32, site: &'static Location<'static>) {
+        self.free_pid.store(pid, Ordering::Relaxed);
+        self.free_site.store(site as *const _ as *mut _, Ordering::Release);
+    }
+
+    pub fn last_alloc(&self) -> Event {
+        Self::event(&self.alloc_pid, &self.alloc_site)
+    }
+
+    pub fn last_free(&self) -> Event {
+        Self::event(&self.free_pid, &self.free_site)
+    }
+
+    fn event(pid: &AtomicU32, site: &AtomicPtr<Location<'static>>) -> Event {
+        let site = site.load(Ordering::Acquire);
+        Event {
+            pid: pid.load(Ordering::Relaxed),
+            // 安全：只存放过 Location::caller() 返回的 'static 引用
+            site: unsafe { site.as_ref() },
+        }
+    }
+}
+
+/// 投毒统计
+struct PoisonStats {
+    poisoned: AtomicU64,
+    checked: AtomicU64,
+    corruptions: AtomicU64,
+    double_frees: AtomicU64,
+    leaks: AtomicU64,
+}
+
+static STATS: PoisonStats = PoisonStats {
+    poisoned: AtomicU64::new(0),
+    checked: AtomicU64::new(0),
+    corruptions: AtomicU64::new(0),
+    double_frees: AtomicU64::new(0),
+    leaks: AtomicU64::new(0),
+};
+
+/// 用投毒图案填满一页
+///
+/// # Safety
+///
+/// addr 必须是已退役（无任何持有者）的恒等映射页
+pub unsafe fn fill(addr: usize) {
+    let words = core::slice::from_raw_parts_mut(addr as *mut u64, PAGE_SIZE / 8);
+    words.fill(POISON);
+    STATS.poisoned.fetch_add(1, Ordering::Relaxed);
+}
+
+/// 检查投毒图案，返回第一个被改写的字节偏移
+///
+/// # Safety
+///
+/// 同 `fill`
+pub unsafe fn check(addr: usize) -> Option<usize> {
+    STATS.checked.fetch_add(1, Ordering::Relaxed);
+    let words = core::slice::from_raw_parts(addr as *const u64, PAGE_SIZE / 8);
+    let i = words.iter().position(|&w| w != POISON)?;
+    let bad = words[i] ^ POISON;
+    Some(i * 8 + (bad.trailing_zeros() / 8) as usize)
+}
+
+/// 报告释放后写入
+pub fn report_use_after_free(addr: usize, offset: usize, history: &FrameHistory) {
+    STATS.corruptions.fetch_add(1, Ordering::Relaxed);
+    crate::println!("  [POISON] Use after free: frame 0x{:x} modified at +0x{:x} ({:#04x})",
+                    addr, offset, unsafe { *((addr + offset) as *const u8) });
+    crate::println!("  [POISON]   allocated by {}", history.last_alloc());
+    crate::println!("  [POISON]   freed by     {}", history.last_free());
+}
+
+/// 报告重复释放
+pub fn report_double_free(addr: usize, pid: u32, site: &'static Location<'static>, history: &FrameHistory) {
+    STATS.double_frees.fetch_add(1, Ordering::Relaxed);
+    crate::println!("  [POISON] Double free: frame 0x{:x} by {}",
+                    addr, Event { pid, site: Some(site) });
+    crate::println!("  [POISON]   allocated by {}", history.last_alloc());
+    crate::println!("  [POISON]   freed by     {}", history.last_free());
+}
+
+/// 报告引用计数下溢（对已没有持有者的帧再放弃一次引用，按重复释放计数）
+pub fn report_underflow(addr: usize, site: &'static Location<'static>, history: &FrameHistory) {
+    STATS.double_frees.fetch_add(1, Ordering::Relaxed);
+    crate::println!("  [POISON] Reference underflow: frame 0x{:x} released at {}:{} with no holders",
+                    addr, site.file(), site.line());
+    crate::println!("  [POISON]   allocated by {}", history.last_alloc());
+    crate::println!("  [POISON]   freed by     {}", history.last_free());
+}
+
+/// 报告泄漏的帧（仍归 pid 所有，但 pid 已没有覆盖它的能力）
+pub fn report_leak(addr: usize, history: &FrameHistory) {
+    STATS.leaks.fetch_add(1, Ordering::Relaxed);
+    crate::println!("  [POISON] Leaked frame 0x{:x}, allocated by {}", addr, history.last_alloc());
+}
+
+/// 投毒统计：(投毒页数, 检查页数, 释放后写入, 重复释放, 泄漏)
+pub fn stats() -> (u64, u64, u64, u64, u64) {
+    (
+        STATS.poisoned.load(Ordering::Relaxed),
+        STATS.checked.load(Ordering::Relaxed),
+        STATS.corruptions.load(Ordering::Relaxed),
+        STATS.double_frees.load(Ordering::Relaxed),
+        STATS.leaks.load(Ordering::Relaxed),
+    )
+}
@@ -0,0 +1,331 @@
+// src/mm/reclaim.rs
+//! 内存回收协议 - 请 LibOS 归还页面
+//!
+//! 按 exokernel 的思路，内核不替 LibOS 决定换出哪些页，而是：
+//! 1. 物理内存耗尽时，按策略选出若干进程，发出“在截止时间前归还 N 页”的请求
+//! 2. LibOS 轮询请求、自行释放页面并确认
+//! 3. 内核在 `tick` 中按实际占用量判断是否履约
+//! 4. 超时未履约则进入中止协议：强制撤销该进程的具体页面
+//!
+//! 核实需要扫描全部帧描述符，所以 `tick` 只在有请求已确认（且距上次核实超过
+//! 截止时间的 1/CHECKS_PER_DEADLINE）或已超时时才扫描，且扫描在锁外进行，
+//! 一次扫描核实所有请求。结束的请求保留在有限长度的历史中，LibOS 可经 `outcome` 查询结果。
+
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::vec::Vec;
+use spin::Mutex;
+use crate::capability::{ProcessId, force_revoke_frame};
+use super::physical;
+
+/// 默认截止时间（arch::timestamp 滴答数）
+pub const DEFAULT_DEADLINE_TICKS: u64 = 1_000_000_000;
+
+/// 默认进程优先级（越大越重要）
+pub const DEFAULT_PRIORITY: u8 = 128;
+
+/// 截止时间内最多核实几次已确认的请求
+pub const CHECKS_PER_DEADLINE: u64 = 16;
+
+/// 保留的已结束请求数
+pub const HISTORY_LEN: usize = 64;
+
+/// 选择被请求进程的策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReclaimPolicy {
+    /// 占用最多的进程优先
+    LargestConsumer,
+    /// 优先级最低的进程优先（同优先级按占用量）
+    LowestPriority,
+}
+
+/// 请求状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestState {
+    /// 等待 LibOS 归还
+    Pending,
+    /// LibOS 已确认，等待内核核实
+    Acknowledged,
+    /// 已按量归还
+    Complied,
+    /// 超时未履约，内核强制撤销了 revoked 页
+    Aborted { revoked: usize },
+}
+
+/// 一条回收请求
+#[derive(Debug, Clone, Copy)]
+pub struct ReclaimRequest {
+    pub id: u64,
+    pub pid: u32,
+    /// 请求归还的页数
+    pub pages: usize,
+    /// 截止时间（arch::timestamp）
+    pub deadline: u64,
+    pub state: RequestState,
+    /// 发出请求时该进程的占用量，用于核实履约
+    baseline: usize,
+}
+
+impl ReclaimRequest {
+    pub fn is_open(&self) -> bool {
+        matches!(self.state, RequestState::Pending | RequestState::Acknowledged)
+    }
+
+    // 已归还的页数（按占用量下降计算）
+    fn released(&self, current: usize) -> usize {
+        self.baseline.saturating_sub(current)
+    }
+}
+
+/// 回收统计
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReclaimStats {
+    pub issued: u64,
+    pub complied: u64,
+    pub aborted: u64,
+    pub pages_revoked: u64,
+}
+
+struct ReclaimState {
+    policy: ReclaimPolicy,
+    deadline_ticks: u64,
+    priorities: BTreeMap<u32, u8>,
+    /// 未结束的请求
+    requests: Vec<ReclaimRequest>,
+    /// 最近结束的请求（最旧的在前）
+    history: VecDeque<ReclaimRequest>,
+    next_id: u64,
+    /// 下次允许核实已确认请求的时间
+    next_check: u64,
+    stats: ReclaimStats,
+}
+
+impl ReclaimState {
+    // 把已结束的请求移入历史，超出 HISTORY_LEN 时丢弃最旧的
+    fn retire_finished(&mut self) {
+        let mut i = 0;
+        while i < self.requests.len() {
+            if self.requests[i].is_open() {
+                i += 1;
+                continue;
+            }
+            if self.history.len() == HISTORY_LEN {
+                self.history.pop_front();
+            }
+            self.history.push_back(self.requests.remove(i));
+        }
+    }
+
+    fn find_mut(&mut self, id: u64) -> Option<&mut ReclaimRequest> {
+        self.requests.iter_mut().chain(self.history.iter_mut()).find(|r| r.id == id)
+    }
+}
+
+static STATE: Mutex<ReclaimState> = Mutex::new(ReclaimState {
+    policy: ReclaimPolicy::LargestConsumer,
+    deadline_ticks: DEFAULT_DEADLINE_TICKS,
+    priorities: BTreeMap::new(),
+    requests: Vec::new(),
+    history: VecDeque::new(),
+    next_id: 1,
+    next_check: 0,
+    stats: ReclaimStats { issued: 0, complied: 0, aborted: 0, pages_revoked: 0 },
+});
+
+// 时间戳 t 是否已到（按回绕差值比较）
+fn reached(now: u64, t: u64) -> bool {
+    (now.wrapping_sub(t) as i64) >= 0
+}
+
+// ========== 配置 ==========
+
+pub fn set_policy(policy: ReclaimPolicy) {
+    STATE.lock().policy = policy;
+}
+
+pub fn set_deadline_ticks(ticks: u64) {
+    STATE.lock().deadline_ticks = ticks;
+}
+
+/// 设置进程优先级（越大越重要）
+pub fn set_priority(pid: ProcessId, priority: u8) {
+    STATE.lock().priorities.insert(pid.as_u32(), priority);
+}
+
+// ========== 内核侧 ==========
+
+/// 请求 LibOS 共归还 needed 页，返回新发出的请求数
+///
+/// 已有未完成请求的进程不会被重复请求
+pub fn request_pages(needed: usize) -> usize {
+    let usage = physical::usage_by_pid();
+    let now = crate::arch::timestamp();
+    let mut state = STATE.lock();
+
+    let mut candidates: Vec<(u32, usize, u8)> = usage
+        .iter()
+        .filter(|(pid, _)| !state.requests.iter().any(|r| r.pid == **pid && r.is_open()))
+        .map(|(&pid, &pages)| (pid, pages, *state.priorities.get(&pid).unwrap_or(&DEFAULT_PRIORITY)))
+        .collect();
+
+    match state.policy {
+        ReclaimPolicy::LargestConsumer => {
+            candidates.sort_by(|a, b| b.1.cmp(&a.1));
+        }
+        ReclaimPolicy::LowestPriority => {
+            candidates.sort_by(|a, b| a.2.cmp(&b.2).then(b.1.cmp(&a.1)));
+        }
+    }
+
+    let mut remaining = needed;
+    let mut issued = 0;
+    for (pid, pages, _) in candidates {
+        if remaining == 0 {
+            break;
+        }
+        let ask = remaining.min(pages);
+        let id = state.next_id;
+        state.next_id += 1;
+        let deadline = now.wrapping_add(state.deadline_ticks);
+        state.requests.push(ReclaimRequest {
+            id,
+            pid,
+            pages: ask,
+            deadline,
+            state: RequestState::Pending,
+            baseline: pages,
+        });
+        crate::println!("  [RECLAIM] Asking pid {} to release {} pages (req #{})", pid, ask, id);
+        remaining -= ask;
+        issued += 1;
+    }
+
+    state.stats.issued += issued as u64;
+    issued
+}
+
+/// 分配失败时调用：若没有未完成的请求，则按需发出新请求
+pub fn on_allocation_failure(pages: usize) {
+    let outstanding: usize = STATE.lock().requests.iter()
+        .filter(|r| r.is_open())
+        .map(|r| r.pages)
+        .sum();
+    if outstanding < pages {
+        request_pages(pages - outstanding);
+    }
+}
+
+/// 推进协议：核实履约、处理超时。返回本轮强制撤销的页数
+///
+/// 由空闲循环或定时器周期调用；没有需要核实的请求时只做 O(请求数) 的判断
+pub fn tick() -> usize {
+    let now = crate::arch::timestamp();
+
+    // 锁内只决定是否核实并复制未结束的请求
+    let open: Vec<ReclaimRequest> = {
+        let mut state = STATE.lock();
+        let overdue = state.requests.iter().any(|r| r.is_open() && reached(now, r.deadline));
+        let acked = state.requests.iter().any(|r| r.state == RequestState::Acknowledged);
+        if !overdue && !(acked && reached(now, state.next_check)) {
+            return 0;
+        }
+        state.next_check = now.wrapping_add(state.deadline_ticks / CHECKS_PER_DEADLINE);
+        state.requests.iter().filter(|r| r.is_open()).copied().collect()
+    };
+
+    // 一次扫描得到所有进程的占用量（锁外）
+    let usage = physical::usage_by_pid();
+
+    let mut aborts: Vec<(u64, u32, usize)> = Vec::new();
+    {
+        let mut guard = STATE.lock();
+        let state = &mut *guard;
+        for snap in open {
+            let released = snap.released(usage.get(&snap.pid).copied().unwrap_or(0));
+            // 其他 CPU 的 tick 可能已经结束了这个请求
+            let req = match state.requests.iter_mut().find(|r| r.id == snap.id && r.is_open()) {
+                Some(req) => req,
+                None => continue,
+            };
+            if released >= req.pages {
+                req.state = RequestState::Complied;
+                state.stats.complied += 1;
+            } else if reached(now, req.deadline) {
+                // 在锁内认领，避免两个 CPU 对同一请求各执行一次中止
+                req.state = RequestState::Aborted { revoked: 0 };
+                aborts.push((req.id, req.pid, req.pages - released));
+            }
+        }
+        state.retire_finished();
+    }
+
+    // 中止协议在锁外执行（需要获取能力表锁）
+    let mut total = 0;
+    for (id, pid, shortfall) in aborts {
+        let revoked = force_revoke(pid, shortfall);
+        crate::println!("  [RECLAIM] pid {} missed deadline for req #{}, revoked {} pages",
+                        pid, id, revoked);
+        let mut state = STATE.lock();
+        if let Some(req) = state.find_mut(id) {
+            req.state = RequestState::Aborted { revoked };
+        }
+        state.stats.aborted += 1;
+        state.stats.pages_revoked += revoked as u64;
+        total += revoked;
+    }
+    total
+}
+
+// 强制撤销 pid 的至少 count 个页：先撤销覆盖该帧的能力（大页整页撤销），再放掉分配引用
+//
+// 释放一律经过引用计数：仍被其他持有者引用的帧保留到最后一个持有者离开
+fn force_revoke(pid: u32, count: usize) -> usize {
+    let mut revoked = 0;
+    let mut covered = 0..0;
+    for addr in physical::frames_owned_by(pid, count) {
+        if covered.contains(&addr) {
+            continue;
+        }
+        let (base, pages, held) = force_revoke_frame(ProcessId::new(pid), addr).unwrap_or((addr, 1, false));
+        covered = base..base + pages * crate::arch::PAGE_SIZE;
+        // 能力持有帧引用时撤销已释放了帧，不能再按分配者释放（帧可能已被重新分配）
+        if held || unsafe { physical::free_range(pid, base, pages) }.is_ok() {
+            revoked += pages;
+        }
+    }
+    revoked
+}
+
+// ========== LibOS 侧 ==========
+
+/// 查询发给某进程的未完成请求
+pub fn pending_for(pid: ProcessId) -> Vec<ReclaimRequest> {
+    STATE.lock().requests.iter()
+        .filter(|r| r.pid == pid.as_u32() && r.is_open())
+        .copied()
+        .collect()
+}
+
+/// 某请求的当前状态（含最近结束的请求）；不是发给 pid 的或已移出历史时为 None
+pub fn outcome(pid: ProcessId, id: u64) -> Option<RequestState> {
+    let state = STATE.lock();
+    state.requests.iter().chain(state.history.iter())
+        .find(|r| r.id == id && r.pid == pid.as_u32())
+        .map(|r| r.state)
+}
+
+/// LibOS 确认已处理某请求（内核仍会在 tick 中按占用量核实）
+pub fn acknowledge(pid: ProcessId, id: u64) -> bool {
+    let mut state = STATE.lock();
+    match state.requests.iter_mut().find(|r| r.id == id && r.pid == pid.as_u32()) {
+        Some(req) if req.is_open() => {
+            req.state = RequestState::Acknowledged;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// 回收统计
+pub fn stats() -> ReclaimStats {
+    STATE.lock().stats
+}
@@ -0,0 +1,173 @@
+// src/mm/reserved.rs
+//! 保留的物理内存范围
+//!
+//! 内核映像、引导信息结构、模块/initrd 和 DTB 必须从可分配内存中排除。
+//! 启动代码在解析引导信息时调用 `reserve` 登记，`mm::init` 再用
+//! `subtract` 把每个可用区域切成不含保留范围的片段。
+//! 列表是定长静态数组，因为它在堆可用之前就要被填充。
+
+use spin::Mutex;
+use crate::arch::PAGE_SIZE;
+
+/// 最多登记的保留范围数
+pub const MAX_RESERVED: usize = 64;
+
+/// 保留原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReservedKind {
+    /// 内核映像（__kernel_start..__kernel_end）
+    Kernel,
+    /// 引导信息结构（Multiboot2 info 等）
+    BootInfo,
+    /// 引导模块
+    Module,
+    /// 初始内存盘
+    Initrd,
+    /// 设备树二进制
+    DeviceTree,
+    /// 固件保留（memreserve、零页等）
+    Firmware,
+}
+
+impl ReservedKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ReservedKind::Kernel => "kernel",
+            ReservedKind::BootInfo => "boot-info",
+            ReservedKind::Module => "module",
+            ReservedKind::Initrd => "initrd",
+            ReservedKind::DeviceTree => "dtb",
+            ReservedKind::Firmware => "firmware",
+        }
+    }
+}
+
+/// 一段保留范围（按页对齐）
+#[derive(Debug, Clone, Copy)]
+pub struct ReservedRange {
+    pub base: usize,
+    pub size: usize,
+    pub kind: ReservedKind,
+}
+
+impl ReservedRange {
+    const fn empty() -> Self {
+        Self { base: 0, size: 0, kind: ReservedKind::Firmware }
+    }
+
+    pub fn end(&self) -> usize {
+        self.base + self.size
+    }
+
+    pub fn contains(&self, addr: usize) -> bool {
+        addr >= self.base && addr < self.end()
+    }
+
+    pub fn overlaps(&self, base: usize, size: usize) -> bool {
+        base < self.end() && self.base < base + size
+    }
+}
+
+struct ReservedList {
+    ranges: [ReservedRange; MAX_RESERVED],
+    count: usize,
+}
+
+static RESERVED: Mutex<ReservedList> = Mutex::new(ReservedList {
+    ranges: [ReservedRange::empty(); MAX_RESERVED],
+    count: 0,
+});
+
+/// 登记保留范围（向外扩展到页边界）
+///
+/// 列表已满时返回 false
+pub fn reserve(base: usize, size: usize, kind: ReservedKind) -> bool {
+    if size == 0 {
+        return true;
+    }
+
+    let start = base & !(PAGE_SIZE - 1);
+    let end = (base + size + PAGE_SIZE - 1) & !(PAGE_SIZE - 1);
+
+    let mut list = RESERVED.lock();
+    if list.count >= MAX_RESERVED {
+        crate::println!("  [MM] Reserved list full, dropping 0x{:x}-0x{:x} ({})",
+                        start, end, kind.name());
+        return false;
+    }
+
+    let idx = list.count;
+    list.ranges[idx] = ReservedRange { base: start, size: end - start, kind };
+    list.count += 1;
+    true
+}
+
+/// 地址是否落在保留范围内
+pub fn is_reserved(addr: usize) -> bool {
+    find(addr).is_some()
+}
+
+/// 查找包含该地址的保留范围
+pub fn find(addr: usize) -> Option<ReservedRange> {
+    let list = RESERVED.lock();
+    list.ranges[..list.count].iter().copied().find(|r| r.contains(addr))
+}
+
+/// 范围是否与任一保留范围重叠
+pub fn overlaps(base: usize, size: usize) -> bool {
+    let list = RESERVED.lock();
+    list.ranges[..list.count].iter().any(|r| r.overlaps(base, size))
+}
+
+/// 遍历所有保留范围（按登记顺序）
+pub fn for_each(mut f: impl FnMut(&ReservedRange)) {
+    let (ranges, count) = snapshot();
+    ranges[..count].iter().for_each(|r| f(r));
+}
+
+/// 从 [base, base+size) 中扣除所有保留范围，对剩余的每个片段调用 f
+pub fn subtract(base: usize, size: usize, mut f: impl FnMut(usize, usize)) {
+    let (mut ranges, count) = snapshot();
+    let ranges = &mut ranges[..count];
+    ranges.sort_unstable_by_key(|r| r.base);
+
+    let end = base + size;
+    let mut cursor = base;
+    for r in ranges.iter() {
+        if r.end() <= cursor || r.base >= end {
+            continue;
+        }
+        if r.base > cursor {
+            f(cursor, r.base - cursor);
+        }
+        cursor = r.end();
+        if cursor >= end {
+            return;
+        }
+    }
+
+    if cursor < end {
+        f(cursor, end - cursor);
+    }
+}
+
+/// 保留范围总字节数
+pub fn total_bytes() -> usize {
+    let list = RESERVED.lock();
+    list.ranges[..list.count].iter().map(|r| r.size).sum()
+}
+
+/// 打印保留范围
+pub fn report() {
+    crate::println!("  [MM] Reserved ranges:");
+    for_each(|r| {
+        crate::println!("  [MM]   0x{:016x} - 0x{:016x} {:>10} ({}KB)",
+                        r.base, r.end(), r.kind.name(), r.size / 1024);
+    });
+}
+
+// 复制一份后释放锁，回调里可以安全地再次查询
+fn snapshot() -> ([ReservedRange; MAX_RESERVED], usize) {
+    let list = RESERVED.lock();
+    (list.ranges, list.count)
+}